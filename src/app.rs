@@ -1,15 +1,23 @@
 //! Application state and event handling.
 
 use anyhow::Result;
-use std::time::Instant;
+use chrono::{DateTime, Local};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
 use tracing::{error, info, warn};
 
-use crate::config::Config;
+use crate::config::{Config, MqttStateFormat, SensorBackend, SensorConfig};
+use crate::diagnostics::Diagnostics;
 use crate::error::MqttError;
-use crate::mqtt::{MqttEvent, MqttPublisher};
+use crate::mqtt::{Availability, MotionState, MqttCommand, MqttEntities, MqttEvent, MqttPublisher};
+use crate::occupancy::OccupancyTracker;
+use crate::outbox::{MotionEventMessage, Outbox};
+use crate::remote::RemoteSensor;
 use crate::screen::ScreenManager;
-use crate::sensor::MotionEvent;
+use crate::sensor::{FaultChange, MotionEvent, RadarReading, SensorEvent, SensorFault};
+use crate::stats::MotionStats;
 use crate::time_events::NightModeManager;
+use crate::trace::{ScreenAction, TraceEvent, TraceRecorder};
 
 /// Application state.
 pub struct App {
@@ -18,8 +26,24 @@ pub struct App {
     screen_manager: Option<ScreenManager>,
     night_mode: NightModeManager,
     motion_active: bool,
+    motion_changed_at: DateTime<Local>,
+    motion_started_at: Option<Instant>,
+    sensor_names: Vec<String>,
+    sensor_states: Vec<Option<bool>>,
+    sensor_faults: Vec<Option<SensorFault>>,
     last_state_publish: Option<Instant>,
     last_lifecycle_publish: Option<Instant>,
+    screen_timeout_at: Option<Instant>,
+    virtual_now: Option<Instant>,
+    trace: TraceRecorder,
+    stats: MotionStats,
+    stats_file: Option<PathBuf>,
+    occupancy: Option<OccupancyTracker>,
+    touch_until: Option<Instant>,
+    remote_sensors: Vec<RemoteSensor>,
+    diagnostics: Option<Diagnostics>,
+    outbox: Option<Outbox>,
+    mqtt_connected: bool,
 }
 
 impl App {
@@ -47,27 +71,97 @@ impl App {
             None
         };
 
+        let sensor_names: Vec<String> = config.sensors().iter().map(SensorConfig::name).collect();
+        let sensor_states = vec![Some(false); sensor_names.len()];
+        let sensor_faults = vec![None; sensor_names.len()];
+        let occupancy = config
+            .occupancy
+            .enabled
+            .then(|| OccupancyTracker::new(&config.occupancy));
+        let remote_sensors = config
+            .remote_sensors
+            .iter()
+            .cloned()
+            .map(RemoteSensor::new)
+            .collect();
+        let diagnostics = config
+            .diagnostics
+            .enabled
+            .then(|| Diagnostics::new(&config.diagnostics, &config.mqtt.host, config.mqtt.port));
+
         Self {
             config,
             mqtt_client: None,
             screen_manager,
             night_mode,
             motion_active: false,
+            motion_changed_at: Local::now(),
+            motion_started_at: None,
+            sensor_names,
+            sensor_states,
+            sensor_faults,
             last_state_publish: None,
             last_lifecycle_publish: None,
+            screen_timeout_at: None,
+            virtual_now: None,
+            trace: TraceRecorder::default(),
+            stats: MotionStats::default(),
+            stats_file: None,
+            occupancy,
+            touch_until: None,
+            remote_sensors,
+            diagnostics,
+            outbox: None,
+            mqtt_connected: false,
         }
     }
 
-    /// Initialize the current motion state from GPIO.
-    pub async fn initialize_motion_state(&mut self, motion_detected: bool) {
-        self.set_motion_state(motion_detected, "startup");
+    /// Continue from previously saved statistics, saving updates to `state_file`.
+    pub fn set_stats(&mut self, stats: MotionStats, state_file: PathBuf) {
+        self.stats = stats;
+        self.stats_file = Some(state_file);
+    }
+
+    /// Publish motion transitions as event messages, queued in `outbox`.
+    pub fn set_outbox(&mut self, outbox: Outbox) {
+        self.outbox = Some(outbox);
+    }
+
+    /// Record motion, screen and MQTT actions to `trace`.
+    pub fn set_trace(&mut self, trace: TraceRecorder) {
+        self.trace = trace;
+    }
+
+    /// Use `now` instead of the system clock for timers (trace replay).
+    pub const fn set_virtual_now(&mut self, now: Instant) {
+        self.virtual_now = Some(now);
+    }
+
+    /// Initialize the current motion state from the GPIO level of each sensor.
+    ///
+    /// `None` marks a sensor that is still warming up; its first real state
+    /// arrives later as [`SensorEvent::Ready`].
+    pub async fn initialize_motion_state(&mut self, sensor_states: &[Option<bool>]) {
+        for (state, initial) in self.sensor_states.iter_mut().zip(sensor_states) {
+            *state = *initial;
+        }
+
+        if self.warming_up() {
+            info!("PIR sensors warming up; motion state reported as initializing");
+            return;
+        }
+
+        let motion_detected = self.any_motion();
+        if self.set_motion_state(motion_detected, "startup") {
+            self.update_occupancy(motion_detected).await;
+        }
         info!(
             gpio_active = motion_detected,
             state = Self::motion_payload(motion_detected),
             "Initial PIR state loaded"
         );
 
-        if motion_detected {
+        if motion_detected && !self.occupancy_drives_screen() {
             self.wake_screen().await;
         }
     }
@@ -79,6 +173,119 @@ impl App {
         self.motion_active
     }
 
+    /// Whether any sensor is still warming up.
+    #[must_use]
+    pub fn warming_up(&self) -> bool {
+        self.sensor_states.iter().any(Option::is_none)
+    }
+
+    /// Entities to announce alongside the combined motion entity.
+    #[must_use]
+    pub fn mqtt_entities(&self) -> MqttEntities {
+        MqttEntities {
+            sensors: self.per_sensor_entities().to_vec(),
+            problem: self.config.mqtt.problem_sensor,
+            stats: self.config.stats.enabled,
+            occupancy: self.occupancy.is_some(),
+            touch: self.config.input.publish,
+            radars: self
+                .config
+                .sensors()
+                .iter()
+                .filter(|sensor| sensor.backend == SensorBackend::Ld2410)
+                .map(SensorConfig::name)
+                .collect(),
+            screen: self.screen_manager.is_some(),
+            diagnostics: self.diagnostics.is_some(),
+        }
+    }
+
+    /// Topics to subscribe to for remote sensors.
+    #[must_use]
+    pub fn mqtt_subscriptions(&self) -> Vec<String> {
+        let mut topics: Vec<String> = Vec::new();
+        for remote in &self.remote_sensors {
+            if !topics.iter().any(|topic| topic == remote.topic()) {
+                topics.push(remote.topic().to_string());
+            }
+        }
+        topics
+    }
+
+    /// Names of the sensors that need their own MQTT entities.
+    ///
+    /// Empty when there is only one sensor, which is covered by the combined entity.
+    #[must_use]
+    pub fn per_sensor_entities(&self) -> &[String] {
+        if self.sensor_names.len() > 1 {
+            &self.sensor_names
+        } else {
+            &[]
+        }
+    }
+
+    /// When the screen motion timeout, the occupancy hold or the touch
+    /// activity ends, if running.
+    #[must_use]
+    pub fn next_deadline(&self) -> Option<Instant> {
+        [
+            self.screen_timeout_at,
+            self.occupancy
+                .as_ref()
+                .and_then(OccupancyTracker::next_deadline),
+            self.touch_until,
+        ]
+        .into_iter()
+        .chain(self.remote_sensors.iter().map(RemoteSensor::next_deadline))
+        .flatten()
+        .min()
+    }
+
+    /// End touch activity and remote motion holds, mark the room vacant and
+    /// dim the screen once their timers have passed.
+    pub async fn handle_deadline(&mut self) {
+        let now = self.now();
+        let mut remote_changed = false;
+        for index in 0..self.remote_sensors.len() {
+            if let Some(active) = self.remote_sensors[index].poll(now) {
+                self.remote_sensor_changed(index, active, "hold expired");
+                remote_changed = true;
+            }
+        }
+        if remote_changed {
+            if let Err(e) = self.update_combined_motion().await {
+                warn!(error = %e, "Failed to handle remote motion clearing");
+            }
+        }
+
+        if self.touch_until.is_some_and(|until| until <= now) {
+            self.touch_until = None;
+            info!("Touch activity ended");
+            self.record(TraceEvent::Touch { active: false });
+            self.publish_touch_state("input_idle").await;
+        }
+
+        if let Some(occupied) = self
+            .occupancy
+            .as_mut()
+            .and_then(|occupancy| occupancy.poll(now))
+        {
+            self.occupancy_changed(occupied).await;
+        }
+
+        match self.screen_timeout_at {
+            Some(at) if at <= self.now() => {}
+            _ => return,
+        }
+
+        self.screen_timeout_at = None;
+        info!(
+            motion_timeout_secs = self.config.screen.motion_timeout_secs,
+            "Screen motion timeout fired"
+        );
+        self.adjust_screen_after_clear().await;
+    }
+
     /// Last successful state publish enqueue time.
     #[must_use]
     pub const fn last_state_publish(&self) -> Option<Instant> {
@@ -91,15 +298,136 @@ impl App {
         self.last_lifecycle_publish
     }
 
-    /// Publish the currently known motion state to MQTT.
+    /// Publish the currently known motion state of every sensor to MQTT.
     pub async fn publish_current_motion_state(
         &mut self,
         reason: &'static str,
+    ) -> Result<(), MqttError> {
+        if self.warming_up() {
+            info!(
+                reason,
+                "PIR sensors warming up; motion state reported as initializing"
+            );
+            // Only the JSON payload can say so; plain ON/OFF would be a guess
+            if self.config.mqtt.state_format == MqttStateFormat::Json {
+                return self.publish_combined_motion_state(reason).await;
+            }
+            return Ok(());
+        }
+
+        if self.config.mqtt.enabled {
+            if let Some(client) = self.mqtt_client.as_ref() {
+                let states = self.sensor_states.iter().flatten();
+                for (sensor, state) in self.per_sensor_entities().iter().zip(states) {
+                    let result = client.publish_sensor_motion(sensor, *state).await;
+                    self.trace.record_at(
+                        self.now(),
+                        TraceEvent::Mqtt {
+                            entity: sensor.clone(),
+                            state: *state,
+                            ok: result.is_ok(),
+                        },
+                    );
+                    if let Err(e) = result {
+                        warn!(
+                            reason,
+                            sensor = %sensor,
+                            state = Self::motion_payload(*state),
+                            error = %e,
+                            "MQTT sensor motion state publish failed"
+                        );
+                    }
+                }
+            }
+        }
+
+        let result = self.publish_combined_motion_state(reason).await;
+        self.publish_occupancy_state(reason).await;
+        self.publish_touch_state(reason).await;
+        self.drain_outbox().await;
+        result
+    }
+
+    /// Publish queued motion events, oldest first, while MQTT is connected.
+    ///
+    /// Stops at the first failure; the rest stay queued for the next attempt.
+    async fn drain_outbox(&mut self) {
+        if !self.mqtt_connected {
+            return;
+        }
+        let (Some(client), Some(outbox)) = (self.mqtt_client.as_ref(), self.outbox.as_mut()) else {
+            return;
+        };
+
+        let queued = outbox.len();
+        while let Some(event) = outbox.front() {
+            if let Err(e) = client.publish_motion_event(event).await {
+                warn!(error = %e, remaining = outbox.len(), "Motion event publish failed; kept in outbox");
+                return;
+            }
+            if let Err(e) = outbox.pop_front() {
+                warn!(error = %e, "Failed to save motion event outbox");
+            }
+        }
+        if queued > 1 {
+            info!(events = queued, "Motion event outbox drained");
+        }
+    }
+
+    /// Publish the touch activity state to MQTT.
+    async fn publish_touch_state(&self, reason: &'static str) {
+        if !self.config.mqtt.enabled || !self.config.input.publish {
+            return;
+        }
+        let Some(client) = self.mqtt_client.as_ref() else {
+            return;
+        };
+
+        let active = self.touch_until.is_some();
+        if let Err(e) = client.publish_touch(active).await {
+            warn!(
+                reason,
+                state = Self::motion_payload(active),
+                error = %e,
+                "Failed to publish touch activity state"
+            );
+        }
+    }
+
+    /// Publish the occupancy state to MQTT.
+    async fn publish_occupancy_state(&self, reason: &'static str) {
+        if !self.config.mqtt.enabled {
+            return;
+        }
+        let (Some(client), Some(occupancy)) = (self.mqtt_client.as_ref(), self.occupancy.as_ref())
+        else {
+            return;
+        };
+
+        let occupied = occupancy.occupied();
+        let result = client
+            .publish_occupancy(occupied, occupancy.confidence(self.now()))
+            .await;
+        if let Err(e) = result {
+            warn!(
+                reason,
+                state = Self::motion_payload(occupied),
+                error = %e,
+                "Failed to publish occupancy state"
+            );
+        }
+    }
+
+    /// Publish the combined "any motion" state to MQTT.
+    async fn publish_combined_motion_state(
+        &mut self,
+        reason: &'static str,
     ) -> Result<(), MqttError> {
         if !self.config.mqtt.enabled {
             return Ok(());
         }
 
+        let motion_state = self.motion_state();
         let Some(client) = self.mqtt_client.as_ref() else {
             warn!(
                 reason,
@@ -110,7 +438,16 @@ impl App {
         };
 
         let state = self.motion_active;
-        match client.publish_motion(state).await {
+        let result = client.publish_motion(&motion_state).await;
+        self.trace.record_at(
+            self.now(),
+            TraceEvent::Mqtt {
+                entity: "motion".to_string(),
+                state,
+                ok: result.is_ok(),
+            },
+        );
+        match result {
             Ok(()) => {
                 self.last_state_publish = Some(Instant::now());
                 info!(
@@ -133,131 +470,761 @@ impl App {
         }
     }
 
-    /// Handle a motion event.
+    /// Publish the motion statistics to MQTT.
+    pub async fn publish_stats(&mut self, reason: &'static str) {
+        if !self.config.mqtt.enabled || !self.config.stats.enabled {
+            return;
+        }
+        let Some(client) = self.mqtt_client.as_ref() else {
+            return;
+        };
+
+        let snapshot = self.stats.snapshot(Local::now(), self.now());
+        if let Err(e) = client.publish_stats(&snapshot).await {
+            warn!(reason, error = %e, "Failed to publish motion statistics");
+        }
+    }
+
+    /// Publish host diagnostics to MQTT.
+    pub async fn publish_diagnostics(&self, reason: &'static str) {
+        if !self.config.mqtt.enabled {
+            return;
+        }
+        let (Some(client), Some(diagnostics)) =
+            (self.mqtt_client.as_ref(), self.diagnostics.as_ref())
+        else {
+            return;
+        };
+
+        let snapshot = diagnostics.collect().await;
+        if let Err(e) = client.publish_diagnostics(&snapshot).await {
+            warn!(reason, error = %e, "Failed to publish diagnostics");
+        }
+    }
+
+    /// Handle an event from one sensor, updating the combined state.
+    pub async fn handle_sensor_event(&mut self, event: SensorEvent) -> Result<()> {
+        match event {
+            SensorEvent::Motion { sensor, event } => self.handle_sensor_motion(sensor, event).await,
+            SensorEvent::Ready {
+                sensor,
+                motion_detected,
+            } => self.handle_sensor_ready(sensor, motion_detected).await,
+            SensorEvent::Fault { sensor, change } => {
+                self.handle_sensor_fault(sensor, change).await;
+                Ok(())
+            }
+            SensorEvent::Radar { sensor, reading } => {
+                self.publish_radar(sensor, &reading).await;
+                Ok(())
+            }
+        }
+    }
+
+    /// Publish a radar sensor's target readings to MQTT.
+    async fn publish_radar(&self, sensor: usize, reading: &RadarReading) {
+        if !self.config.mqtt.enabled {
+            return;
+        }
+        let (Some(client), Some(name)) = (self.mqtt_client.as_ref(), self.sensor_names.get(sensor))
+        else {
+            return;
+        };
+
+        if let Err(e) = client.publish_radar(name, reading).await {
+            warn!(sensor = %name, error = %e, "Failed to publish radar readings");
+        }
+    }
+
+    async fn handle_sensor_motion(&mut self, sensor: usize, event: MotionEvent) -> Result<()> {
+        let detected = event == MotionEvent::Detected;
+        let Some(state) = self.sensor_states.get_mut(sensor) else {
+            warn!(sensor, "Motion event from unknown sensor ignored");
+            return Ok(());
+        };
+        *state = Some(detected);
+
+        if self.warming_up() {
+            info!(
+                sensor,
+                "Motion event held until all sensors finish warming up"
+            );
+            return Ok(());
+        }
+
+        if let (Some(client), Some(name)) = (
+            self.mqtt_client.as_ref(),
+            self.per_sensor_entities().get(sensor),
+        ) {
+            info!(sensor = %name, state = Self::motion_payload(detected), "Sensor motion event");
+            let result = client.publish_sensor_motion(name, detected).await;
+            self.trace.record_at(
+                self.now(),
+                TraceEvent::Mqtt {
+                    entity: name.clone(),
+                    state: detected,
+                    ok: result.is_ok(),
+                },
+            );
+            if let Err(e) = result {
+                warn!(sensor = %name, error = %e, "Failed to publish sensor motion state");
+            }
+        }
+
+        // A lone sensor passes every edge through; combined state only reports changes
+        let any_motion = self.any_motion();
+        let lone_sensor = self.sensor_states.len() == 1 && self.remote_sensors.is_empty();
+        if lone_sensor || any_motion != self.motion_active {
+            let combined = if any_motion {
+                MotionEvent::Detected
+            } else {
+                MotionEvent::Cleared
+            };
+            self.handle_motion(combined).await?;
+        } else {
+            // The sensors detecting motion changed
+            self.republish_json_motion_state("sensor_motion").await;
+        }
+
+        Ok(())
+    }
+
+    async fn handle_sensor_ready(&mut self, sensor: usize, motion_detected: bool) -> Result<()> {
+        let Some(state) = self.sensor_states.get_mut(sensor) else {
+            warn!(sensor, "Ready event from unknown sensor ignored");
+            return Ok(());
+        };
+        *state = Some(motion_detected);
+        info!(
+            sensor,
+            state = Self::motion_payload(motion_detected),
+            "PIR sensor settled after warm-up"
+        );
+        self.record(TraceEvent::SensorMotion {
+            sensor,
+            detected: motion_detected,
+        });
+
+        if self.warming_up() {
+            return Ok(());
+        }
+
+        let motion_detected = self.any_motion();
+        if self.set_motion_state(motion_detected, "warmup_complete") {
+            self.update_occupancy(motion_detected).await;
+        }
+
+        if let Some(ref client) = self.mqtt_client {
+            if let Err(e) = client.publish_availability(Availability::Online).await {
+                error!(error = %e, "Failed to publish availability");
+            } else {
+                self.last_lifecycle_publish = Some(Instant::now());
+            }
+        }
+
+        if let Err(e) = self.publish_current_motion_state("warmup_complete").await {
+            warn!(error = %e, "Failed to publish motion state after warm-up");
+        }
+
+        if motion_detected && !self.occupancy_drives_screen() {
+            self.wake_screen().await;
+        }
+
+        Ok(())
+    }
+
+    async fn handle_sensor_fault(&mut self, sensor: usize, change: FaultChange) {
+        let Some(fault) = self.sensor_faults.get_mut(sensor) else {
+            warn!(sensor, "Fault event from unknown sensor ignored");
+            return;
+        };
+        let name = &self.sensor_names[sensor];
+        let (current, recorded, active) = match change {
+            FaultChange::Raised(raised) => {
+                warn!(
+                    sensor = %name,
+                    fault = raised.as_str(),
+                    "PIR sensor fault; check the sensor and its wiring"
+                );
+                (Some(raised), raised, true)
+            }
+            FaultChange::Cleared(cleared) => {
+                info!(sensor = %name, fault = cleared.as_str(), "PIR sensor fault cleared");
+                (None, cleared, false)
+            }
+        };
+        *fault = current;
+        self.record(TraceEvent::Fault {
+            sensor,
+            fault: recorded,
+            active,
+        });
+
+        self.publish_problem_state("sensor_fault").await;
+    }
+
+    /// Publish which sensors are faulty to the problem entity.
+    async fn publish_problem_state(&self, reason: &'static str) {
+        if !self.config.mqtt.enabled || !self.config.mqtt.problem_sensor {
+            return;
+        }
+        let Some(client) = self.mqtt_client.as_ref() else {
+            return;
+        };
+
+        let faults: Vec<(String, SensorFault)> = self
+            .sensor_names
+            .iter()
+            .zip(&self.sensor_faults)
+            .filter_map(|(name, fault)| fault.map(|fault| (name.clone(), fault)))
+            .collect();
+        if let Err(e) = client.publish_problem(&faults).await {
+            warn!(reason, error = %e, "Failed to publish sensor problem state");
+        }
+    }
+
+    /// Handle a motion event for the combined "any motion" state.
     pub async fn handle_motion(&mut self, event: MotionEvent) -> Result<()> {
+        let reason = match event {
+            MotionEvent::Detected => "pir_detected",
+            MotionEvent::Cleared => "pir_cleared",
+        };
+        self.apply_motion(event, reason).await
+    }
+
+    /// Apply a combined motion event; `reason` says what caused it.
+    async fn apply_motion(&mut self, event: MotionEvent, reason: &'static str) -> Result<()> {
         match event {
             MotionEvent::Detected => {
-                let changed = self.set_motion_state(true, "pir_detected");
+                let changed = self.set_motion_state(true, reason);
                 info!(state = "detected", changed, "Motion event");
+                self.record(TraceEvent::Motion { detected: true });
 
-                if let Err(e) = self.publish_current_motion_state("pir_detected").await {
+                if let Err(e) = self.publish_combined_motion_state(reason).await {
                     warn!(error = %e, "Failed to publish detected motion state");
                 }
-
                 if changed {
-                    self.wake_screen().await;
+                    self.publish_stats(reason).await;
+                    self.update_occupancy(true).await;
+                }
+
+                if !self.occupancy_drives_screen() {
+                    self.screen_on_activity(changed).await;
                 }
             }
             MotionEvent::Cleared => {
-                let changed = self.set_motion_state(false, "pir_cleared");
+                let changed = self.set_motion_state(false, reason);
                 info!(state = "cleared", changed, "Motion event");
+                self.record(TraceEvent::Motion { detected: false });
 
-                if let Err(e) = self.publish_current_motion_state("pir_cleared").await {
+                if let Err(e) = self.publish_combined_motion_state(reason).await {
                     warn!(error = %e, "Failed to publish cleared motion state");
                 }
-
                 if changed {
-                    self.adjust_screen_after_clear().await;
+                    self.publish_stats(reason).await;
+                    self.update_occupancy(false).await;
+                    if !self.occupancy_drives_screen() {
+                        self.adjust_screen_after_clear().await;
+                    }
                 }
             }
         }
 
+        self.drain_outbox().await;
         Ok(())
     }
 
+    /// Handle touchscreen or keyboard activity.
+    ///
+    /// Wakes the screen and restarts its timeout unless motion is already
+    /// keeping it bright, and holds the touch activity entity on until
+    /// `input.idle_secs` pass without input.
+    pub async fn handle_input_activity(&mut self) {
+        let now = self.now();
+        let started = self.touch_until.is_none();
+        self.touch_until = Some(now + Duration::from_secs(self.config.input.idle_secs));
+        if started {
+            info!("Touch activity started");
+            self.record(TraceEvent::Touch { active: true });
+            self.publish_touch_state("input_activity").await;
+        }
+
+        if self.screen_held_bright() {
+            return;
+        }
+        if self.screen_timeout_at.is_some() {
+            // Still bright; push the pending timeout back
+            self.screen_timeout_at =
+                Some(now + Duration::from_secs(self.config.screen.motion_timeout_secs));
+        } else {
+            self.wake_screen().await;
+            self.start_screen_timeout().await;
+        }
+    }
+
     /// Handle MQTT connection events.
     pub async fn handle_mqtt_event(&mut self, event: MqttEvent) -> Result<()> {
         match event {
             MqttEvent::Connected => {
                 info!("MQTT connected, republishing discovery, availability, and current state");
-                if let Some(ref client) = self.mqtt_client {
-                    let client_id = self.config.client_id();
-                    if let Err(e) = client.publish_discovery(&client_id).await {
-                        error!(error = %e, "Failed to publish discovery");
-                    } else {
-                        self.last_lifecycle_publish = Some(Instant::now());
-                    }
-                    if let Err(e) = client.publish_availability(true).await {
-                        error!(error = %e, "Failed to publish availability");
-                    } else {
-                        self.last_lifecycle_publish = Some(Instant::now());
-                    }
-                } else if self.config.mqtt.enabled {
-                    warn!("MQTT connected event received but client is unavailable");
-                }
-
-                if let Err(e) = self.publish_current_motion_state("mqtt_connected").await {
-                    error!(error = %e, "Failed to publish current state after MQTT connect");
-                }
+                self.mqtt_connected = true;
+                self.republish_all("mqtt_connected").await;
+            }
+            MqttEvent::HomeAssistantOnline => {
+                info!("Home Assistant online, republishing discovery, availability, and current state");
+                self.republish_all("ha_online").await;
             }
             MqttEvent::Disconnected => {
                 warn!("MQTT disconnected, will reconnect automatically");
+                self.mqtt_connected = false;
             }
             MqttEvent::Error(msg) => {
                 error!(message = %msg, "MQTT error");
             }
+            MqttEvent::Message { topic, payload } => {
+                self.handle_remote_message(&topic, &payload).await?;
+            }
+            MqttEvent::Command(command) => self.handle_command(command).await?,
         }
         Ok(())
     }
 
-    /// Gracefully disconnect from MQTT.
-    pub async fn shutdown(&self) {
+    /// Publish discovery, availability and every state, for a fresh
+    /// connection or a restarted Home Assistant.
+    async fn republish_all(&mut self, reason: &'static str) {
         if let Some(ref client) = self.mqtt_client {
-            if let Err(e) = client.disconnect().await {
-                warn!(error = %e, "Error disconnecting from MQTT");
+            let client_id = self.config.client_id();
+            if let Err(e) = client.publish_discovery(&client_id).await {
+                error!(reason, error = %e, "Failed to publish discovery");
+            } else {
+                self.last_lifecycle_publish = Some(Instant::now());
             }
+            // Entities stay unavailable until the sensors settle
+            let availability = if self.warming_up() {
+                Availability::Offline
+            } else {
+                Availability::Online
+            };
+            if let Err(e) = client.publish_availability(availability).await {
+                error!(reason, error = %e, "Failed to publish availability");
+            } else {
+                self.last_lifecycle_publish = Some(Instant::now());
+            }
+        } else if self.config.mqtt.enabled {
+            warn!(reason, "MQTT client unavailable; nothing republished");
         }
+
+        if let Err(e) = self.publish_current_motion_state(reason).await {
+            error!(reason, error = %e, "Failed to republish current state");
+        }
+        self.publish_problem_state(reason).await;
+        self.publish_stats(reason).await;
+        self.publish_diagnostics(reason).await;
+        self.publish_screen_state(reason).await;
+        self.publish_night_mode_state(reason).await;
     }
 
-    const fn motion_payload(motion_detected: bool) -> &'static str {
-        if motion_detected {
-            "ON"
-        } else {
-            "OFF"
+    /// Carry out a command from Home Assistant and echo the new state.
+    async fn handle_command(&mut self, command: MqttCommand) -> Result<()> {
+        info!(?command, "Home Assistant command received");
+        match command {
+            MqttCommand::Screen { on, brightness } => self.command_screen(on, brightness).await,
+            MqttCommand::NightMode(mode) => {
+                self.night_mode.set_override(mode);
+                self.publish_night_mode_state("night_mode_command").await;
+                // Apply the new mode now if the screen is already dimmed or off
+                if self.screen_manager.is_some()
+                    && !self.screen_held_bright()
+                    && self.screen_timeout_at.is_none()
+                {
+                    // Republishes the JSON motion state with the screen
+                    self.adjust_screen_after_clear().await;
+                } else {
+                    self.republish_json_motion_state("night_mode_command").await;
+                }
+            }
+            MqttCommand::SimulateMotion => {
+                if self.motion_active {
+                    info!("Simulated motion ignored; motion already active");
+                    return Ok(());
+                }
+                self.handle_motion(MotionEvent::Detected).await?;
+                self.handle_motion(MotionEvent::Cleared).await?;
+            }
         }
+        Ok(())
     }
 
-    fn set_motion_state(&mut self, motion_detected: bool, reason: &'static str) -> bool {
-        let previous = self.motion_active;
-        self.motion_active = motion_detected;
-        let changed = previous ^ motion_detected;
+    /// Turn the screen on (at `brightness` on Home Assistant's scale, or
+    /// bright) or off on request.
+    ///
+    /// A screen turned on dims again after the motion timeout unless motion
+    /// holds it bright; one turned off stays off until the next motion.
+    async fn command_screen(&mut self, on: bool, brightness: Option<u8>) {
+        let now = self.now();
+        let Some(manager) = self.screen_manager.as_mut() else {
+            warn!("Screen command ignored; screen control is disabled");
+            return;
+        };
 
-        if changed {
-            info!(
-                reason,
-                previous_state = Self::motion_payload(previous),
-                new_state = Self::motion_payload(motion_detected),
-                "Motion state transition"
-            );
-            true
+        let (action, result) = if on {
+            let level = brightness.map_or(self.config.screen.bright_brightness, |scaled| {
+                manager.level_for_scaled(scaled)
+            });
+            (ScreenAction::Bright, manager.set_level(level).await)
         } else {
-            info!(
-                reason,
-                state = Self::motion_payload(motion_detected),
-                "Motion state unchanged"
-            );
-            false
+            (ScreenAction::Off, manager.turn_off().await)
+        };
+        self.trace.record_at(now, TraceEvent::Screen { action });
+        if let Err(e) = result {
+            warn!(error = %e, "Failed to apply screen command");
+        }
+
+        self.screen_timeout_at = None;
+        if on && !self.screen_held_bright() {
+            self.start_screen_timeout().await;
         }
+        self.screen_changed("screen_command").await;
     }
 
-    async fn wake_screen(&mut self) {
+    /// Publish the screen light state to MQTT.
+    async fn publish_screen_state(&self, reason: &'static str) {
+        if !self.config.mqtt.enabled {
+            return;
+        }
+        let (Some(client), Some(manager)) =
+            (self.mqtt_client.as_ref(), self.screen_manager.as_ref())
+        else {
+            return;
+        };
+
+        let brightness = manager.scaled_brightness();
+        if let Err(e) = client.publish_screen(brightness > 0, brightness).await {
+            warn!(reason, error = %e, "Failed to publish screen state");
+        }
+    }
+
+    /// Publish a screen change, with the JSON motion state carrying the new
+    /// brightness.
+    async fn screen_changed(&mut self, reason: &'static str) {
+        self.publish_screen_state(reason).await;
+        self.republish_json_motion_state(reason).await;
+    }
+
+    /// Republish the JSON motion state after its context changed.
+    async fn republish_json_motion_state(&mut self, reason: &'static str) {
+        if self.config.mqtt.state_format != MqttStateFormat::Json || self.warming_up() {
+            return;
+        }
+        if let Err(e) = self.publish_combined_motion_state(reason).await {
+            warn!(reason, error = %e, "Failed to republish JSON motion state");
+        }
+    }
+
+    /// Publish the night mode override to MQTT.
+    async fn publish_night_mode_state(&self, reason: &'static str) {
+        if !self.config.mqtt.enabled || !self.config.mqtt.commands {
+            return;
+        }
+        let Some(client) = self.mqtt_client.as_ref() else {
+            return;
+        };
+
+        let mode = self.night_mode.override_mode();
+        if let Err(e) = client.publish_night_mode(mode).await {
+            warn!(reason, mode = mode.as_str(), error = %e, "Failed to publish night mode");
+        }
+    }
+
+    /// Match a message against the remote sensors, merging their motion into
+    /// the combined state.
+    async fn handle_remote_message(&mut self, topic: &str, payload: &[u8]) -> Result<()> {
+        let now = self.now();
+        let mut changed = false;
+        for index in 0..self.remote_sensors.len() {
+            if let Some(active) = self.remote_sensors[index].handle_message(topic, payload, now) {
+                self.remote_sensor_changed(index, active, topic);
+                changed = true;
+            }
+        }
+
+        if changed {
+            self.update_combined_motion().await?;
+        }
+        Ok(())
+    }
+
+    fn remote_sensor_changed(&self, index: usize, active: bool, cause: &str) {
+        let name = self.remote_sensors[index].name();
+        info!(
+            remote = %name,
+            state = Self::motion_payload(active),
+            cause,
+            "Remote sensor motion event"
+        );
+        self.record(TraceEvent::Remote { name, active });
+    }
+
+    /// Report a change in the combined local and remote motion state.
+    async fn update_combined_motion(&mut self) -> Result<()> {
+        if self.warming_up() {
+            return Ok(());
+        }
+        let any_motion = self.any_motion();
+        if any_motion == self.motion_active {
+            self.republish_json_motion_state("remote_motion").await;
+            return Ok(());
+        }
+        let (combined, reason) = if any_motion {
+            (MotionEvent::Detected, "remote_detected")
+        } else {
+            (MotionEvent::Cleared, "remote_cleared")
+        };
+        self.apply_motion(combined, reason).await
+    }
+
+    /// Gracefully disconnect from MQTT.
+    pub async fn shutdown(&self) {
+        if let Some(ref client) = self.mqtt_client {
+            if let Err(e) = client.disconnect().await {
+                warn!(error = %e, "Error disconnecting from MQTT");
+            }
+        }
+    }
+
+    fn now(&self) -> Instant {
+        self.virtual_now.unwrap_or_else(Instant::now)
+    }
+
+    fn record(&self, event: TraceEvent) {
+        self.trace.record_at(self.now(), event);
+    }
+
+    fn any_motion(&self) -> bool {
+        self.sensor_states.contains(&Some(true))
+            || self.remote_sensors.iter().any(RemoteSensor::active)
+    }
+
+    const fn motion_payload(motion_detected: bool) -> &'static str {
+        if motion_detected {
+            "ON"
+        } else {
+            "OFF"
+        }
+    }
+
+    fn set_motion_state(&mut self, motion_detected: bool, reason: &'static str) -> bool {
+        let previous = self.motion_active;
+        self.motion_active = motion_detected;
+        let changed = previous ^ motion_detected;
+
+        if changed {
+            let now = self.now();
+            let duration = if motion_detected {
+                self.motion_started_at = Some(now);
+                None
+            } else {
+                self.motion_started_at
+                    .take()
+                    .map(|started| now.saturating_duration_since(started))
+            };
+            self.motion_changed_at = Local::now();
+            info!(
+                reason,
+                previous_state = Self::motion_payload(previous),
+                new_state = Self::motion_payload(motion_detected),
+                "Motion state transition"
+            );
+            self.update_stats(motion_detected);
+            self.queue_motion_event(motion_detected, reason, duration);
+            true
+        } else {
+            info!(
+                reason,
+                state = Self::motion_payload(motion_detected),
+                "Motion state unchanged"
+            );
+            false
+        }
+    }
+
+    /// The combined motion state with its context, for the state topic.
+    fn motion_state(&mut self) -> MotionState {
+        MotionState {
+            motion: self.motion_active,
+            last_changed: self.motion_changed_at,
+            sensors: self.active_sensors(),
+            night_mode: self.night_mode.is_night_mode(),
+            brightness: self
+                .screen_manager
+                .as_ref()
+                .map(ScreenManager::scaled_brightness),
+            initializing: self.warming_up(),
+        }
+    }
+
+    /// Names of the local and remote sensors detecting motion.
+    fn active_sensors(&self) -> Vec<String> {
+        let local = self
+            .sensor_names
+            .iter()
+            .zip(&self.sensor_states)
+            .filter(|(_, state)| **state == Some(true))
+            .map(|(name, _)| name.clone());
+        let remote = self
+            .remote_sensors
+            .iter()
+            .filter(|remote| remote.active())
+            .map(RemoteSensor::name);
+        local.chain(remote).collect()
+    }
+
+    /// Queue a motion transition for the event topic; `duration` is how
+    /// long the motion that just ended lasted.
+    fn queue_motion_event(
+        &mut self,
+        motion_detected: bool,
+        reason: &'static str,
+        duration: Option<Duration>,
+    ) {
+        let Some(outbox) = self.outbox.as_mut() else {
+            return;
+        };
+        let event = MotionEventMessage {
+            motion: motion_detected,
+            timestamp: Local::now(),
+            sequence: outbox.next_sequence(),
+            reason: reason.to_string(),
+            duration_secs: duration.map(|duration| (duration.as_secs_f64() * 10.0).round() / 10.0),
+        };
+        if let Err(e) = outbox.push(event) {
+            warn!(error = %e, "Failed to save motion event outbox");
+        }
+    }
+
+    fn update_stats(&mut self, motion_detected: bool) {
+        if !motion_detected {
+            self.stats.motion_stopped(self.now());
+            return;
+        }
+
+        self.stats.motion_started(Local::now(), self.now());
+        if let Some(path) = &self.stats_file {
+            if let Err(e) = self.stats.save(path) {
+                warn!(path = %path.display(), error = %e, "Failed to save motion statistics");
+            }
+        }
+    }
+
+    const fn occupancy_drives_screen(&self) -> bool {
+        self.occupancy.is_some() && self.config.occupancy.drive_screen
+    }
+
+    /// Whether motion (or occupancy, when it drives the screen) keeps the
+    /// screen bright without a timeout.
+    fn screen_held_bright(&self) -> bool {
+        if self.occupancy_drives_screen() {
+            self.occupancy
+                .as_ref()
+                .is_some_and(OccupancyTracker::occupied)
+        } else {
+            self.motion_active
+        }
+    }
+
+    /// Feed a change in the combined motion state to the occupancy tracker.
+    async fn update_occupancy(&mut self, motion_detected: bool) {
+        let now = self.now();
+        if let Some(occupied) = self
+            .occupancy
+            .as_mut()
+            .and_then(|occupancy| occupancy.motion_changed(motion_detected, now))
+        {
+            self.occupancy_changed(occupied).await;
+        }
+    }
+
+    async fn occupancy_changed(&mut self, occupied: bool) {
+        info!(
+            state = Self::motion_payload(occupied),
+            "Occupancy state transition"
+        );
+        self.record(TraceEvent::Occupancy { occupied });
+        self.publish_occupancy_state("occupancy_changed").await;
+
+        if self.occupancy_drives_screen() {
+            if occupied {
+                self.screen_on_activity(true).await;
+            } else {
+                self.adjust_screen_after_clear().await;
+            }
+        }
+    }
+
+    /// Keep the screen bright for new activity, waking it if it changed.
+    async fn screen_on_activity(&mut self, changed: bool) {
+        // The screen is still bright while the timeout is pending
+        let timeout_cancelled = self.screen_timeout_at.take().is_some();
+        if timeout_cancelled {
+            info!("Screen motion timeout cancelled");
+        }
+        if changed && !timeout_cancelled {
+            self.wake_screen().await;
+        }
+    }
+
+    /// Dim the screen once `motion_timeout_secs` passes without motion.
+    async fn start_screen_timeout(&mut self) {
+        if self.screen_manager.is_none() {
+            return;
+        }
+
+        let timeout = Duration::from_secs(self.config.screen.motion_timeout_secs);
+        if timeout.is_zero() {
+            self.adjust_screen_after_clear().await;
+            return;
+        }
+
+        self.screen_timeout_at = Some(self.now() + timeout);
+        info!(
+            motion_timeout_secs = self.config.screen.motion_timeout_secs,
+            "Screen motion timeout started"
+        );
+    }
+
+    async fn wake_screen(&mut self) {
+        let now = self.now();
         if let Some(ref mut manager) = self.screen_manager {
+            self.trace.record_at(
+                now,
+                TraceEvent::Screen {
+                    action: ScreenAction::Bright,
+                },
+            );
             if let Err(e) = manager.on_motion().await {
                 warn!(error = %e, "Failed to wake screen");
             }
+            self.screen_changed("screen_wake").await;
         }
     }
 
     async fn adjust_screen_after_clear(&mut self) {
+        let now = self.now();
         if let Some(ref mut manager) = self.screen_manager {
-            let result = if self.night_mode.is_night_mode() {
-                manager.on_night_mode().await
+            let action = if self.night_mode.is_night_mode() {
+                ScreenAction::Off
             } else {
-                manager.on_motion_timeout().await
+                ScreenAction::Dim
+            };
+            self.trace.record_at(now, TraceEvent::Screen { action });
+            let result = match action {
+                ScreenAction::Off => manager.on_night_mode().await,
+                _ => manager.on_motion_timeout().await,
             };
             if let Err(e) = result {
                 warn!(error = %e, "Failed to adjust screen");
             }
+            self.screen_changed("screen_adjust").await;
         }
     }
 }
@@ -265,20 +1232,35 @@ impl App {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::RemoteSensorConfig;
+    use crate::diagnostics::DiagnosticsSnapshot;
     use crate::mqtt::MqttPublishFuture;
+    use crate::stats::StatsSnapshot;
+    use crate::time_events::NightModeOverride;
     use std::sync::{Arc, Mutex};
 
     #[derive(Clone, Debug, PartialEq, Eq)]
     enum FakePublish {
         Discovery(String),
-        Availability(bool),
+        Availability(Availability),
         Motion(bool),
+        SensorMotion(String, bool),
+        Problem(Vec<(String, SensorFault)>),
+        Stats(u64),
+        Diagnostics(u64),
+        Occupancy(bool),
+        Touch(bool),
+        Radar(String, u16),
+        Screen(bool, u8),
+        NightMode(NightModeOverride),
+        MotionEvent(bool),
         Disconnect,
     }
 
     #[derive(Clone)]
     struct FakeMqttPublisher {
         records: Arc<Mutex<Vec<FakePublish>>>,
+        last_motion: Arc<Mutex<Option<MotionState>>>,
         fail_motion: bool,
     }
 
@@ -286,6 +1268,7 @@ mod tests {
         fn new(fail_motion: bool) -> Self {
             Self {
                 records: Arc::new(Mutex::new(Vec::new())),
+                last_motion: Arc::new(Mutex::new(None)),
                 fail_motion,
             }
         }
@@ -297,6 +1280,14 @@ mod tests {
                 .clone()
         }
 
+        fn last_motion(&self) -> MotionState {
+            self.last_motion
+                .lock()
+                .expect("motion mutex should not be poisoned")
+                .clone()
+                .expect("motion state should be published")
+        }
+
         fn push(&self, publish: FakePublish) -> Result<(), MqttError> {
             let mut records = self
                 .records
@@ -315,16 +1306,21 @@ mod tests {
             })
         }
 
-        fn publish_availability(&self, online: bool) -> MqttPublishFuture<'_> {
+        fn publish_availability(&self, availability: Availability) -> MqttPublishFuture<'_> {
             Box::pin(async move {
-                self.push(FakePublish::Availability(online))?;
+                self.push(FakePublish::Availability(availability))?;
                 Ok(())
             })
         }
 
-        fn publish_motion(&self, motion_detected: bool) -> MqttPublishFuture<'_> {
+        fn publish_motion<'a>(&'a self, state: &'a MotionState) -> MqttPublishFuture<'a> {
             Box::pin(async move {
-                self.push(FakePublish::Motion(motion_detected))?;
+                self.push(FakePublish::Motion(state.motion))?;
+                *self
+                    .last_motion
+                    .lock()
+                    .map_err(|_| MqttError::InvalidConfig("motion mutex poisoned".to_string()))? =
+                    Some(state.clone());
                 if self.fail_motion {
                     return Err(MqttError::InvalidConfig(
                         "forced publish failure".to_string(),
@@ -334,6 +1330,99 @@ mod tests {
             })
         }
 
+        fn publish_sensor_motion<'a>(
+            &'a self,
+            sensor: &'a str,
+            motion_detected: bool,
+        ) -> MqttPublishFuture<'a> {
+            Box::pin(async move {
+                self.push(FakePublish::SensorMotion(
+                    sensor.to_string(),
+                    motion_detected,
+                ))?;
+                Ok(())
+            })
+        }
+
+        fn publish_problem<'a>(
+            &'a self,
+            faults: &'a [(String, SensorFault)],
+        ) -> MqttPublishFuture<'a> {
+            Box::pin(async move {
+                self.push(FakePublish::Problem(faults.to_vec()))?;
+                Ok(())
+            })
+        }
+
+        fn publish_occupancy(&self, occupied: bool, _confidence: f64) -> MqttPublishFuture<'_> {
+            Box::pin(async move {
+                self.push(FakePublish::Occupancy(occupied))?;
+                Ok(())
+            })
+        }
+
+        fn publish_touch(&self, active: bool) -> MqttPublishFuture<'_> {
+            Box::pin(async move {
+                self.push(FakePublish::Touch(active))?;
+                Ok(())
+            })
+        }
+
+        fn publish_screen(&self, on: bool, brightness: u8) -> MqttPublishFuture<'_> {
+            Box::pin(async move {
+                self.push(FakePublish::Screen(on, brightness))?;
+                Ok(())
+            })
+        }
+
+        fn publish_night_mode(&self, mode: NightModeOverride) -> MqttPublishFuture<'_> {
+            Box::pin(async move {
+                self.push(FakePublish::NightMode(mode))?;
+                Ok(())
+            })
+        }
+
+        fn publish_radar<'a>(
+            &'a self,
+            sensor: &'a str,
+            reading: &'a RadarReading,
+        ) -> MqttPublishFuture<'a> {
+            Box::pin(async move {
+                self.push(FakePublish::Radar(
+                    sensor.to_string(),
+                    reading.detection_distance_cm,
+                ))?;
+                Ok(())
+            })
+        }
+
+        fn publish_stats<'a>(&'a self, stats: &'a StatsSnapshot) -> MqttPublishFuture<'a> {
+            Box::pin(async move {
+                self.push(FakePublish::Stats(stats.motion_events_today))?;
+                Ok(())
+            })
+        }
+
+        fn publish_diagnostics<'a>(
+            &'a self,
+            diagnostics: &'a DiagnosticsSnapshot,
+        ) -> MqttPublishFuture<'a> {
+            Box::pin(async move {
+                self.push(FakePublish::Diagnostics(diagnostics.uptime_secs))?;
+                Ok(())
+            })
+        }
+
+        fn publish_motion_event<'a>(
+            &'a self,
+            event: &'a MotionEventMessage,
+        ) -> MqttPublishFuture<'a> {
+            Box::pin(async move {
+                self.push(FakePublish::MotionEvent(event.motion))?;
+                Ok(())
+            })
+        }
+
         fn disconnect(&self) -> MqttPublishFuture<'_> {
             Box::pin(async move {
                 self.push(FakePublish::Disconnect)?;
@@ -355,7 +1444,7 @@ mod tests {
         let fake = FakeMqttPublisher::new(false);
         app.mqtt_client = Some(Box::new(fake.clone()));
 
-        app.initialize_motion_state(false).await;
+        app.initialize_motion_state(&[Some(false)]).await;
         app.publish_current_motion_state("startup")
             .await
             .expect("startup state publish should succeed");
@@ -371,7 +1460,7 @@ mod tests {
         let fake = FakeMqttPublisher::new(false);
         app.mqtt_client = Some(Box::new(fake.clone()));
 
-        app.initialize_motion_state(true).await;
+        app.initialize_motion_state(&[Some(true)]).await;
         app.handle_mqtt_event(MqttEvent::Connected)
             .await
             .expect("connect handling should succeed");
@@ -380,7 +1469,7 @@ mod tests {
             fake.records(),
             vec![
                 FakePublish::Discovery("mrpir-officescreen".to_string()),
-                FakePublish::Availability(true),
+                FakePublish::Availability(Availability::Online),
                 FakePublish::Motion(true),
             ]
         );
@@ -388,6 +1477,24 @@ mod tests {
         assert!(app.last_state_publish().is_some());
     }
 
+    #[tokio::test]
+    async fn home_assistant_restart_republishes_without_reconnecting() {
+        let mut app = App::new(test_config());
+        let fake = FakeMqttPublisher::new(false);
+        app.mqtt_client = Some(Box::new(fake.clone()));
+
+        app.initialize_motion_state(&[Some(false)]).await;
+        app.handle_mqtt_event(MqttEvent::Connected)
+            .await
+            .expect("connect handling should succeed");
+        let on_connect = fake.records();
+
+        app.handle_mqtt_event(MqttEvent::HomeAssistantOnline)
+            .await
+            .expect("Home Assistant status handling should succeed");
+        assert_eq!(fake.records()[on_connect.len()..], on_connect[..]);
+    }
+
     #[tokio::test]
     async fn detected_and_cleared_events_publish_state_even_as_edges() {
         let mut app = App::new(test_config());
@@ -422,4 +1529,603 @@ mod tests {
         assert!(app.motion_active());
         assert!(app.last_state_publish().is_none());
     }
+
+    fn multi_sensor_config() -> Config {
+        let mut config = test_config();
+        config.sensors = ["door", "window"]
+            .iter()
+            .zip([17, 27])
+            .map(|(name, gpio_pin)| SensorConfig {
+                name: Some((*name).to_string()),
+                gpio_pin,
+                ..SensorConfig::default()
+            })
+            .collect();
+        config
+    }
+
+    #[tokio::test]
+    async fn multiple_sensors_fuse_into_any_motion() {
+        let mut app = App::new(multi_sensor_config());
+        let fake = FakeMqttPublisher::new(false);
+        app.mqtt_client = Some(Box::new(fake.clone()));
+
+        for (sensor, event) in [
+            (0, MotionEvent::Detected),
+            (1, MotionEvent::Detected),
+            (0, MotionEvent::Cleared),
+            (1, MotionEvent::Cleared),
+        ] {
+            app.handle_sensor_event(SensorEvent::Motion { sensor, event })
+                .await
+                .expect("sensor event should succeed");
+        }
+
+        assert_eq!(
+            fake.records(),
+            vec![
+                FakePublish::SensorMotion("door".to_string(), true),
+                FakePublish::Motion(true),
+                FakePublish::SensorMotion("window".to_string(), true),
+                FakePublish::SensorMotion("door".to_string(), false),
+                FakePublish::SensorMotion("window".to_string(), false),
+                FakePublish::Motion(false),
+            ]
+        );
+        assert!(!app.motion_active());
+    }
+
+    #[tokio::test]
+    async fn json_motion_state_follows_sensors_screen_and_night_mode() {
+        let mut config = multi_sensor_config();
+        config.mqtt.state_format = MqttStateFormat::Json;
+        config.screen.enabled = true;
+        config.screen.transition_time_secs = 0;
+        let mut app = App::new(config);
+        let fake = FakeMqttPublisher::new(false);
+        app.mqtt_client = Some(Box::new(fake.clone()));
+
+        for sensor in [0, 1] {
+            app.handle_sensor_event(SensorEvent::Motion {
+                sensor,
+                event: MotionEvent::Detected,
+            })
+            .await
+            .expect("sensor event should succeed");
+        }
+        assert_eq!(fake.last_motion().sensors, ["door", "window"]);
+
+        app.handle_mqtt_event(MqttEvent::Command(MqttCommand::Screen {
+            on: true,
+            brightness: Some(100),
+        }))
+        .await
+        .expect("command should be handled");
+        assert_eq!(fake.last_motion().brightness, Some(100));
+
+        app.handle_mqtt_event(MqttEvent::Command(MqttCommand::NightMode(
+            NightModeOverride::On,
+        )))
+        .await
+        .expect("command should be handled");
+        let state = fake.last_motion();
+        assert!(state.motion);
+        assert!(state.night_mode);
+    }
+
+    #[tokio::test]
+    async fn startup_state_is_combined_from_all_sensors() {
+        let mut app = App::new(multi_sensor_config());
+        let fake = FakeMqttPublisher::new(false);
+        app.mqtt_client = Some(Box::new(fake.clone()));
+
+        app.initialize_motion_state(&[Some(false), Some(true)])
+            .await;
+        app.publish_current_motion_state("startup")
+            .await
+            .expect("startup state publish should succeed");
+
+        assert!(app.motion_active());
+        assert_eq!(
+            fake.records(),
+            vec![
+                FakePublish::SensorMotion("door".to_string(), false),
+                FakePublish::SensorMotion("window".to_string(), true),
+                FakePublish::Motion(true),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn warm_up_reports_initializing_until_all_sensors_settle() {
+        let mut app = App::new(multi_sensor_config());
+        let fake = FakeMqttPublisher::new(false);
+        app.mqtt_client = Some(Box::new(fake.clone()));
+
+        app.initialize_motion_state(&[None, None]).await;
+        assert!(app.warming_up());
+        app.handle_mqtt_event(MqttEvent::Connected)
+            .await
+            .expect("connect handling should succeed");
+        app.handle_sensor_event(SensorEvent::Ready {
+            sensor: 0,
+            motion_detected: true,
+        })
+        .await
+        .expect("ready event should succeed");
+
+        assert_eq!(
+            fake.records(),
+            vec![
+                FakePublish::Discovery("mrpir-officescreen".to_string()),
+                FakePublish::Availability(Availability::Offline),
+            ]
+        );
+        assert!(app.last_state_publish().is_none());
+
+        app.handle_sensor_event(SensorEvent::Ready {
+            sensor: 1,
+            motion_detected: false,
+        })
+        .await
+        .expect("ready event should succeed");
+
+        assert!(!app.warming_up());
+        assert!(app.motion_active());
+        assert_eq!(
+            fake.records()[2..],
+            [
+                FakePublish::Availability(Availability::Online),
+                FakePublish::SensorMotion("door".to_string(), true),
+                FakePublish::SensorMotion("window".to_string(), false),
+                FakePublish::Motion(true),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn json_state_reports_initializing_during_warm_up() {
+        let mut config = multi_sensor_config();
+        config.mqtt.state_format = MqttStateFormat::Json;
+        let mut app = App::new(config);
+        let fake = FakeMqttPublisher::new(false);
+        app.mqtt_client = Some(Box::new(fake.clone()));
+
+        app.initialize_motion_state(&[Some(true), None]).await;
+        app.publish_current_motion_state("startup")
+            .await
+            .expect("startup state publish should succeed");
+        assert!(fake.last_motion().initializing);
+
+        app.handle_sensor_event(SensorEvent::Ready {
+            sensor: 1,
+            motion_detected: false,
+        })
+        .await
+        .expect("ready event should succeed");
+        let state = fake.last_motion();
+        assert!(!state.initializing);
+        assert!(state.motion);
+    }
+
+    #[tokio::test]
+    async fn sensor_faults_publish_problem_state() {
+        let mut config = multi_sensor_config();
+        config.mqtt.problem_sensor = true;
+        let mut app = App::new(config);
+        let fake = FakeMqttPublisher::new(false);
+        app.mqtt_client = Some(Box::new(fake.clone()));
+
+        for change in [
+            FaultChange::Raised(SensorFault::Flapping),
+            FaultChange::Cleared(SensorFault::Flapping),
+        ] {
+            app.handle_sensor_event(SensorEvent::Fault { sensor: 1, change })
+                .await
+                .expect("fault event should succeed");
+        }
+
+        assert_eq!(
+            fake.records(),
+            vec![
+                FakePublish::Problem(vec![("window".to_string(), SensorFault::Flapping)]),
+                FakePublish::Problem(Vec::new()),
+            ]
+        );
+        assert!(!app.motion_active());
+    }
+
+    #[tokio::test]
+    async fn remote_sensors_merge_with_local_motion() {
+        let mut config = test_config();
+        config.remote_sensors = vec![RemoteSensorConfig {
+            name: Some("front_door".to_string()),
+            topic: "zigbee2mqtt/front_door".to_string(),
+            json_field: Some("contact".to_string()),
+            on_payloads: vec!["false".to_string()],
+            off_payloads: vec!["true".to_string()],
+            hold_secs: 0,
+        }];
+        let mut app = App::new(config);
+        let fake = FakeMqttPublisher::new(false);
+        app.mqtt_client = Some(Box::new(fake.clone()));
+        let dir = tempfile::tempdir().expect("temp dir should be created");
+        app.set_outbox(Outbox::in_memory(&dir.path().join("outbox.jsonl"), 10));
+        assert_eq!(app.mqtt_subscriptions(), ["zigbee2mqtt/front_door"]);
+
+        let door = |contact: bool| MqttEvent::Message {
+            topic: "zigbee2mqtt/front_door".to_string(),
+            payload: format!(r#"{{"contact": {contact}}}"#).into_bytes(),
+        };
+        app.handle_mqtt_event(door(false))
+            .await
+            .expect("remote message should be handled");
+        assert!(app.motion_active());
+
+        // Local motion clearing does not end the remote motion
+        app.handle_sensor_event(SensorEvent::Motion {
+            sensor: 0,
+            event: MotionEvent::Detected,
+        })
+        .await
+        .expect("motion event should succeed");
+        app.handle_sensor_event(SensorEvent::Motion {
+            sensor: 0,
+            event: MotionEvent::Cleared,
+        })
+        .await
+        .expect("motion event should succeed");
+        assert!(app.motion_active());
+
+        app.handle_mqtt_event(door(true))
+            .await
+            .expect("remote message should be handled");
+        assert!(!app.motion_active());
+        assert_eq!(
+            fake.records(),
+            vec![FakePublish::Motion(true), FakePublish::Motion(false)]
+        );
+
+        let outbox = app.outbox.as_mut().expect("outbox is set");
+        let mut reasons = Vec::new();
+        while let Some(event) = outbox.front() {
+            reasons.push(event.reason.clone());
+            outbox.pop_front().expect("pop");
+        }
+        assert_eq!(reasons, ["remote_detected", "remote_cleared"]);
+    }
+
+    #[tokio::test]
+    async fn diagnostics_are_published_on_connect() {
+        let mut config = test_config();
+        config.diagnostics.enabled = true;
+        config.diagnostics.thermal_zone = "/nonexistent/temp".into();
+        let mut app = App::new(config);
+        let fake = FakeMqttPublisher::new(false);
+        app.mqtt_client = Some(Box::new(fake.clone()));
+        assert!(app.mqtt_entities().diagnostics);
+
+        app.handle_mqtt_event(MqttEvent::Connected)
+            .await
+            .expect("connect handling should succeed");
+        app.publish_diagnostics("periodic_refresh").await;
+
+        let diagnostics: Vec<_> = fake
+            .records()
+            .into_iter()
+            .filter(|record| matches!(record, FakePublish::Diagnostics(_)))
+            .collect();
+        assert_eq!(
+            diagnostics,
+            vec![FakePublish::Diagnostics(0), FakePublish::Diagnostics(0)]
+        );
+    }
+
+    #[tokio::test]
+    async fn home_assistant_commands_are_applied_and_echoed() {
+        let mut config = test_config();
+        config.mqtt.commands = true;
+        config.screen.enabled = true;
+        config.screen.transition_time_secs = 0;
+        let mut app = App::new(config);
+        let fake = FakeMqttPublisher::new(false);
+        app.mqtt_client = Some(Box::new(fake.clone()));
+        assert!(app.mqtt_entities().screen);
+
+        for command in [
+            MqttCommand::Screen {
+                on: false,
+                brightness: None,
+            },
+            MqttCommand::Screen {
+                on: true,
+                brightness: Some(100),
+            },
+            MqttCommand::NightMode(NightModeOverride::On),
+            MqttCommand::SimulateMotion,
+        ] {
+            app.handle_mqtt_event(MqttEvent::Command(command))
+                .await
+                .expect("command should be handled");
+        }
+
+        assert_eq!(
+            fake.records(),
+            vec![
+                FakePublish::Screen(false, 0),
+                FakePublish::Screen(true, 100),
+                FakePublish::NightMode(NightModeOverride::On),
+                FakePublish::Motion(true),
+                FakePublish::Motion(false),
+                // Night mode is on, so the cleared motion turns the screen off
+                FakePublish::Screen(false, 0),
+            ]
+        );
+        assert!(!app.motion_active());
+        assert!(app.next_deadline().is_none());
+    }
+
+    #[tokio::test]
+    async fn radar_readings_are_published_under_the_sensor_name() {
+        let mut config = multi_sensor_config();
+        config.sensors[1].backend = SensorBackend::Ld2410;
+        let mut app = App::new(config);
+        let fake = FakeMqttPublisher::new(false);
+        app.mqtt_client = Some(Box::new(fake.clone()));
+
+        assert_eq!(app.mqtt_entities().radars, vec!["window".to_string()]);
+        let reading = RadarReading {
+            moving: false,
+            stationary: true,
+            moving_distance_cm: 0,
+            moving_energy: 0,
+            static_distance_cm: 180,
+            static_energy: 35,
+            detection_distance_cm: 180,
+        };
+        app.handle_sensor_event(SensorEvent::Radar { sensor: 1, reading })
+            .await
+            .expect("radar event should succeed");
+
+        assert_eq!(
+            fake.records(),
+            vec![FakePublish::Radar("window".to_string(), 180)]
+        );
+        assert!(!app.motion_active());
+    }
+
+    #[tokio::test]
+    async fn motion_statistics_are_saved_and_published() {
+        let dir = tempfile::tempdir().expect("temp dir should be created");
+        let path = dir.path().join("stats.json");
+        let mut config = test_config();
+        config.stats.enabled = true;
+        let mut app = App::new(config);
+        app.set_stats(MotionStats::default(), path.clone());
+        let fake = FakeMqttPublisher::new(false);
+        app.mqtt_client = Some(Box::new(fake.clone()));
+
+        for event in [
+            MotionEvent::Detected,
+            MotionEvent::Cleared,
+            MotionEvent::Detected,
+        ] {
+            app.handle_motion(event)
+                .await
+                .expect("motion event should succeed");
+        }
+
+        assert_eq!(
+            fake.records(),
+            vec![
+                FakePublish::Motion(true),
+                FakePublish::Stats(1),
+                FakePublish::Motion(false),
+                FakePublish::Stats(1),
+                FakePublish::Motion(true),
+                FakePublish::Stats(2),
+            ]
+        );
+
+        let mut restored = MotionStats::load(&path).expect("saved stats should load");
+        assert_eq!(
+            restored
+                .snapshot(Local::now(), Instant::now())
+                .motion_events_today,
+            2
+        );
+    }
+
+    #[tokio::test]
+    async fn motion_events_are_kept_while_offline_and_sent_in_order() {
+        let dir = tempfile::tempdir().expect("temp dir should be created");
+        let path = dir.path().join("outbox.jsonl");
+        let mut app = App::new(test_config());
+        app.set_outbox(Outbox::load(&path, 10).expect("outbox should load"));
+        let fake = FakeMqttPublisher::new(false);
+        app.mqtt_client = Some(Box::new(fake.clone()));
+
+        // Broker down: states are attempted, events wait in the outbox
+        for event in [MotionEvent::Detected, MotionEvent::Cleared] {
+            app.handle_motion(event)
+                .await
+                .expect("motion event should succeed");
+        }
+        assert_eq!(
+            fake.records(),
+            vec![FakePublish::Motion(true), FakePublish::Motion(false)]
+        );
+        assert_eq!(
+            Outbox::load(&path, 10).expect("saved outbox loads").len(),
+            2
+        );
+
+        app.handle_mqtt_event(MqttEvent::Connected)
+            .await
+            .expect("connect handling should succeed");
+        app.handle_motion(MotionEvent::Detected)
+            .await
+            .expect("motion event should succeed");
+        assert_eq!(
+            fake.records()[2..],
+            [
+                FakePublish::Discovery("mrpir-officescreen".to_string()),
+                FakePublish::Availability(Availability::Online),
+                FakePublish::Motion(false),
+                FakePublish::MotionEvent(true),
+                FakePublish::MotionEvent(false),
+                FakePublish::Motion(true),
+                FakePublish::MotionEvent(true),
+            ]
+        );
+
+        app.handle_mqtt_event(MqttEvent::Disconnected)
+            .await
+            .expect("disconnect handling should succeed");
+        app.handle_motion(MotionEvent::Cleared)
+            .await
+            .expect("motion event should succeed");
+        assert_eq!(fake.records().last(), Some(&FakePublish::Motion(false)));
+        assert_eq!(
+            Outbox::load(&path, 10).expect("saved outbox loads").len(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn motion_events_carry_sequence_reason_and_duration() {
+        let dir = tempfile::tempdir().expect("temp dir should be created");
+        let path = dir.path().join("outbox.jsonl");
+        let mut app = App::new(test_config());
+        app.set_outbox(Outbox::load(&path, 10).expect("outbox should load"));
+        let start = Instant::now();
+
+        app.set_virtual_now(start);
+        app.handle_motion(MotionEvent::Detected)
+            .await
+            .expect("motion event should succeed");
+        app.set_virtual_now(start + Duration::from_millis(12_340));
+        app.handle_motion(MotionEvent::Cleared)
+            .await
+            .expect("motion event should succeed");
+
+        let mut outbox = Outbox::load(&path, 10).expect("saved outbox loads");
+        let started = outbox.front().cloned().expect("start event");
+        assert!(started.motion);
+        assert_eq!(started.sequence, 1);
+        assert_eq!(started.reason, "pir_detected");
+        assert_eq!(started.duration_secs, None);
+
+        outbox.pop_front().expect("pop");
+        let ended = outbox.front().cloned().expect("end event");
+        assert!(!ended.motion);
+        assert_eq!(ended.sequence, 2);
+        assert_eq!(ended.reason, "pir_cleared");
+        assert_eq!(ended.duration_secs, Some(12.3));
+    }
+
+    #[tokio::test]
+    async fn occupancy_outlasts_motion_and_can_drive_the_screen() {
+        let mut config = test_config();
+        config.occupancy.enabled = true;
+        config.occupancy.drive_screen = true;
+        config.occupancy.min_hold_secs = 120;
+        config.screen.enabled = true;
+        config.screen.motion_timeout_secs = 0;
+        let mut app = App::new(config);
+        let fake = FakeMqttPublisher::new(false);
+        app.mqtt_client = Some(Box::new(fake.clone()));
+        let (trace, mut traced) = TraceRecorder::channel(Instant::now());
+        app.set_trace(trace);
+
+        let start = Instant::now();
+        app.set_virtual_now(start);
+        app.handle_motion(MotionEvent::Detected)
+            .await
+            .expect("detected event should succeed");
+        app.set_virtual_now(start + Duration::from_secs(10));
+        app.handle_motion(MotionEvent::Cleared)
+            .await
+            .expect("cleared event should succeed");
+
+        // Still occupied (and bright) after motion cleared
+        let vacant_at = app.next_deadline().expect("occupancy hold should run");
+        assert_eq!(vacant_at, start + Duration::from_secs(130));
+        app.set_virtual_now(vacant_at);
+        app.handle_deadline().await;
+        assert!(app.next_deadline().is_none());
+
+        assert_eq!(
+            fake.records(),
+            vec![
+                FakePublish::Motion(true),
+                FakePublish::Occupancy(true),
+                FakePublish::Screen(true, 255),
+                FakePublish::Motion(false),
+                FakePublish::Occupancy(false),
+                FakePublish::Screen(false, 0),
+            ]
+        );
+        let mut screen = Vec::new();
+        while let Ok(record) = traced.try_recv() {
+            if let TraceEvent::Screen { action } = record.event {
+                screen.push((record.t_ms / 1000, action));
+            }
+        }
+        assert_eq!(
+            screen,
+            vec![(0, ScreenAction::Bright), (130, ScreenAction::Dim)]
+        );
+    }
+
+    #[tokio::test]
+    async fn input_activity_keeps_the_screen_awake() {
+        let mut config = test_config();
+        config.input.enabled = true;
+        config.input.publish = true;
+        config.input.idle_secs = 30;
+        config.screen.enabled = true;
+        config.screen.motion_timeout_secs = 60;
+        let mut app = App::new(config);
+        let fake = FakeMqttPublisher::new(false);
+        app.mqtt_client = Some(Box::new(fake.clone()));
+        let (trace, mut traced) = TraceRecorder::channel(Instant::now());
+        app.set_trace(trace);
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+
+        // A tap wakes the dimmed screen; later taps only push the timeout back
+        app.set_virtual_now(start);
+        app.handle_input_activity().await;
+        app.set_virtual_now(at(50));
+        app.handle_input_activity().await;
+        assert_eq!(app.next_deadline(), Some(at(80)));
+
+        app.set_virtual_now(at(80));
+        app.handle_deadline().await;
+        assert_eq!(app.next_deadline(), Some(at(110)));
+        app.set_virtual_now(at(110));
+        app.handle_deadline().await;
+        assert!(app.next_deadline().is_none());
+
+        assert_eq!(
+            fake.records(),
+            vec![
+                FakePublish::Touch(true),
+                FakePublish::Screen(true, 255),
+                FakePublish::Touch(false),
+                FakePublish::Screen(false, 0),
+            ]
+        );
+        let mut screen = Vec::new();
+        while let Ok(record) = traced.try_recv() {
+            if let TraceEvent::Screen { action } = record.event {
+                screen.push((record.t_ms / 1000, action));
+            }
+        }
+        assert_eq!(
+            screen,
+            vec![(0, ScreenAction::Bright), (110, ScreenAction::Dim)]
+        );
+    }
 }
@@ -12,10 +12,25 @@ use figment::{
     Figment,
 };
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 
 use crate::error::ConfigError;
 
+/// Names used by device-level entities next to the per-sensor ones; with a
+/// `base_topic` lacking `{component}`, every entity shares one namespace.
+const RESERVED_SENSOR_NAMES: [&str; 9] = [
+    "problem",
+    "occupancy",
+    "touch",
+    "screen",
+    "stats",
+    "diagnostics",
+    "night_mode",
+    "simulate_motion",
+    "events",
+];
+
 /// Main configuration structure.
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Config {
@@ -31,6 +46,10 @@ pub struct Config {
     #[serde(default)]
     pub sensor: SensorConfig,
 
+    /// Multiple PIR sensors (overrides `sensor` when non-empty)
+    #[serde(default)]
+    pub sensors: Vec<SensorConfig>,
+
     /// MQTT configuration
     #[serde(default)]
     pub mqtt: MqttConfig,
@@ -50,15 +69,67 @@ pub struct Config {
     /// Logging configuration
     #[serde(default)]
     pub logging: LoggingConfig,
+
+    /// Motion statistics configuration
+    #[serde(default)]
+    pub stats: StatsConfig,
+
+    /// Occupancy configuration
+    #[serde(default)]
+    pub occupancy: OccupancyConfig,
+
+    /// Touchscreen and keyboard input configuration
+    #[serde(default)]
+    pub input: InputConfig,
+
+    /// Host diagnostics configuration
+    #[serde(default)]
+    pub diagnostics: DiagnosticsConfig,
+
+    /// Outbox configuration for motion event messages
+    #[serde(default)]
+    pub outbox: OutboxConfig,
+
+    /// Motion sources on other devices, read from MQTT topics
+    #[serde(default)]
+    pub remote_sensors: Vec<RemoteSensorConfig>,
 }
 
-/// PIR sensor configuration.
+/// Motion sensor configuration.
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct SensorConfig {
+    /// Sensor name used for per-sensor MQTT entities
+    #[serde(default)]
+    pub name: Option<String>,
+
     /// GPIO pin number for the PIR sensor
     #[serde(default = "default_gpio_pin")]
     pub gpio_pin: u8,
 
+    /// Driver used to read the sensor
+    #[serde(default)]
+    pub backend: SensorBackend,
+
+    /// GPIO chip for the cdev backend (`/dev/gpiochipN`, its name, or its label)
+    #[serde(default = "default_gpio_chip")]
+    pub chip: String,
+
+    /// GPIO line for the cdev backend, by offset or name (defaults to `gpio_pin`)
+    #[serde(default)]
+    pub line: Option<GpioLine>,
+
+    /// Serial port for the ld2410 backend
+    #[serde(default = "default_serial_port")]
+    pub port: String,
+
+    /// Serial baud rate for the ld2410 backend
+    #[serde(default = "default_radar_baud_rate")]
+    pub baud_rate: u32,
+
+    /// Radar module settings written at startup (ld2410 backend)
+    #[serde(default)]
+    pub radar: RadarConfig,
+
     /// Delay in seconds before considering motion stopped
     #[serde(default = "default_no_motion_delay")]
     pub no_motion_delay_secs: u64,
@@ -66,10 +137,144 @@ pub struct SensorConfig {
     /// Polling interval in milliseconds
     #[serde(default = "default_poll_interval")]
     pub poll_interval_ms: u64,
+
+    /// How the GPIO input is watched for changes
+    #[serde(default)]
+    pub mode: SensorMode,
+
+    /// Internal pull resistor on the GPIO input
+    #[serde(default)]
+    pub pull: PullMode,
+
+    /// Which GPIO level means motion
+    #[serde(default)]
+    pub polarity: Polarity,
+
+    /// Minimum time in milliseconds the input must stay active to count as motion
+    #[serde(default)]
+    pub min_pulse_ms: u64,
+
+    /// Number of recent samples considered by the glitch filter (poll mode, 0 disables)
+    #[serde(default)]
+    pub filter_window: u8,
+
+    /// Active samples required within `filter_window` to count as motion
+    #[serde(default)]
+    pub filter_threshold: u8,
+
+    /// Seconds after power-up during which the sensor output is ignored
+    #[serde(default)]
+    pub warmup_secs: u64,
+
+    /// Seconds the input may stay active before it is reported stuck (0 disables)
+    #[serde(default = "default_stuck_high")]
+    pub stuck_high_secs: u64,
+
+    /// Rising edges within `flap_window_secs` reported as flapping (0 disables)
+    #[serde(default = "default_flap_max_edges")]
+    pub flap_max_edges: u8,
+
+    /// Window in seconds for counting rising edges
+    #[serde(default = "default_flap_window")]
+    pub flap_window_secs: u64,
+
+    /// Treat a stuck sensor as no motion so it cannot keep the screen on
+    #[serde(default)]
+    pub ignore_stuck: bool,
+}
+
+/// Drivers for reading the sensor.
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SensorBackend {
+    /// Raspberry Pi GPIO registers via rppal
+    #[default]
+    Rppal,
+    /// Linux GPIO character device (`/dev/gpiochipN`)
+    Cdev,
+    /// HLK-LD2410 mmWave radar on a serial port; presence counts as motion
+    Ld2410,
+}
+
+/// LD2410 radar settings.
+///
+/// Unset values leave the module's stored configuration alone.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct RadarConfig {
+    /// Farthest distance gate (2-8, 0.75 m each) that detects moving targets
+    #[serde(default)]
+    pub max_moving_gate: Option<u8>,
+
+    /// Farthest distance gate (2-8) that detects stationary targets
+    #[serde(default)]
+    pub max_static_gate: Option<u8>,
+
+    /// Moving target sensitivity (0-100) for gates 0, 1, ...
+    #[serde(default)]
+    pub moving_sensitivity: Vec<u8>,
+
+    /// Stationary target sensitivity (0-100) for gates 0, 1, ...
+    #[serde(default)]
+    pub static_sensitivity: Vec<u8>,
+}
+
+/// A GPIO line on a character device.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum GpioLine {
+    /// Line offset within the chip
+    Offset(u32),
+    /// Line name from the device tree or `gpio-sim`
+    Name(String),
+}
+
+impl std::fmt::Display for GpioLine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Offset(offset) => write!(f, "{offset}"),
+            Self::Name(name) => f.write_str(name),
+        }
+    }
+}
+
+/// Sensor input modes.
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SensorMode {
+    /// Wake on GPIO interrupts for rising and falling edges
+    #[default]
+    Edge,
+    /// Sample the GPIO level every `poll_interval_ms` (fallback)
+    Poll,
+}
+
+/// GPIO pull resistor modes.
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PullMode {
+    /// Pull the input low (push-pull PIR modules)
+    #[default]
+    Down,
+    /// Pull the input high (open-collector modules)
+    Up,
+    /// No internal pull resistor (external resistor fitted)
+    None,
+}
+
+/// Sensor output polarity.
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Polarity {
+    /// High level means motion
+    #[default]
+    ActiveHigh,
+    /// Low level means motion
+    ActiveLow,
 }
 
 /// MQTT broker configuration.
 #[derive(Debug, Clone, Deserialize, Serialize)]
+#[allow(clippy::struct_excessive_bools)] // independent feature switches
 pub struct MqttConfig {
     /// Enable MQTT functionality
     #[serde(default = "default_true")]
@@ -103,9 +308,126 @@ pub struct MqttConfig {
     #[serde(default = "default_ha_prefix")]
     pub ha_discovery_prefix: String,
 
+    /// Base of the state, availability, attributes and command topics; may
+    /// contain `{prefix}`, `{component}`, `{device}`, `{client_id}` and `{hostname}`
+    #[serde(default = "default_base_topic")]
+    pub base_topic: String,
+
+    /// Topic Home Assistant announces itself on (default: `<ha_discovery_prefix>/status`)
+    #[serde(default)]
+    pub ha_status_topic: Option<String>,
+
+    /// Longest random delay before republishing when Home Assistant comes online
+    #[serde(default = "default_ha_status_delay")]
+    pub ha_status_max_delay_secs: u64,
+
     /// Keep-alive interval in seconds
     #[serde(default = "default_keep_alive")]
     pub keep_alive_secs: u64,
+
+    /// Accept screen, night mode and simulated motion commands from Home Assistant
+    #[serde(default)]
+    pub commands: bool,
+
+    /// Announce a problem binary sensor for stuck or flapping sensors
+    #[serde(default)]
+    pub problem_sensor: bool,
+
+    /// Motion state payload: bare `ON`/`OFF`, or JSON with context attributes
+    #[serde(default)]
+    pub state_format: MqttStateFormat,
+
+    /// TLS for the broker connection
+    #[serde(default)]
+    pub tls: MqttTlsConfig,
+
+    /// How to reach the broker: plain TCP, or MQTT over a WebSocket
+    #[serde(default)]
+    pub transport: MqttTransport,
+
+    /// URL path of the broker's WebSocket endpoint
+    #[serde(default = "default_ws_path")]
+    pub ws_path: String,
+
+    /// Extra headers for the WebSocket handshake, e.g. proxy authentication
+    #[serde(default)]
+    pub ws_headers: BTreeMap<String, String>,
+
+    /// MQTT protocol version: 4 (MQTT 3.1.1) or 5
+    #[serde(default = "default_protocol_version")]
+    pub protocol_version: u8,
+
+    /// Expiry of retained motion states, so stale ones age out (MQTT 5)
+    #[serde(default)]
+    pub message_expiry_secs: Option<u32>,
+
+    /// How long the broker keeps the session after a disconnect (MQTT 5)
+    #[serde(default)]
+    pub session_expiry_secs: Option<u32>,
+
+    /// User properties sent with the connection and every publish (MQTT 5)
+    #[serde(default)]
+    pub user_properties: BTreeMap<String, String>,
+}
+
+/// MQTT transports.
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum MqttTransport {
+    /// MQTT over TCP (with `mqtt.tls` when enabled)
+    #[default]
+    Tcp,
+    /// MQTT over a WebSocket
+    Ws,
+    /// MQTT over a WebSocket with TLS, configured by `mqtt.tls`
+    Wss,
+}
+
+/// Payload formats of the motion state topic.
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum MqttStateFormat {
+    /// `ON` or `OFF`
+    #[default]
+    Plain,
+    /// JSON object with motion, last change, sensor, night mode and brightness
+    Json,
+}
+
+/// MQTT TLS configuration.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct MqttTlsConfig {
+    /// Connect to the broker over TLS
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// CA certificate (PEM) to verify the broker with (unset: the system's CA store)
+    #[serde(default)]
+    pub ca_file: Option<PathBuf>,
+
+    /// Client certificate (PEM) for mutual TLS
+    #[serde(default)]
+    pub client_cert: Option<PathBuf>,
+
+    /// Private key (PEM) of the client certificate
+    #[serde(default)]
+    pub client_key: Option<PathBuf>,
+
+    /// Accept any broker certificate (for lab use only)
+    #[serde(default)]
+    pub insecure_skip_verify: bool,
+
+    /// ALPN protocols to offer, e.g. `["mqtt"]`
+    #[serde(default)]
+    pub alpn: Vec<String>,
+
+    /// Name the broker certificate must match (default: `mqtt.host`)
+    #[serde(default)]
+    pub server_name: Option<String>,
+
+    /// Send the server name (SNI) in the handshake
+    #[serde(default = "default_true")]
+    pub sni: bool,
 }
 
 /// Screen control configuration.
@@ -213,6 +535,133 @@ pub struct LoggingConfig {
     /// Log to file
     #[serde(default)]
     pub file: Option<PathBuf>,
+
+    /// Record a motion trace (JSONL) for `mrpir replay`
+    #[serde(default)]
+    pub trace_file: Option<PathBuf>,
+}
+
+/// Motion statistics configuration.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct StatsConfig {
+    /// Publish motion statistics as Home Assistant sensors
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// File the daily counters are saved to across restarts
+    #[serde(default)]
+    pub state_file: Option<PathBuf>,
+
+    /// How often the rolling statistics are republished (seconds)
+    #[serde(default = "default_stats_interval")]
+    pub publish_interval_secs: u64,
+}
+
+/// Occupancy configuration.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct OccupancyConfig {
+    /// Publish an occupancy entity derived from motion
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Dim the screen when the room becomes vacant instead of when motion clears
+    #[serde(default)]
+    pub drive_screen: bool,
+
+    /// Seconds a single brief trigger keeps the room occupied after motion clears
+    #[serde(default = "default_occupancy_min_hold")]
+    pub min_hold_secs: u64,
+
+    /// Upper limit on the hold after frequent motion (seconds)
+    #[serde(default = "default_occupancy_max_hold")]
+    pub max_hold_secs: u64,
+
+    /// Seconds for the weight of a past motion event to halve
+    #[serde(default = "default_occupancy_half_life")]
+    pub half_life_secs: u64,
+}
+
+/// Touchscreen and keyboard (evdev) input configuration.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct InputConfig {
+    /// Count input events as activity that keeps the screen awake
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Input devices to watch, e.g. `/dev/input/event0` (empty: auto-detect touchscreens)
+    #[serde(default)]
+    pub devices: Vec<PathBuf>,
+
+    /// Publish a touch activity entity
+    #[serde(default)]
+    pub publish: bool,
+
+    /// Seconds without input before the touch activity entity turns off
+    #[serde(default = "default_input_idle")]
+    pub idle_secs: u64,
+}
+
+/// Host diagnostics configuration.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct DiagnosticsConfig {
+    /// Publish uptime, IP address, temperature, Wi-Fi signal, load and memory
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// How often the diagnostics are republished (seconds)
+    #[serde(default = "default_diagnostics_interval")]
+    pub publish_interval_secs: u64,
+
+    /// Thermal zone temperature file, in millidegrees Celsius
+    #[serde(default = "default_thermal_zone")]
+    pub thermal_zone: PathBuf,
+
+    /// Wireless interface to report the signal of (unset: the first in `/proc/net/wireless`)
+    #[serde(default)]
+    pub wifi_interface: Option<String>,
+}
+
+/// Outbox configuration.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct OutboxConfig {
+    /// Keep unsent motion event messages on disk across outages and restarts
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// File the unsent events are saved to
+    #[serde(default)]
+    pub file: Option<PathBuf>,
+
+    /// Most events kept; the oldest are dropped beyond this
+    #[serde(default = "default_outbox_max_events")]
+    pub max_events: usize,
+}
+
+/// A motion source on another device, read from an MQTT topic.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RemoteSensorConfig {
+    /// Name used in logs and traces (defaults to the topic)
+    #[serde(default)]
+    pub name: Option<String>,
+
+    /// Topic to subscribe to (wildcards allowed)
+    pub topic: String,
+
+    /// Dot-separated JSON field holding the state, e.g. `occupancy` (unset: the whole payload)
+    #[serde(default)]
+    pub json_field: Option<String>,
+
+    /// Payloads (or field values) that mean motion
+    #[serde(default = "default_remote_on_payloads")]
+    pub on_payloads: Vec<String>,
+
+    /// Payloads (or field values) that clear motion
+    #[serde(default = "default_remote_off_payloads")]
+    pub off_payloads: Vec<String>,
+
+    /// Clear motion this many seconds after the last motion payload (0: wait for an off payload)
+    #[serde(default)]
+    pub hold_secs: u64,
 }
 
 // Default value functions
@@ -223,10 +672,42 @@ fn default_device_name() -> String {
         .unwrap_or_else(|| "mrpir".to_string())
 }
 
+fn default_remote_on_payloads() -> Vec<String> {
+    vec!["ON".to_string()]
+}
+
+fn default_remote_off_payloads() -> Vec<String> {
+    vec!["OFF".to_string()]
+}
+
 fn default_gpio_pin() -> u8 {
     17
 }
 
+fn default_gpio_chip() -> String {
+    "gpiochip0".to_string()
+}
+
+fn default_stuck_high() -> u64 {
+    14400
+}
+
+fn default_flap_max_edges() -> u8 {
+    20
+}
+
+fn default_flap_window() -> u64 {
+    60
+}
+
+fn default_serial_port() -> String {
+    "/dev/serial0".to_string()
+}
+
+fn default_radar_baud_rate() -> u32 {
+    256_000
+}
+
 fn default_no_motion_delay() -> u64 {
     5
 }
@@ -247,10 +728,26 @@ fn default_mqtt_port() -> u16 {
     1883
 }
 
+fn default_ha_status_delay() -> u64 {
+    5
+}
+
+fn default_protocol_version() -> u8 {
+    4
+}
+
+fn default_ws_path() -> String {
+    "/mqtt".to_string()
+}
+
 fn default_ha_prefix() -> String {
     "homeassistant".to_string()
 }
 
+fn default_base_topic() -> String {
+    "{prefix}/{component}/{device}".to_string()
+}
+
 fn default_keep_alive() -> u64 {
     60
 }
@@ -283,6 +780,38 @@ fn default_screen_off_delay() -> u64 {
     3600
 }
 
+fn default_stats_interval() -> u64 {
+    60
+}
+
+fn default_outbox_max_events() -> usize {
+    1000
+}
+
+fn default_diagnostics_interval() -> u64 {
+    60
+}
+
+fn default_thermal_zone() -> PathBuf {
+    PathBuf::from("/sys/class/thermal/thermal_zone0/temp")
+}
+
+fn default_occupancy_min_hold() -> u64 {
+    60
+}
+
+fn default_occupancy_max_hold() -> u64 {
+    1800
+}
+
+fn default_occupancy_half_life() -> u64 {
+    600
+}
+
+fn default_input_idle() -> u64 {
+    30
+}
+
 fn default_log_level() -> String {
     "info".to_string()
 }
@@ -294,11 +823,18 @@ impl Default for Config {
             device_name: default_device_name(),
             display_name: None,
             sensor: SensorConfig::default(),
+            sensors: Vec::new(),
             mqtt: MqttConfig::default(),
             screen: ScreenConfig::default(),
             night_mode: NightModeConfig::default(),
             location: LocationConfig::default(),
             logging: LoggingConfig::default(),
+            stats: StatsConfig::default(),
+            occupancy: OccupancyConfig::default(),
+            input: InputConfig::default(),
+            diagnostics: DiagnosticsConfig::default(),
+            outbox: OutboxConfig::default(),
+            remote_sensors: Vec::new(),
         }
     }
 }
@@ -306,35 +842,383 @@ impl Default for Config {
 impl Default for SensorConfig {
     fn default() -> Self {
         Self {
+            name: None,
             gpio_pin: default_gpio_pin(),
+            backend: SensorBackend::default(),
+            port: default_serial_port(),
+            baud_rate: default_radar_baud_rate(),
+            radar: RadarConfig::default(),
+            chip: default_gpio_chip(),
+            line: None,
             no_motion_delay_secs: default_no_motion_delay(),
             poll_interval_ms: default_poll_interval(),
+            mode: SensorMode::default(),
+            pull: PullMode::default(),
+            polarity: Polarity::default(),
+            min_pulse_ms: 0,
+            filter_window: 0,
+            filter_threshold: 0,
+            warmup_secs: 0,
+            stuck_high_secs: default_stuck_high(),
+            flap_max_edges: default_flap_max_edges(),
+            flap_window_secs: default_flap_window(),
+            ignore_stuck: false,
         }
     }
 }
 
-impl Default for MqttConfig {
-    fn default() -> Self {
-        Self {
-            enabled: true,
-            host: default_mqtt_host(),
-            port: default_mqtt_port(),
-            username: None,
-            password: None,
-            client_id: None,
-            ha_discovery: true,
-            ha_discovery_prefix: default_ha_prefix(),
-            keep_alive_secs: default_keep_alive(),
+impl SensorConfig {
+    /// Get the sensor name, falling back to the GPIO line name or pin, or
+    /// the serial port's file name.
+    pub fn name(&self) -> String {
+        if let Some(name) = &self.name {
+            return name.clone();
+        }
+
+        match (self.backend, self.cdev_line()) {
+            (SensorBackend::Cdev, GpioLine::Name(line)) => line,
+            (SensorBackend::Ld2410, _) => {
+                self.port.rsplit('/').next().unwrap_or_default().to_string()
+            }
+            _ => format!("gpio{}", self.gpio_pin),
         }
     }
-}
 
-impl Default for ScreenConfig {
-    fn default() -> Self {
-        Self {
-            enabled: false,
-            method: ScreenMethod::None,
-            dim_brightness: 0,
+    /// The character-device line, falling back to `gpio_pin` as the offset.
+    pub fn cdev_line(&self) -> GpioLine {
+        self.line
+            .clone()
+            .unwrap_or_else(|| GpioLine::Offset(u32::from(self.gpio_pin)))
+    }
+
+    /// Human-readable identifier of the input, unique per physical line or port.
+    pub fn input_id(&self) -> String {
+        match self.backend {
+            SensorBackend::Rppal => format!("gpio{}", self.gpio_pin),
+            SensorBackend::Cdev => format!("{}:{}", self.chip, self.cdev_line()),
+            SensorBackend::Ld2410 => self.port.clone(),
+        }
+    }
+
+    /// Validate the backend-specific input settings.
+    fn validate_input(&self, field_prefix: &str) -> Result<(), ConfigError> {
+        match self.backend {
+            // Validate GPIO pin range (Raspberry Pi has pins 0-27)
+            SensorBackend::Rppal => {
+                if self.gpio_pin > 27 {
+                    return Err(ConfigError::InvalidValue {
+                        field: format!("{field_prefix}.gpio_pin"),
+                        message: format!("GPIO pin must be 0-27, got {}", self.gpio_pin),
+                    });
+                }
+            }
+            // Other boards have their own numbering; the chip reports bad offsets
+            SensorBackend::Cdev => {
+                if self.chip.is_empty() {
+                    return Err(ConfigError::InvalidValue {
+                        field: format!("{field_prefix}.chip"),
+                        message: "GPIO chip must not be empty".to_string(),
+                    });
+                }
+                if self.line == Some(GpioLine::Name(String::new())) {
+                    return Err(ConfigError::InvalidValue {
+                        field: format!("{field_prefix}.line"),
+                        message: "GPIO line name must not be empty".to_string(),
+                    });
+                }
+            }
+            SensorBackend::Ld2410 => {
+                if self.port.is_empty() {
+                    return Err(ConfigError::InvalidValue {
+                        field: format!("{field_prefix}.port"),
+                        message: "Serial port must not be empty".to_string(),
+                    });
+                }
+                if self.baud_rate == 0 {
+                    return Err(ConfigError::InvalidValue {
+                        field: format!("{field_prefix}.baud_rate"),
+                        message: "Baud rate must not be 0".to_string(),
+                    });
+                }
+                self.radar.validate(&format!("{field_prefix}.radar"))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Validate a single sensor's settings.
+    fn validate(&self, field_prefix: &str) -> Result<(), ConfigError> {
+        self.validate_input(field_prefix)?;
+
+        // An active-low input idles low with a pull-down and reads as constant motion
+        if self.polarity == Polarity::ActiveLow && self.pull == PullMode::Down {
+            return Err(ConfigError::InvalidValue {
+                field: format!("{field_prefix}.pull"),
+                message: "active-low sensors need pull = \"up\" or \"none\"".to_string(),
+            });
+        }
+
+        if self.filter_window > 64 {
+            return Err(ConfigError::InvalidValue {
+                field: format!("{field_prefix}.filter_window"),
+                message: format!(
+                    "Filter window must be 0-64 samples, got {}",
+                    self.filter_window
+                ),
+            });
+        }
+
+        if self.filter_window > 0 {
+            if self.filter_threshold == 0 || self.filter_threshold > self.filter_window {
+                return Err(ConfigError::InvalidValue {
+                    field: format!("{field_prefix}.filter_threshold"),
+                    message: format!(
+                        "Filter threshold must be 1-{}, got {}",
+                        self.filter_window, self.filter_threshold
+                    ),
+                });
+            }
+
+            if self.mode != SensorMode::Poll {
+                return Err(ConfigError::InvalidValue {
+                    field: format!("{field_prefix}.filter_window"),
+                    message:
+                        "Sample filtering requires mode = \"poll\"; use min_pulse_ms in edge mode"
+                            .to_string(),
+                });
+            }
+        }
+
+        if self.flap_max_edges > 0 && self.flap_window_secs == 0 {
+            return Err(ConfigError::InvalidValue {
+                field: format!("{field_prefix}.flap_window_secs"),
+                message: "Flap window must be at least 1 second".to_string(),
+            });
+        }
+
+        if self.ignore_stuck && self.stuck_high_secs == 0 {
+            return Err(ConfigError::InvalidValue {
+                field: format!("{field_prefix}.ignore_stuck"),
+                message: "ignore_stuck requires stuck_high_secs > 0".to_string(),
+            });
+        }
+
+        let name = self.name();
+        if name.is_empty()
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        {
+            return Err(ConfigError::InvalidValue {
+                field: format!("{field_prefix}.name"),
+                message: format!(
+                    "Sensor name must be non-empty and contain only letters, digits, '_' or '-', got {name:?}"
+                ),
+            });
+        }
+
+        // Shares the device topic namespace with the device-level entities
+        if RESERVED_SENSOR_NAMES.contains(&name.as_str()) {
+            return Err(ConfigError::InvalidValue {
+                field: format!("{field_prefix}.name"),
+                message: format!("Sensor name {name:?} is reserved"),
+            });
+        }
+
+        Ok(())
+    }
+}
+
+impl RadarConfig {
+    /// Highest LD2410 distance gate.
+    pub const MAX_GATE: u8 = 8;
+
+    fn validate(&self, field_prefix: &str) -> Result<(), ConfigError> {
+        for (field, gate) in [
+            ("max_moving_gate", self.max_moving_gate),
+            ("max_static_gate", self.max_static_gate),
+        ] {
+            // The module rejects a range below gate 2
+            if gate.is_some_and(|gate| !(2..=Self::MAX_GATE).contains(&gate)) {
+                return Err(ConfigError::InvalidValue {
+                    field: format!("{field_prefix}.{field}"),
+                    message: format!("Gate must be 2-{}", Self::MAX_GATE),
+                });
+            }
+        }
+
+        // Both sensitivities of a gate are written by one command
+        if self.moving_sensitivity.len() != self.static_sensitivity.len() {
+            return Err(ConfigError::InvalidValue {
+                field: format!("{field_prefix}.static_sensitivity"),
+                message: "moving_sensitivity and static_sensitivity need one value per gate each"
+                    .to_string(),
+            });
+        }
+        for (field, values) in [
+            ("moving_sensitivity", &self.moving_sensitivity),
+            ("static_sensitivity", &self.static_sensitivity),
+        ] {
+            if values.len() > usize::from(Self::MAX_GATE) + 1 {
+                return Err(ConfigError::InvalidValue {
+                    field: format!("{field_prefix}.{field}"),
+                    message: format!("At most {} gates", Self::MAX_GATE + 1),
+                });
+            }
+            if values.iter().any(|value| *value > 100) {
+                return Err(ConfigError::InvalidValue {
+                    field: format!("{field_prefix}.{field}"),
+                    message: "Sensitivity must be 0-100".to_string(),
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for MqttConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            host: default_mqtt_host(),
+            port: default_mqtt_port(),
+            username: None,
+            password: None,
+            client_id: None,
+            ha_discovery: true,
+            ha_discovery_prefix: default_ha_prefix(),
+            base_topic: default_base_topic(),
+            ha_status_topic: None,
+            ha_status_max_delay_secs: default_ha_status_delay(),
+            keep_alive_secs: default_keep_alive(),
+            commands: false,
+            problem_sensor: false,
+            state_format: MqttStateFormat::default(),
+            tls: MqttTlsConfig::default(),
+            transport: MqttTransport::default(),
+            ws_path: default_ws_path(),
+            ws_headers: BTreeMap::new(),
+            protocol_version: default_protocol_version(),
+            message_expiry_secs: None,
+            session_expiry_secs: None,
+            user_properties: BTreeMap::new(),
+        }
+    }
+}
+
+impl MqttConfig {
+    /// Topic of Home Assistant's birth and last will messages.
+    pub fn ha_status_topic(&self) -> String {
+        self.ha_status_topic
+            .clone()
+            .unwrap_or_else(|| format!("{}/status", self.ha_discovery_prefix))
+    }
+
+    /// Broker URL for the WebSocket transports, `None` for TCP.
+    pub fn websocket_url(&self) -> Option<String> {
+        let scheme = match self.transport {
+            MqttTransport::Tcp => return None,
+            MqttTransport::Ws => "ws",
+            MqttTransport::Wss => "wss",
+        };
+        Some(format!(
+            "{scheme}://{}:{}{}",
+            self.host, self.port, self.ws_path
+        ))
+    }
+
+    fn validate(&self) -> Result<(), ConfigError> {
+        match self.transport {
+            MqttTransport::Tcp => {}
+            MqttTransport::Ws if self.tls.enabled => {
+                return Err(ConfigError::InvalidValue {
+                    field: "mqtt.transport".to_string(),
+                    message: "Use transport = \"wss\" for WebSockets over TLS".to_string(),
+                });
+            }
+            MqttTransport::Ws | MqttTransport::Wss => {
+                if !self.ws_path.starts_with('/') {
+                    return Err(ConfigError::InvalidValue {
+                        field: "mqtt.ws_path".to_string(),
+                        message: "WebSocket path must start with '/'".to_string(),
+                    });
+                }
+            }
+        }
+        if self.tls.enabled || self.transport == MqttTransport::Wss {
+            self.tls.validate()?;
+        }
+        self.validate_base_topic()?;
+        self.validate_protocol()
+    }
+
+    fn validate_base_topic(&self) -> Result<(), ConfigError> {
+        let invalid = |message: &str| ConfigError::InvalidValue {
+            field: "mqtt.base_topic".to_string(),
+            message: message.to_string(),
+        };
+        let resolved = ["prefix", "component", "device", "client_id", "hostname"]
+            .iter()
+            .fold(self.base_topic.clone(), |topic, placeholder| {
+                topic.replace(&format!("{{{placeholder}}}"), "x")
+            });
+        if resolved.contains(['{', '}']) {
+            return Err(invalid(
+                "Unknown placeholder; use {prefix}, {component}, {device}, {client_id} or {hostname}",
+            ));
+        }
+        if resolved.contains(['+', '#']) {
+            return Err(invalid("Base topic must not contain wildcards"));
+        }
+        if resolved.is_empty() || resolved.starts_with('/') || resolved.ends_with('/') {
+            return Err(invalid(
+                "Base topic must not be empty or start or end with '/'",
+            ));
+        }
+        Ok(())
+    }
+
+    fn validate_protocol(&self) -> Result<(), ConfigError> {
+        let v5_only = [
+            ("message_expiry_secs", self.message_expiry_secs.is_some()),
+            ("session_expiry_secs", self.session_expiry_secs.is_some()),
+            ("user_properties", !self.user_properties.is_empty()),
+        ];
+        match self.protocol_version {
+            4 => {
+                if let Some((field, _)) = v5_only.iter().find(|(_, set)| *set) {
+                    return Err(ConfigError::InvalidValue {
+                        field: format!("mqtt.{field}"),
+                        message: "Requires protocol_version = 5".to_string(),
+                    });
+                }
+            }
+            5 => {
+                if self.message_expiry_secs == Some(0) {
+                    return Err(ConfigError::InvalidValue {
+                        field: "mqtt.message_expiry_secs".to_string(),
+                        message: "Message expiry must be at least 1 second".to_string(),
+                    });
+                }
+            }
+            _ => {
+                return Err(ConfigError::InvalidValue {
+                    field: "mqtt.protocol_version".to_string(),
+                    message: "Protocol version must be 4 (MQTT 3.1.1) or 5".to_string(),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for ScreenConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            method: ScreenMethod::None,
+            dim_brightness: 0,
             bright_brightness: default_bright_brightness(),
             brightness_path: None,
             transition_time_secs: default_transition_time(),
@@ -361,10 +1245,161 @@ impl Default for LoggingConfig {
         Self {
             level: default_log_level(),
             file: None,
+            trace_file: None,
+        }
+    }
+}
+
+impl Default for StatsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            state_file: None,
+            publish_interval_secs: default_stats_interval(),
+        }
+    }
+}
+
+impl Default for OccupancyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            drive_screen: false,
+            min_hold_secs: default_occupancy_min_hold(),
+            max_hold_secs: default_occupancy_max_hold(),
+            half_life_secs: default_occupancy_half_life(),
+        }
+    }
+}
+
+impl Default for InputConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            devices: Vec::new(),
+            publish: false,
+            idle_secs: default_input_idle(),
+        }
+    }
+}
+
+impl Default for MqttTlsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            ca_file: None,
+            client_cert: None,
+            client_key: None,
+            insecure_skip_verify: false,
+            alpn: Vec::new(),
+            server_name: None,
+            sni: true,
+        }
+    }
+}
+
+impl MqttTlsConfig {
+    fn validate(&self) -> Result<(), ConfigError> {
+        if self.client_cert.is_some() != self.client_key.is_some() {
+            return Err(ConfigError::InvalidValue {
+                field: "mqtt.tls.client_key".to_string(),
+                message: "client_cert and client_key must be set together".to_string(),
+            });
+        }
+        if self.alpn.iter().any(String::is_empty) {
+            return Err(ConfigError::InvalidValue {
+                field: "mqtt.tls.alpn".to_string(),
+                message: "ALPN protocols must not be empty".to_string(),
+            });
+        }
+        Ok(())
+    }
+}
+
+impl Default for DiagnosticsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            publish_interval_secs: default_diagnostics_interval(),
+            thermal_zone: default_thermal_zone(),
+            wifi_interface: None,
+        }
+    }
+}
+
+impl Default for OutboxConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            file: None,
+            max_events: default_outbox_max_events(),
+        }
+    }
+}
+
+impl OutboxConfig {
+    /// Get the outbox file, defaulting to the state directory.
+    pub fn file(&self) -> PathBuf {
+        self.file
+            .clone()
+            .unwrap_or_else(|| state_dir().join("outbox.jsonl"))
+    }
+}
+
+impl RemoteSensorConfig {
+    /// Get the name, falling back to the topic.
+    pub fn name(&self) -> String {
+        self.name.clone().unwrap_or_else(|| self.topic.clone())
+    }
+
+    /// Validate a single remote sensor's settings.
+    fn validate(&self, field_prefix: &str) -> Result<(), ConfigError> {
+        if self.topic.is_empty() || !rumqttc::valid_filter(&self.topic) {
+            return Err(ConfigError::InvalidValue {
+                field: format!("{field_prefix}.topic"),
+                message: format!("Invalid MQTT topic filter {:?}", self.topic),
+            });
+        }
+        if self.json_field.as_deref() == Some("") {
+            return Err(ConfigError::InvalidValue {
+                field: format!("{field_prefix}.json_field"),
+                message: "JSON field must not be empty".to_string(),
+            });
+        }
+        if self.on_payloads.is_empty() {
+            return Err(ConfigError::InvalidValue {
+                field: format!("{field_prefix}.on_payloads"),
+                message: "At least one motion payload is required".to_string(),
+            });
         }
+        if self.off_payloads.is_empty() && self.hold_secs == 0 {
+            return Err(ConfigError::InvalidValue {
+                field: format!("{field_prefix}.hold_secs"),
+                message: "Without off_payloads, hold_secs must be at least 1 second".to_string(),
+            });
+        }
+        Ok(())
+    }
+}
+
+impl StatsConfig {
+    /// Get the state file, defaulting to the systemd `StateDirectory` or the
+    /// user state directory.
+    pub fn state_file(&self) -> PathBuf {
+        self.state_file
+            .clone()
+            .unwrap_or_else(|| state_dir().join("stats.json"))
     }
 }
 
+/// The systemd `StateDirectory`, or the user state directory.
+fn state_dir() -> PathBuf {
+    std::env::var_os("STATE_DIRECTORY")
+        .map(PathBuf::from)
+        .or_else(|| dirs::state_dir().map(|dir| dir.join("mrpir")))
+        .unwrap_or_else(|| PathBuf::from("/var/lib/mrpir"))
+}
+
 impl Config {
     /// Load configuration from all sources.
     ///
@@ -396,16 +1431,118 @@ impl Config {
         Ok(config)
     }
 
+    /// Validate each sensor and check that no two share a name or input.
+    fn validate_sensors(&self) -> Result<(), ConfigError> {
+        let field_prefix = if self.sensors.is_empty() {
+            "sensor"
+        } else {
+            "sensors"
+        };
+        let sensors = self.sensors();
+        for (index, sensor) in sensors.iter().enumerate() {
+            sensor.validate(field_prefix)?;
+
+            let name = sensor.name();
+            for other in &sensors[..index] {
+                if other.name() == name {
+                    return Err(ConfigError::InvalidValue {
+                        field: format!("{field_prefix}.name"),
+                        message: format!("Duplicate sensor name {name:?}"),
+                    });
+                }
+                if other.backend == sensor.backend && other.input_id() == sensor.input_id() {
+                    let field = match sensor.backend {
+                        SensorBackend::Rppal => "gpio_pin",
+                        SensorBackend::Cdev => "line",
+                        SensorBackend::Ld2410 => "port",
+                    };
+                    return Err(ConfigError::InvalidValue {
+                        field: format!("{field_prefix}.{field}"),
+                        message: format!(
+                            "GPIO input {} used by more than one sensor",
+                            sensor.input_id()
+                        ),
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
     /// Validate configuration values.
     fn validate(&self) -> Result<(), ConfigError> {
-        // Validate GPIO pin range (Raspberry Pi has pins 0-27)
-        if self.sensor.gpio_pin > 27 {
+        self.validate_sensors()?;
+        if self.mqtt.enabled {
+            self.mqtt.validate()?;
+        }
+
+        if self.stats.enabled && self.stats.publish_interval_secs == 0 {
+            return Err(ConfigError::InvalidValue {
+                field: "stats.publish_interval_secs".to_string(),
+                message: "Publish interval must be at least 1 second".to_string(),
+            });
+        }
+
+        if self.mqtt.enabled && self.outbox.max_events == 0 {
+            return Err(ConfigError::InvalidValue {
+                field: "outbox.max_events".to_string(),
+                message: "Outbox must hold at least 1 event".to_string(),
+            });
+        }
+
+        if self.diagnostics.enabled && self.diagnostics.publish_interval_secs == 0 {
+            return Err(ConfigError::InvalidValue {
+                field: "diagnostics.publish_interval_secs".to_string(),
+                message: "Publish interval must be at least 1 second".to_string(),
+            });
+        }
+
+        if self.occupancy.enabled {
+            if self.occupancy.half_life_secs == 0 {
+                return Err(ConfigError::InvalidValue {
+                    field: "occupancy.half_life_secs".to_string(),
+                    message: "Half-life must be at least 1 second".to_string(),
+                });
+            }
+            if self.occupancy.min_hold_secs > self.occupancy.max_hold_secs {
+                return Err(ConfigError::InvalidValue {
+                    field: "occupancy.max_hold_secs".to_string(),
+                    message: format!(
+                        "Maximum hold must be at least min_hold_secs ({})",
+                        self.occupancy.min_hold_secs
+                    ),
+                });
+            }
+        } else if self.occupancy.drive_screen {
             return Err(ConfigError::InvalidValue {
-                field: "sensor.gpio_pin".to_string(),
-                message: format!("GPIO pin must be 0-27, got {}", self.sensor.gpio_pin),
+                field: "occupancy.drive_screen".to_string(),
+                message: "drive_screen requires occupancy.enabled".to_string(),
             });
         }
 
+        if self.input.publish && !self.input.enabled {
+            return Err(ConfigError::InvalidValue {
+                field: "input.publish".to_string(),
+                message: "publish requires input.enabled".to_string(),
+            });
+        }
+        if self.input.enabled && self.input.idle_secs == 0 {
+            return Err(ConfigError::InvalidValue {
+                field: "input.idle_secs".to_string(),
+                message: "Idle time must be at least 1 second".to_string(),
+            });
+        }
+
+        if !self.remote_sensors.is_empty() && !self.mqtt.enabled {
+            return Err(ConfigError::InvalidValue {
+                field: "remote_sensors".to_string(),
+                message: "remote sensors require mqtt.enabled".to_string(),
+            });
+        }
+        for (index, remote) in self.remote_sensors.iter().enumerate() {
+            remote.validate(&format!("remote_sensors[{index}]"))?;
+        }
+
         // Validate night mode hours
         if self.night_mode.start_hour > 23 || self.night_mode.end_hour > 23 {
             return Err(ConfigError::InvalidValue {
@@ -426,6 +1563,17 @@ impl Config {
         Ok(())
     }
 
+    /// Get the effective list of sensors.
+    ///
+    /// Uses `[[sensors]]` when configured, otherwise the single `[sensor]` table.
+    pub fn sensors(&self) -> Vec<SensorConfig> {
+        if self.sensors.is_empty() {
+            vec![self.sensor.clone()]
+        } else {
+            self.sensors.clone()
+        }
+    }
+
     /// Get the effective client ID.
     pub fn client_id(&self) -> String {
         self.mqtt
@@ -448,6 +1596,7 @@ mod tests {
     fn test_default_config() {
         let config = Config::default();
         assert_eq!(config.sensor.gpio_pin, 17);
+        assert_eq!(config.sensor.mode, SensorMode::Edge);
         assert_eq!(config.mqtt.port, 1883);
         assert!(config.mqtt.enabled);
     }
@@ -459,6 +1608,318 @@ mod tests {
         assert!(config.validate().is_err());
     }
 
+    #[test]
+    fn test_sensors_fall_back_to_single_sensor() {
+        let config = Config::default();
+        let sensors = config.sensors();
+        assert_eq!(sensors.len(), 1);
+        assert_eq!(sensors[0].gpio_pin, 17);
+        assert_eq!(sensors[0].name(), "gpio17");
+    }
+
+    #[test]
+    fn test_validation_duplicate_sensors() {
+        let sensor = |name: &str, gpio_pin| SensorConfig {
+            name: Some(name.to_string()),
+            gpio_pin,
+            ..SensorConfig::default()
+        };
+
+        let mut config = Config::default();
+        config.sensors = vec![sensor("door", 17), sensor("door", 27)];
+        assert!(config.validate().is_err());
+
+        config.sensors = vec![sensor("door", 17), sensor("window", 17)];
+        assert!(config.validate().is_err());
+
+        config.sensors = vec![sensor("door", 17), sensor("window", 27)];
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_validation_sensor_name_must_be_topic_safe() {
+        let mut config = Config::default();
+        config.sensor.name = Some("front/door".to_string());
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_validation_active_low_requires_pull_up() {
+        let mut config = Config::default();
+        config.sensor.polarity = Polarity::ActiveLow;
+        assert!(config.validate().is_err());
+
+        config.sensor.pull = PullMode::Up;
+        assert!(config.validate().is_ok());
+
+        config.sensor.pull = PullMode::None;
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_validation_glitch_filter() {
+        let mut config = Config::default();
+        config.sensor.mode = SensorMode::Poll;
+        config.sensor.filter_window = 5;
+        config.sensor.filter_threshold = 6;
+        assert!(config.validate().is_err());
+
+        config.sensor.filter_threshold = 3;
+        assert!(config.validate().is_ok());
+
+        config.sensor.mode = SensorMode::Edge;
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_validation_ld2410_backend() {
+        let mut config = Config::default();
+        config.sensor.backend = SensorBackend::Ld2410;
+        config.sensor.port = "/dev/ttyAMA0".to_string();
+        config.sensor.radar.max_moving_gate = Some(6);
+        config.sensor.radar.moving_sensitivity = vec![50, 50, 40];
+        config.sensor.radar.static_sensitivity = vec![0, 0, 40];
+        assert!(config.validate().is_ok());
+        assert_eq!(config.sensor.name(), "ttyAMA0");
+
+        config.sensor.radar.static_sensitivity.pop();
+        assert!(config.validate().is_err());
+
+        config.sensor.radar.static_sensitivity.push(101);
+        assert!(config.validate().is_err());
+
+        config.sensor.radar.static_sensitivity = vec![0, 0, 40];
+        config.sensor.radar.max_static_gate = Some(9);
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_validation_remote_sensors() {
+        let mut config = Config::default();
+        config.remote_sensors = vec![Figment::from(Toml::string(
+            "topic = \"zigbee2mqtt/hallway\"\njson_field = \"occupancy\"",
+        ))
+        .extract()
+        .expect("remote sensor should parse")];
+        assert!(config.validate().is_ok());
+        assert_eq!(config.remote_sensors[0].name(), "zigbee2mqtt/hallway");
+        assert_eq!(config.remote_sensors[0].on_payloads, ["ON"]);
+
+        config.remote_sensors[0].off_payloads.clear();
+        assert!(config.validate().is_err());
+        config.remote_sensors[0].hold_secs = 30;
+        assert!(config.validate().is_ok());
+
+        config.remote_sensors[0].topic = "zigbee2mqtt/#/hallway".to_string();
+        assert!(config.validate().is_err());
+
+        config.remote_sensors[0].topic = "zigbee2mqtt/+".to_string();
+        config.mqtt.enabled = false;
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_validation_mqtt_tls() {
+        let mut config = Config::default();
+        config.mqtt.tls.enabled = true;
+        config.mqtt.tls.client_cert = Some(PathBuf::from("/etc/mrpir/client.pem"));
+        assert!(config.validate().is_err());
+
+        config.mqtt.tls.client_key = Some(PathBuf::from("/etc/mrpir/client.key"));
+        assert!(config.validate().is_ok());
+
+        config.mqtt.tls.alpn = vec![String::new()];
+        assert!(config.validate().is_err());
+
+        config.mqtt.tls.enabled = false;
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_validation_mqtt_websocket() {
+        let mut config = Config::default();
+        assert_eq!(config.mqtt.websocket_url(), None);
+
+        config.mqtt.transport = MqttTransport::Wss;
+        config.mqtt.host = "mqtt.example.com".to_string();
+        config.mqtt.port = 443;
+        assert!(config.validate().is_ok());
+        assert_eq!(
+            config.mqtt.websocket_url().as_deref(),
+            Some("wss://mqtt.example.com:443/mqtt")
+        );
+
+        config.mqtt.ws_path = "mqtt".to_string();
+        assert!(config.validate().is_err());
+
+        config.mqtt.ws_path = "/broker/ws".to_string();
+        config.mqtt.transport = MqttTransport::Ws;
+        config.mqtt.tls.enabled = true;
+        assert!(config.validate().is_err());
+
+        config.mqtt.tls.enabled = false;
+        assert!(config.validate().is_ok());
+        assert_eq!(
+            config.mqtt.websocket_url().as_deref(),
+            Some("ws://mqtt.example.com:443/broker/ws")
+        );
+    }
+
+    #[test]
+    fn test_ha_status_topic() {
+        let mut config = MqttConfig::default();
+        assert_eq!(config.ha_status_topic(), "homeassistant/status");
+
+        config.ha_discovery_prefix = "ha".to_string();
+        assert_eq!(config.ha_status_topic(), "ha/status");
+
+        config.ha_status_topic = Some("hass/status".to_string());
+        assert_eq!(config.ha_status_topic(), "hass/status");
+    }
+
+    #[test]
+    fn test_validation_mqtt_base_topic() {
+        let mut config = Config::default();
+        assert!(config.validate().is_ok());
+
+        config.mqtt.base_topic = "mrpir/{hostname}/{client_id}/{device}".to_string();
+        assert!(config.validate().is_ok());
+
+        for base_topic in ["mrpir/{room}", "mrpir/+/{device}", "mrpir/{device}/", ""] {
+            config.mqtt.base_topic = base_topic.to_string();
+            assert!(
+                config.validate().is_err(),
+                "{base_topic:?} should be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn test_validation_mqtt_protocol() {
+        let mut config = Config::default();
+        config.mqtt.message_expiry_secs = Some(300);
+        assert!(config.validate().is_err());
+
+        config.mqtt.protocol_version = 5;
+        config.mqtt.session_expiry_secs = Some(3600);
+        config
+            .mqtt
+            .user_properties
+            .insert("room".to_string(), "office".to_string());
+        assert!(config.validate().is_ok());
+
+        config.mqtt.message_expiry_secs = Some(0);
+        assert!(config.validate().is_err());
+
+        config.mqtt.message_expiry_secs = None;
+        config.mqtt.protocol_version = 3;
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_validation_cdev_backend() {
+        let mut config = Config::default();
+        config.sensor.backend = SensorBackend::Cdev;
+        config.sensor.gpio_pin = 200;
+        assert!(config.validate().is_ok());
+
+        config.sensor.chip = String::new();
+        assert!(config.validate().is_err());
+
+        let sensor = |line: GpioLine| SensorConfig {
+            backend: SensorBackend::Cdev,
+            line: Some(line),
+            ..SensorConfig::default()
+        };
+        config.sensors = vec![
+            sensor(GpioLine::Name("PIR_DOOR".to_string())),
+            sensor(GpioLine::Name("PIR_DOOR".to_string())),
+        ];
+        assert!(config.validate().is_err());
+
+        config.sensors[1] = sensor(GpioLine::Offset(3));
+        assert!(config.validate().is_ok());
+        assert_eq!(config.sensors[0].name(), "PIR_DOOR");
+        assert_eq!(config.sensors[1].name(), "gpio17");
+        assert_eq!(config.sensors[1].input_id(), "gpiochip0:3");
+    }
+
+    #[test]
+    fn test_cdev_line_by_offset_or_name() {
+        let sensor: SensorConfig = Figment::from(Toml::string(
+            "backend = \"cdev\"\nchip = \"gpio-sim.0-node0\"\nline = 4",
+        ))
+        .extract()
+        .expect("offset line should parse");
+        assert_eq!(sensor.line, Some(GpioLine::Offset(4)));
+
+        let sensor: SensorConfig =
+            Figment::from(Toml::string("backend = \"cdev\"\nline = \"PIR\""))
+                .extract()
+                .expect("named line should parse");
+        assert_eq!(sensor.cdev_line(), GpioLine::Name("PIR".to_string()));
+        assert_eq!(sensor.chip, "gpiochip0");
+    }
+
+    #[test]
+    fn test_validation_fault_thresholds() {
+        let mut config = Config::default();
+        config.sensor.ignore_stuck = true;
+        assert!(config.validate().is_ok());
+
+        config.sensor.stuck_high_secs = 0;
+        assert!(config.validate().is_err());
+
+        config.sensor.ignore_stuck = false;
+        config.sensor.flap_window_secs = 0;
+        assert!(config.validate().is_err());
+
+        config.sensor.flap_max_edges = 0;
+        assert!(config.validate().is_ok());
+
+        config.sensor.name = Some("problem".to_string());
+        assert!(config.validate().is_err());
+
+        // Shares a topic with the screen light when the base topic has no {component}
+        config.mqtt.base_topic = "mrpir/{device}".to_string();
+        config.sensor.name = Some("screen".to_string());
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_validation_occupancy() {
+        let mut config = Config::default();
+        config.occupancy.drive_screen = true;
+        assert!(config.validate().is_err());
+
+        config.occupancy.enabled = true;
+        assert!(config.validate().is_ok());
+
+        config.occupancy.max_hold_secs = 30;
+        assert!(config.validate().is_err());
+
+        config.occupancy.max_hold_secs = 1800;
+        config.sensor.name = Some("occupancy".to_string());
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_validation_input() {
+        let mut config = Config::default();
+        config.input.publish = true;
+        assert!(config.validate().is_err());
+
+        config.input.enabled = true;
+        assert!(config.validate().is_ok());
+
+        config.input.idle_secs = 0;
+        assert!(config.validate().is_err());
+
+        config.input.idle_secs = 30;
+        config.sensor.name = Some("touch".to_string());
+        assert!(config.validate().is_err());
+    }
+
     #[test]
     fn test_validation_sun_times_without_location() {
         let mut config = Config::default();
@@ -0,0 +1,234 @@
+//! Host health readings published as Home Assistant diagnostic sensors.
+//!
+//! Readings come from procfs and sysfs on each publish. One that can't be
+//! read (no Wi-Fi, no thermal zone, ...) is published as `null`, which Home
+//! Assistant shows as unknown. The broker host is resolved once, with a
+//! timeout, so a slow resolver can't hold up the main loop on every publish.
+
+use serde::Serialize;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use tokio::net::UdpSocket;
+use tokio::sync::OnceCell;
+use tracing::debug;
+
+use crate::config::DiagnosticsConfig;
+
+const WIRELESS_PATH: &str = "/proc/net/wireless";
+const LOADAVG_PATH: &str = "/proc/loadavg";
+const MEMINFO_PATH: &str = "/proc/meminfo";
+
+/// Longest wait for the broker host to resolve.
+const RESOLVE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Diagnostics as published to Home Assistant.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DiagnosticsSnapshot {
+    /// Seconds since the daemon started
+    pub uptime_secs: u64,
+    /// Address of the interface used to reach the MQTT broker
+    pub ip_address: Option<String>,
+    /// System-on-chip temperature in degrees Celsius
+    pub cpu_temperature: Option<f64>,
+    /// Wi-Fi signal level in dBm
+    pub wifi_rssi: Option<i32>,
+    /// One-minute load average
+    pub load_average: Option<f64>,
+    /// Memory in use, as a percentage of the total
+    pub memory_used_percent: Option<f64>,
+}
+
+/// Collects diagnostics for the host.
+#[derive(Debug)]
+pub struct Diagnostics {
+    config: DiagnosticsConfig,
+    broker: (String, u16),
+    broker_addr: OnceCell<SocketAddr>,
+    started: Instant,
+}
+
+impl Diagnostics {
+    /// Start counting uptime now; `broker` picks the interface whose address
+    /// is reported.
+    pub fn new(config: &DiagnosticsConfig, broker_host: &str, broker_port: u16) -> Self {
+        Self {
+            config: config.clone(),
+            broker: (broker_host.to_string(), broker_port),
+            broker_addr: OnceCell::new(),
+            started: Instant::now(),
+        }
+    }
+
+    /// Read the current diagnostics.
+    pub async fn collect(&self) -> DiagnosticsSnapshot {
+        let wifi_interface = self.config.wifi_interface.as_deref();
+        DiagnosticsSnapshot {
+            uptime_secs: self.started.elapsed().as_secs(),
+            ip_address: self.host_ip().await,
+            cpu_temperature: read(&self.config.thermal_zone)
+                .await
+                .and_then(|text| parse_temperature(&text)),
+            wifi_rssi: read(Path::new(WIRELESS_PATH))
+                .await
+                .and_then(|text| parse_wireless_rssi(&text, wifi_interface)),
+            load_average: read(Path::new(LOADAVG_PATH))
+                .await
+                .and_then(|text| parse_load_average(&text)),
+            memory_used_percent: read(Path::new(MEMINFO_PATH))
+                .await
+                .and_then(|text| parse_memory_used(&text)),
+        }
+    }
+
+    /// The local address of a UDP socket aimed at the broker.
+    ///
+    /// Connecting a UDP socket sends nothing; it only picks the route.
+    async fn host_ip(&self) -> Option<String> {
+        let broker = *self
+            .broker_addr
+            .get_or_try_init(|| self.resolve_broker())
+            .await
+            .ok()?;
+        let local = if broker.is_ipv4() {
+            "0.0.0.0:0"
+        } else {
+            "[::]:0"
+        };
+        let socket = UdpSocket::bind(local).await.ok()?;
+        if let Err(e) = socket.connect(broker).await {
+            debug!(error = %e, "No route to the MQTT broker for the host address");
+            return None;
+        }
+        socket.local_addr().ok().map(|addr| addr.ip().to_string())
+    }
+
+    /// Resolve the broker host, giving up after [`RESOLVE_TIMEOUT`].
+    async fn resolve_broker(&self) -> Result<SocketAddr, ()> {
+        let (host, port) = (self.broker.0.as_str(), self.broker.1);
+        match tokio::time::timeout(RESOLVE_TIMEOUT, tokio::net::lookup_host((host, port))).await {
+            Ok(Ok(mut addrs)) => addrs.next().ok_or(()),
+            Ok(Err(e)) => {
+                debug!(host, error = %e, "Failed to resolve the MQTT broker for the host address");
+                Err(())
+            }
+            Err(_) => {
+                debug!(
+                    host,
+                    "Timed out resolving the MQTT broker for the host address"
+                );
+                Err(())
+            }
+        }
+    }
+}
+
+async fn read(path: &Path) -> Option<String> {
+    tokio::fs::read_to_string(path)
+        .await
+        .map_err(|e| debug!(path = %path.display(), error = %e, "Diagnostic reading unavailable"))
+        .ok()
+}
+
+/// Round to one decimal place.
+fn round_tenth(value: f64) -> f64 {
+    (value * 10.0).round() / 10.0
+}
+
+/// Degrees Celsius from a thermal zone's millidegrees.
+fn parse_temperature(text: &str) -> Option<f64> {
+    let millidegrees: f64 = text.trim().parse().ok()?;
+    Some(round_tenth(millidegrees / 1000.0))
+}
+
+/// Signal level of `interface` (or the first interface) in `/proc/net/wireless`.
+///
+/// After two header lines, each line reads
+/// `wlan0: 0000   54.  -56.  -256 ...`: status, link quality, then level.
+fn parse_wireless_rssi(text: &str, interface: Option<&str>) -> Option<i32> {
+    text.lines().skip(2).find_map(|line| {
+        let (name, fields) = line.split_once(':')?;
+        if interface.is_some_and(|wanted| wanted != name.trim()) {
+            return None;
+        }
+        fields
+            .split_whitespace()
+            .nth(2)?
+            .trim_end_matches('.')
+            .parse()
+            .ok()
+    })
+}
+
+/// One-minute load average from `/proc/loadavg`.
+fn parse_load_average(text: &str) -> Option<f64> {
+    text.split_whitespace().next()?.parse().ok()
+}
+
+/// Percentage of memory in use from `/proc/meminfo`.
+fn parse_memory_used(text: &str) -> Option<f64> {
+    let field = |name: &str| -> Option<f64> {
+        text.lines()
+            .find_map(|line| line.strip_prefix(name)?.strip_prefix(':'))?
+            .split_whitespace()
+            .next()?
+            .parse()
+            .ok()
+    };
+    let total = field("MemTotal")?;
+    let available = field("MemAvailable")?;
+    (total > 0.0).then(|| round_tenth((total - available) / total * 100.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn procfs_and_sysfs_readings_are_parsed() {
+        assert_eq!(parse_temperature("48312\n"), Some(48.3));
+        assert_eq!(parse_temperature(""), None);
+
+        assert_eq!(
+            parse_load_average("0.52 0.41 0.30 1/234 5678\n"),
+            Some(0.52)
+        );
+
+        let meminfo = "MemTotal:        3884536 kB\nMemFree:          912344 kB\nMemAvailable:    2913402 kB\n";
+        assert_eq!(parse_memory_used(meminfo), Some(25.0));
+        assert_eq!(parse_memory_used("MemTotal: 100 kB\n"), None);
+    }
+
+    #[test]
+    fn wireless_signal_is_read_per_interface() {
+        let wireless = "\
+Inter-| sta-|   Quality        |   Discarded packets               | Missed | WE
+ face | tus | link level noise |  nwid  crypt   frag  retry   misc | beacon | 22
+ wlan0: 0000   54.  -56.  -256        0      0      0      0      0        0
+ wlan1: 0000   30.  -80.  -256        0      0      0      0      0        0
+";
+        assert_eq!(parse_wireless_rssi(wireless, None), Some(-56));
+        assert_eq!(parse_wireless_rssi(wireless, Some("wlan1")), Some(-80));
+        assert_eq!(parse_wireless_rssi(wireless, Some("wlan2")), None);
+        let header: String = wireless.lines().take(2).collect();
+        assert_eq!(parse_wireless_rssi(&header, None), None);
+    }
+
+    #[tokio::test]
+    async fn uptime_and_address_are_collected() {
+        let config = DiagnosticsConfig {
+            thermal_zone: "/nonexistent/temp".into(),
+            ..DiagnosticsConfig::default()
+        };
+        let diagnostics = Diagnostics::new(&config, "127.0.0.1", 1883);
+
+        let snapshot = diagnostics.collect().await;
+        assert_eq!(snapshot.uptime_secs, 0);
+        assert_eq!(snapshot.ip_address.as_deref(), Some("127.0.0.1"));
+        assert_eq!(snapshot.cpu_temperature, None);
+        assert_eq!(
+            diagnostics.broker_addr.get(),
+            Some(&SocketAddr::from(([127, 0, 0, 1], 1883)))
+        );
+    }
+}
@@ -15,11 +15,36 @@ pub enum MqttError {
     #[error("failed to publish message: {0}")]
     PublishFailed(#[from] rumqttc::ClientError),
 
+    #[error("failed to connect to MQTT broker: {0}")]
+    ConnectionFailedV5(#[from] rumqttc::v5::ConnectionError),
+
+    #[error("failed to publish message: {0}")]
+    PublishFailedV5(#[from] rumqttc::v5::ClientError),
+
     #[error("timed out queueing MQTT publish to {topic} after {timeout_secs}s")]
     PublishTimedOut { topic: String, timeout_secs: u64 },
 
     #[error("invalid MQTT configuration: {0}")]
     InvalidConfig(String),
+
+    #[error("invalid command on {topic}: {reason}")]
+    InvalidCommand { topic: String, reason: String },
+
+    #[error("failed to read TLS file {path}: {source}")]
+    TlsFile {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("invalid certificate in {path}: {reason}")]
+    InvalidCertificate { path: String, reason: String },
+
+    #[error("invalid private key in {path}: {reason}")]
+    InvalidPrivateKey { path: String, reason: String },
+
+    #[error("TLS setup failed: {0}")]
+    Tls(#[from] rustls::Error),
 }
 
 /// Errors that can occur in the sensor module.
@@ -29,11 +54,29 @@ pub enum SensorError {
     #[error("GPIO initialization failed: {0}")]
     GpioInit(#[from] rppal::gpio::Error),
 
+    #[error("GPIO character device error: {0}")]
+    Cdev(#[from] gpio_cdev::Error),
+
     #[error("invalid GPIO pin: {0}")]
     InvalidPin(u8),
 
+    #[error("GPIO chip {0} not found")]
+    ChipNotFound(String),
+
+    #[error("GPIO line {line} not found on {chip}")]
+    LineNotFound { chip: String, line: String },
+
     #[error("sensor read failed: {0}")]
     ReadFailed(String),
+
+    #[error("{0} is not a GPIO input")]
+    NotGpio(String),
+
+    #[error("serial port error: {0}")]
+    Serial(#[from] tokio_serial::Error),
+
+    #[error("radar command {command:#06x} failed: {reason}")]
+    RadarCommand { command: u16, reason: String },
 }
 
 /// Errors that can occur in the screen control module.
@@ -53,6 +96,42 @@ pub enum ScreenError {
     OperationFailed(String),
 }
 
+/// Errors that can occur watching input devices.
+#[derive(Error, Debug)]
+#[non_exhaustive]
+pub enum InputError {
+    #[error("failed to open input device {path}: {source}")]
+    Open {
+        path: String,
+        source: std::io::Error,
+    },
+
+    #[error("no touchscreen found under /dev/input")]
+    NoDevices,
+}
+
+/// Errors that can occur saving or loading motion statistics.
+#[derive(Error, Debug)]
+#[non_exhaustive]
+pub enum StatsError {
+    #[error("stats state file I/O failed: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("invalid stats state file: {0}")]
+    Format(#[from] serde_json::Error),
+}
+
+/// Errors that can occur in the event outbox.
+#[derive(Error, Debug)]
+#[non_exhaustive]
+pub enum OutboxError {
+    #[error("outbox file I/O failed: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("invalid outbox file: {0}")]
+    Format(#[from] serde_json::Error),
+}
+
 /// Errors that can occur in configuration loading.
 #[derive(Error, Debug)]
 #[non_exhaustive]
@@ -0,0 +1,206 @@
+//! Touchscreen and keyboard activity from Linux input devices (evdev).
+//!
+//! Key, touch and pointer events on the watched devices count as activity.
+//! Each device reports at most one activity per [`ACTIVITY_INTERVAL`], so a
+//! finger dragging across the screen doesn't flood the main loop.
+
+use evdev::{AbsoluteAxisCode, Device, EventType, InputEvent, KeyCode, PropType};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc::{self, error::TrySendError};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+
+use crate::config::InputConfig;
+use crate::error::InputError;
+
+/// Shortest time between activity reports from one device.
+const ACTIVITY_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Watches input devices for user activity.
+pub struct InputWatcher {
+    devices: Vec<(PathBuf, Device)>,
+}
+
+impl InputWatcher {
+    /// Open the configured devices, or every touchscreen when none are listed.
+    pub fn open(config: &InputConfig) -> Result<Self, InputError> {
+        let devices = if config.devices.is_empty() {
+            let touchscreens: Vec<_> = evdev::enumerate()
+                .filter(|(_, device)| is_touchscreen(device))
+                .collect();
+            if touchscreens.is_empty() {
+                return Err(InputError::NoDevices);
+            }
+            touchscreens
+        } else {
+            config
+                .devices
+                .iter()
+                .map(|path| {
+                    Device::open(path)
+                        .map(|device| (path.clone(), device))
+                        .map_err(|source| InputError::Open {
+                            path: path.display().to_string(),
+                            source,
+                        })
+                })
+                .collect::<Result<_, _>>()?
+        };
+
+        for (path, device) in &devices {
+            info!(
+                path = %path.display(),
+                name = device.name().unwrap_or("unknown"),
+                "Watching input device for activity"
+            );
+        }
+        Ok(Self { devices })
+    }
+
+    /// Watch every device until `shutdown` is cancelled, sending a unit to
+    /// `tx` on activity.
+    ///
+    /// A device that fails (e.g. is unplugged) is logged and dropped.
+    pub fn spawn(self, tx: &mpsc::Sender<()>, shutdown: &CancellationToken) {
+        for (path, device) in self.devices {
+            tokio::spawn(watch_device(path, device, tx.clone(), shutdown.clone()));
+        }
+    }
+}
+
+async fn watch_device(
+    path: PathBuf,
+    device: Device,
+    tx: mpsc::Sender<()>,
+    shutdown: CancellationToken,
+) {
+    let mut events = match device.into_event_stream() {
+        Ok(events) => events,
+        Err(e) => {
+            warn!(path = %path.display(), error = %e, "Failed to read input device");
+            return;
+        }
+    };
+    let mut last_report: Option<Instant> = None;
+
+    loop {
+        let event = tokio::select! {
+            () = shutdown.cancelled() => return,
+            event = events.next_event() => event,
+        };
+        let event = match event {
+            Ok(event) => event,
+            Err(e) => {
+                warn!(path = %path.display(), error = %e, "Input device stopped; no longer watching it");
+                return;
+            }
+        };
+        if !is_activity(&event) {
+            continue;
+        }
+
+        let now = Instant::now();
+        if last_report.is_some_and(|at| now.duration_since(at) < ACTIVITY_INTERVAL) {
+            continue;
+        }
+        last_report = Some(now);
+        debug!(path = %path.display(), "Input activity");
+
+        // A full channel already holds an activity report
+        if matches!(tx.try_send(()), Err(TrySendError::Closed(()))) {
+            return;
+        }
+    }
+}
+
+/// Whether the event comes from the user rather than the device's bookkeeping.
+fn is_activity(event: &InputEvent) -> bool {
+    matches!(
+        event.event_type(),
+        EventType::KEY | EventType::ABSOLUTE | EventType::RELATIVE
+    )
+}
+
+/// Whether the device is a touchscreen (a direct touch device, unlike a touchpad).
+fn is_touchscreen(device: &Device) -> bool {
+    let touch = device
+        .supported_keys()
+        .is_some_and(|keys| keys.contains(KeyCode::BTN_TOUCH));
+    let positioned = device.supported_absolute_axes().is_some_and(|axes| {
+        axes.contains(AbsoluteAxisCode::ABS_X) || axes.contains(AbsoluteAxisCode::ABS_MT_POSITION_X)
+    });
+    touch && positioned && device.properties().contains(PropType::DIRECT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use evdev::uinput::VirtualDevice;
+    use evdev::{AbsInfo, AttributeSet, UinputAbsSetup};
+
+    #[test]
+    fn only_user_events_count_as_activity() {
+        let touch = InputEvent::new(EventType::KEY.0, KeyCode::BTN_TOUCH.0, 1);
+        let moved = InputEvent::new(EventType::ABSOLUTE.0, AbsoluteAxisCode::ABS_X.0, 100);
+        let sync = InputEvent::new(EventType::SYNCHRONIZATION.0, 0, 0);
+        let led = InputEvent::new(EventType::LED.0, 0, 1);
+
+        assert!(is_activity(&touch));
+        assert!(is_activity(&moved));
+        assert!(!is_activity(&sync));
+        assert!(!is_activity(&led));
+    }
+
+    /// Tap a uinput virtual touchscreen and watch for activity.
+    ///
+    /// Needs write access to `/dev/uinput` and read access to the new
+    /// `/dev/input/event*` node (e.g. run as root):
+    ///
+    /// ```sh
+    /// cargo test -- --ignored uinput
+    /// ```
+    #[tokio::test]
+    #[ignore = "needs /dev/uinput"]
+    async fn uinput_touchscreen_reports_activity() {
+        let mut keys = AttributeSet::<KeyCode>::new();
+        keys.insert(KeyCode::BTN_TOUCH);
+        let mut properties = AttributeSet::<PropType>::new();
+        properties.insert(PropType::DIRECT);
+        let axis = |code| UinputAbsSetup::new(code, AbsInfo::new(0, 0, 1024, 0, 0, 1));
+        let mut touchscreen = VirtualDevice::builder()
+            .expect("failed to open /dev/uinput")
+            .name("mrpir test touchscreen")
+            .with_keys(&keys)
+            .and_then(|builder| builder.with_absolute_axis(&axis(AbsoluteAxisCode::ABS_X)))
+            .and_then(|builder| builder.with_absolute_axis(&axis(AbsoluteAxisCode::ABS_Y)))
+            .and_then(|builder| builder.with_properties(&properties))
+            .and_then(evdev::uinput::VirtualDeviceBuilder::build)
+            .expect("failed to create virtual touchscreen");
+        let path = touchscreen
+            .enumerate_dev_nodes_blocking()
+            .expect("failed to list device nodes")
+            .find_map(Result::ok)
+            .expect("virtual touchscreen has no event node");
+
+        let config = InputConfig {
+            enabled: true,
+            devices: vec![path],
+            ..InputConfig::default()
+        };
+        let watcher = InputWatcher::open(&config).expect("failed to open virtual touchscreen");
+        assert!(is_touchscreen(&watcher.devices[0].1));
+
+        let (tx, mut rx) = mpsc::channel(1);
+        let shutdown = CancellationToken::new();
+        watcher.spawn(&tx, &shutdown);
+
+        let tap = InputEvent::new(EventType::KEY.0, KeyCode::BTN_TOUCH.0, 1);
+        touchscreen.emit(&[tap]).expect("failed to tap");
+        tokio::time::timeout(Duration::from_secs(1), rx.recv())
+            .await
+            .expect("no activity within 1s")
+            .expect("activity channel closed");
+        shutdown.cancel();
+    }
+}
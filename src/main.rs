@@ -17,24 +17,35 @@
 
 mod app;
 mod config;
+mod diagnostics;
 mod error;
+mod input;
 mod mqtt;
+mod occupancy;
+mod outbox;
+mod remote;
 mod screen;
 mod sensor;
+mod stats;
 mod time_events;
+mod trace;
 
 use anyhow::{anyhow, Context, Result};
 use std::future;
 use std::time::{Duration, Instant};
 use tokio::sync::{mpsc, watch};
 use tokio_util::sync::CancellationToken;
-use tracing::{error, info, warn};
+use tracing::{error, info, info_span, warn, Instrument};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 use app::App;
-use config::Config;
-use mqtt::MqttClient;
-use sensor::PirSensor;
+use config::{Config, SensorBackend, SensorConfig};
+use input::InputWatcher;
+use mqtt::{MqttClient, MqttEvent};
+use outbox::Outbox;
+use sensor::MotionSource;
+use stats::MotionStats;
+use trace::TraceRecorder;
 
 const STATE_REFRESH_INTERVAL: Duration = Duration::from_secs(120);
 const HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(10);
@@ -84,6 +95,16 @@ fn init_logging(config: &Config) {
 /// Main entry point.
 #[tokio::main]
 async fn main() -> Result<()> {
+    let mut args = std::env::args().skip(1);
+    if let Some(command) = args.next() {
+        if command == "replay" {
+            return trace::replay::run_cli(args).await;
+        }
+        return Err(anyhow!(
+            "unknown command {command:?}; usage: mrpir [replay <trace.jsonl> ...]"
+        ));
+    }
+
     // Load configuration
     let config = Config::load().context("Failed to load configuration")?;
 
@@ -93,7 +114,7 @@ async fn main() -> Result<()> {
     info!(version = %env!("CARGO_PKG_VERSION"), "mrpir starting up");
     info!(
         device = %config.device_name,
-        pin = config.sensor.gpio_pin,
+        sensors = config.sensors().len(),
         mqtt_enabled = config.mqtt.enabled,
         screen_enabled = config.screen.enabled,
         "Configuration loaded"
@@ -102,6 +123,46 @@ async fn main() -> Result<()> {
     // Create application
     let mut app = App::new(config.clone());
 
+    // Record a motion trace if configured
+    let trace = match &config.logging.trace_file {
+        Some(path) => TraceRecorder::open(path)
+            .with_context(|| format!("Failed to open trace file {}", path.display()))?,
+        None => TraceRecorder::default(),
+    };
+    app.set_trace(trace.clone());
+
+    // Restore motion statistics
+    if config.stats.enabled {
+        let state_file = config.stats.state_file();
+        let stats = MotionStats::load(&state_file).unwrap_or_else(|e| {
+            warn!(path = %state_file.display(), error = %e, "Failed to load motion statistics; starting from zero");
+            MotionStats::default()
+        });
+        info!(path = %state_file.display(), "Motion statistics state file");
+        app.set_stats(stats, state_file);
+    }
+
+    // Queue motion events, restoring those not yet published from disk
+    if config.mqtt.enabled {
+        let file = config.outbox.file();
+        let max_events = config.outbox.max_events;
+        let outbox = if config.outbox.enabled {
+            match Outbox::load(&file, max_events) {
+                Ok(outbox) => {
+                    info!(path = %file.display(), queued = outbox.len(), "Motion event outbox");
+                    outbox
+                }
+                Err(e) => {
+                    warn!(path = %file.display(), error = %e, "Failed to load motion event outbox; events kept in memory");
+                    Outbox::in_memory(&file, max_events)
+                }
+            }
+        } else {
+            Outbox::in_memory(&file, max_events)
+        };
+        app.set_outbox(outbox);
+    }
+
     // Set up shutdown signal handling with CancellationToken
     let shutdown_token = CancellationToken::new();
     let shutdown_token_signal = shutdown_token.clone();
@@ -139,48 +200,70 @@ async fn main() -> Result<()> {
         shutdown_token_signal.cancel();
     });
 
-    // Set up PIR sensor
-    let (motion_tx, mut motion_rx) = mpsc::channel(10);
-    let (sensor_tick_tx, sensor_tick_rx) = watch::channel(Instant::now());
-    let sensor_shutdown = shutdown_token.clone();
-
-    let sensor = match PirSensor::new(&config.sensor) {
-        Ok(sensor) => sensor,
-        Err(e) => {
-            error!(error = %e, "Failed to initialize PIR sensor");
-            error!("This program requires access to Raspberry Pi GPIO.");
-            error!("Make sure you're running on a Raspberry Pi with proper permissions.");
-            return Err(e.into());
-        }
-    };
+    // Set up motion sensors
+    let sensor_configs = config.sensors();
+    let mut sources: Vec<(Box<dyn MotionSource>, Option<bool>)> =
+        Vec::with_capacity(sensor_configs.len());
 
-    let initial_motion_state = sensor.read();
-    info!(
-        pin = config.sensor.gpio_pin,
-        gpio_level = if initial_motion_state { "high" } else { "low" },
-        motion_state = if initial_motion_state { "ON" } else { "OFF" },
-        "Initial PIR GPIO level"
-    );
-    app.initialize_motion_state(initial_motion_state).await;
+    for (index, sensor_config) in sensor_configs.iter().enumerate() {
+        let sensor = match sensor::open(sensor_config, index, trace.clone()) {
+            Ok(sensor) => sensor,
+            Err(e) => {
+                error!(sensor = %sensor_config.name(), error = %e, "Failed to initialize motion sensor");
+                match sensor_config.backend {
+                    SensorBackend::Rppal => {
+                        error!("This program requires access to Raspberry Pi GPIO.");
+                        error!(
+                            "Make sure you're running on a Raspberry Pi with proper permissions."
+                        );
+                    }
+                    SensorBackend::Cdev => {
+                        error!(chip = %sensor_config.chip, "Check that the GPIO chip and line exist and are readable.");
+                    }
+                    SensorBackend::Ld2410 => {
+                        error!(port = %sensor_config.port, "Check that the serial port exists and is readable (dialout group), and that the UART login console is disabled.");
+                    }
+                }
+                return Err(e.into());
+            }
+        };
 
-    tokio::spawn(async move {
-        sensor
-            .run(
-                motion_tx,
-                sensor_shutdown,
-                sensor_tick_tx,
-                initial_motion_state,
-            )
-            .await;
-    });
+        let initial_motion_state = sensor.initial_state();
+        match initial_motion_state {
+            Some(active) => info!(
+                sensor = %sensor_config.name(),
+                input = %sensor_config.input_id(),
+                polarity = ?sensor_config.polarity,
+                gpio_active = active,
+                motion_state = if active { "ON" } else { "OFF" },
+                "Initial PIR GPIO level"
+            ),
+            None if sensor_config.backend == SensorBackend::Ld2410 => info!(
+                sensor = %sensor_config.name(),
+                input = %sensor_config.input_id(),
+                "Waiting for the first radar report"
+            ),
+            None => info!(
+                sensor = %sensor_config.name(),
+                input = %sensor_config.input_id(),
+                warmup_secs = sensor_config.warmup_secs,
+                "PIR sensor warming up; output ignored until settled"
+            ),
+        }
+        sources.push((sensor, initial_motion_state));
+    }
+    let initial_motion_states: Vec<_> = sources.iter().map(|(_, state)| *state).collect();
+    app.initialize_motion_state(&initial_motion_states).await;
 
     // Set up MQTT if enabled
-    let mut mqtt_rx = None;
-    let mut mqtt_progress_rx = None;
+    let mut mqtt_channels = None;
     if config.mqtt.enabled {
         info!(
             host = %config.mqtt.host,
             port = config.mqtt.port,
+            tls = config.mqtt.tls.enabled,
+            transport = ?config.mqtt.transport,
+            protocol_version = config.mqtt.protocol_version,
             "Connecting to MQTT broker"
         );
 
@@ -189,11 +272,12 @@ async fn main() -> Result<()> {
             &config.device_name,
             config.display_name(),
             &config.client_id(),
+            app.mqtt_entities(),
+            app.mqtt_subscriptions(),
         ) {
             Ok((client, rx, progress_rx)) => {
                 app.mqtt_client = Some(Box::new(client));
-                mqtt_rx = Some(rx);
-                mqtt_progress_rx = Some(progress_rx);
+                mqtt_channels = Some((rx, progress_rx));
                 info!("MQTT client initialized");
 
                 if let Err(e) = app.publish_current_motion_state("startup").await {
@@ -209,6 +293,21 @@ async fn main() -> Result<()> {
         info!("MQTT disabled by configuration");
     }
 
+    // Watch touchscreens and keyboards for activity
+    let mut input_rx = None;
+    if config.input.enabled {
+        match InputWatcher::open(&config.input) {
+            Ok(watcher) => {
+                let (input_tx, rx) = mpsc::channel(1);
+                watcher.spawn(&input_tx, &shutdown_token);
+                input_rx = Some(rx);
+            }
+            Err(e) => {
+                warn!(error = %e, "Input devices not available; touch activity disabled");
+            }
+        }
+    }
+
     // Notify systemd we're ready
     let _ = sd_notify::notify(&[sd_notify::NotifyState::Ready]);
 
@@ -239,12 +338,85 @@ async fn main() -> Result<()> {
         }
     };
 
+    let result = run(
+        &config,
+        &mut app,
+        sources,
+        mqtt_channels,
+        input_rx,
+        &shutdown_token,
+        watchdog_interval,
+    )
+    .await;
+
+    // Graceful shutdown
+    info!("Shutting down...");
+
+    // Cancel any remaining tasks
+    shutdown_token.cancel();
+
+    // Disconnect MQTT gracefully
+    app.shutdown().await;
+
+    // Notify systemd we're stopping
+    let _ = sd_notify::notify(&[sd_notify::NotifyState::Stopping]);
+
+    result?;
+    info!("Goodbye!");
+    Ok(())
+}
+
+/// Spawn the motion sources and run the main event loop.
+///
+/// Returns when `shutdown_token` is cancelled, or with an error on a health
+/// failure so systemd can restart the service.
+async fn run(
+    config: &Config,
+    app: &mut App,
+    sources: Vec<(Box<dyn MotionSource>, Option<bool>)>,
+    mqtt_channels: Option<(mpsc::Receiver<MqttEvent>, watch::Receiver<Instant>)>,
+    mut input_rx: Option<mpsc::Receiver<()>>,
+    shutdown_token: &CancellationToken,
+    watchdog_interval: Option<Duration>,
+) -> Result<()> {
+    let sensor_configs = config.sensors();
+    let (motion_tx, mut motion_rx) = mpsc::channel(10);
+    let mut sensor_tick_rxs = Vec::with_capacity(sources.len());
+    for ((source, initial_motion_state), sensor_config) in sources.into_iter().zip(&sensor_configs)
+    {
+        let (sensor_tick_tx, sensor_tick_rx) = watch::channel(Instant::now());
+        sensor_tick_rxs.push(sensor_tick_rx);
+
+        tokio::spawn(
+            source
+                .run(
+                    motion_tx.clone(),
+                    shutdown_token.clone(),
+                    sensor_tick_tx,
+                    initial_motion_state,
+                )
+                .instrument(info_span!("sensor", name = %sensor_config.name())),
+        );
+    }
+    drop(motion_tx);
+
+    let (mut mqtt_rx, mqtt_progress_rx) = mqtt_channels.unzip();
     let mut mqtt_runtime = MqttRuntimeState::new(config.mqtt.enabled);
     let mut state_refresh = tokio::time::interval_at(
         tokio::time::Instant::now() + STATE_REFRESH_INTERVAL,
         STATE_REFRESH_INTERVAL,
     );
     state_refresh.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+    let stats_interval = Duration::from_secs(config.stats.publish_interval_secs.max(1));
+    let mut stats_publish =
+        tokio::time::interval_at(tokio::time::Instant::now() + stats_interval, stats_interval);
+    stats_publish.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+    let diagnostics_interval = Duration::from_secs(config.diagnostics.publish_interval_secs.max(1));
+    let mut diagnostics_publish = tokio::time::interval_at(
+        tokio::time::Instant::now() + diagnostics_interval,
+        diagnostics_interval,
+    );
+    diagnostics_publish.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
     let mut health_check = tokio::time::interval_at(
         tokio::time::Instant::now() + HEALTH_CHECK_INTERVAL,
         HEALTH_CHECK_INTERVAL,
@@ -260,27 +432,25 @@ async fn main() -> Result<()> {
 
     // Main event loop
     loop {
+        let screen_deadline = app.next_deadline();
         tokio::select! {
             // Check for shutdown
             () = shutdown_token.cancelled() => {
                 info!("Shutdown signal received");
-                break;
+                return Ok(());
             }
 
             // Handle motion events
             motion_event = motion_rx.recv() => {
                 match motion_event {
                     Some(event) => {
-                        if let Err(e) = app.handle_motion(event).await {
+                        if let Err(e) = app.handle_sensor_event(event).await {
                             error!(error = %e, "Error handling motion event");
                         }
                     }
                     None => {
                         let error = anyhow!("PIR sensor event channel closed unexpectedly");
                         error!(error = %error, "Health failure; terminating for systemd restart");
-                        shutdown_token.cancel();
-                        app.shutdown().await;
-                        let _ = sd_notify::notify(&[sd_notify::NotifyState::Stopping]);
                         return Err(error);
                     }
                 }
@@ -296,9 +466,12 @@ async fn main() -> Result<()> {
                 match event {
                     Some(event) => {
                         match &event {
-                            mqtt::MqttEvent::Connected => mqtt_runtime.mark_connected(),
-                            mqtt::MqttEvent::Disconnected => mqtt_runtime.mark_disconnected(),
-                            mqtt::MqttEvent::Error(_) => {}
+                            MqttEvent::Connected => mqtt_runtime.mark_connected(),
+                            MqttEvent::Disconnected => mqtt_runtime.mark_disconnected(),
+                            MqttEvent::Error(_)
+                            | MqttEvent::Message { .. }
+                            | MqttEvent::Command(_)
+                            | MqttEvent::HomeAssistantOnline => {}
                         }
 
                         if let Err(e) = app.handle_mqtt_event(event).await {
@@ -308,14 +481,36 @@ async fn main() -> Result<()> {
                     None => {
                         let error = anyhow!("MQTT event channel closed unexpectedly");
                         error!(error = %error, "Health failure; terminating for systemd restart");
-                        shutdown_token.cancel();
-                        app.shutdown().await;
-                        let _ = sd_notify::notify(&[sd_notify::NotifyState::Stopping]);
                         return Err(error);
                     }
                 }
             }
 
+            // Touchscreen and keyboard activity
+            activity = async {
+                match &mut input_rx {
+                    Some(rx) => rx.recv().await,
+                    None => future::pending().await,
+                }
+            } => {
+                if activity.is_some() {
+                    app.handle_input_activity().await;
+                } else {
+                    warn!("All input devices stopped; touch activity disabled");
+                    input_rx = None;
+                }
+            }
+
+            // Screen timeout after touch input or a screen command
+            () = async {
+                match screen_deadline {
+                    Some(deadline) => tokio::time::sleep_until(deadline.into()).await,
+                    None => future::pending().await,
+                }
+            } => {
+                app.handle_deadline().await;
+            }
+
             // Periodic retained state heartbeat for Home Assistant and broker restarts
             _ = state_refresh.tick(), if config.mqtt.enabled => {
                 if let Err(e) = app.publish_current_motion_state("periodic_refresh").await {
@@ -323,19 +518,27 @@ async fn main() -> Result<()> {
                 }
             }
 
+            // Rolling motion statistics
+            _ = stats_publish.tick(), if config.mqtt.enabled && config.stats.enabled => {
+                app.publish_stats("periodic_refresh").await;
+            }
+
+            // Host diagnostics
+            _ = diagnostics_publish.tick(), if config.mqtt.enabled && config.diagnostics.enabled => {
+                app.publish_diagnostics("periodic_refresh").await;
+            }
+
             // Internal health monitor
             _ = health_check.tick() => {
                 if let Err(e) = check_runtime_health(
-                    &config,
-                    &app,
-                    &sensor_tick_rx,
+                    config,
+                    app,
+                    &sensor_configs,
+                    &sensor_tick_rxs,
                     mqtt_progress_rx.as_ref(),
                     &mqtt_runtime,
                 ) {
                     error!(error = %e, "Health failure; terminating for systemd restart");
-                    shutdown_token.cancel();
-                    app.shutdown().await;
-                    let _ = sd_notify::notify(&[sd_notify::NotifyState::Stopping]);
                     return Err(e);
                 }
             }
@@ -352,38 +555,26 @@ async fn main() -> Result<()> {
             }
         }
     }
-
-    // Graceful shutdown
-    info!("Shutting down...");
-
-    // Cancel any remaining tasks
-    shutdown_token.cancel();
-
-    // Disconnect MQTT gracefully
-    app.shutdown().await;
-
-    // Notify systemd we're stopping
-    let _ = sd_notify::notify(&[sd_notify::NotifyState::Stopping]);
-
-    info!("Goodbye!");
-    Ok(())
 }
 
 fn check_runtime_health(
     config: &Config,
     app: &App,
-    sensor_tick_rx: &watch::Receiver<Instant>,
+    sensor_configs: &[SensorConfig],
+    sensor_tick_rxs: &[watch::Receiver<Instant>],
     mqtt_progress_rx: Option<&watch::Receiver<Instant>>,
     mqtt_runtime: &MqttRuntimeState,
 ) -> Result<()> {
     let now = Instant::now();
-    let last_pir_tick = *sensor_tick_rx.borrow();
-    let pir_elapsed = now.duration_since(last_pir_tick);
-    if pir_elapsed > pir_stall_timeout(config) {
-        return Err(anyhow!(
-            "PIR polling loop stalled: no tick for {:?}",
-            pir_elapsed
-        ));
+    for (sensor_config, sensor_tick_rx) in sensor_configs.iter().zip(sensor_tick_rxs) {
+        let last_pir_tick = *sensor_tick_rx.borrow();
+        let pir_elapsed = now.duration_since(last_pir_tick);
+        if pir_elapsed > pir_stall_timeout(sensor_config) {
+            return Err(anyhow!(
+                "PIR sensor {} loop stalled: no tick for {pir_elapsed:?}",
+                sensor_config.name()
+            ));
+        }
     }
 
     if config.mqtt.enabled {
@@ -413,7 +604,10 @@ fn check_runtime_health(
                 ));
             }
 
-            if app.last_state_publish().is_none() && connected_for > HEALTH_STARTUP_GRACE {
+            if app.last_state_publish().is_none()
+                && !app.warming_up()
+                && connected_for > HEALTH_STARTUP_GRACE
+            {
                 return Err(anyhow!(
                     "MQTT connected for {:?} without successful state publish",
                     connected_for
@@ -443,8 +637,8 @@ fn check_runtime_health(
     Ok(())
 }
 
-fn pir_stall_timeout(config: &Config) -> Duration {
-    let timeout = Duration::from_millis(config.sensor.poll_interval_ms.saturating_mul(20));
+fn pir_stall_timeout(sensor_config: &SensorConfig) -> Duration {
+    let timeout = Duration::from_millis(sensor_config.poll_interval_ms.saturating_mul(20));
     if timeout < Duration::from_secs(30) {
         Duration::from_secs(30)
     } else {
@@ -468,3 +662,95 @@ fn state_publish_stall_timeout() -> Duration {
             .saturating_add(30),
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sensor::{MotionEvent, SimulatedSource};
+
+    fn test_config() -> Config {
+        let mut config = Config::default();
+        config.mqtt.enabled = false;
+        config.screen.enabled = false;
+        config
+    }
+
+    async fn run_to_completion(
+        sources: Vec<(Box<dyn MotionSource>, Option<bool>)>,
+    ) -> (App, Result<()>) {
+        let config = test_config();
+        let mut app = App::new(config.clone());
+        let initial: Vec<_> = sources.iter().map(|(_, state)| *state).collect();
+        app.initialize_motion_state(&initial).await;
+        let result = run(
+            &config,
+            &mut app,
+            sources,
+            None,
+            None,
+            &CancellationToken::new(),
+            None,
+        )
+        .await;
+        (app, result)
+    }
+
+    #[tokio::test]
+    async fn motion_flows_from_source_to_app() {
+        let (source, handle) = SimulatedSource::new(0, Some(false));
+        handle.motion(MotionEvent::Detected);
+        drop(handle);
+
+        let (app, result) = run_to_completion(vec![(Box::new(source), Some(false))]).await;
+
+        // The source closing its channel is a health failure
+        let error = result.expect_err("closed source should stop the loop");
+        assert!(error.to_string().contains("channel closed"));
+        assert!(app.motion_active());
+    }
+
+    #[tokio::test]
+    async fn warming_source_reports_ready_state() {
+        let (source, handle) = SimulatedSource::new(0, None);
+        handle.ready(true);
+        drop(handle);
+
+        let (app, _) = run_to_completion(vec![(Box::new(source), None)]).await;
+
+        assert!(!app.warming_up());
+        assert!(app.motion_active());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn stalled_source_fails_health_check() {
+        let (source, handle) = SimulatedSource::new(0, Some(false));
+        let stale = Instant::now()
+            .checked_sub(Duration::from_secs(60))
+            .expect("clock should be past 60s");
+        handle.tick(stale);
+
+        let (app, result) = run_to_completion(vec![(Box::new(source), Some(false))]).await;
+
+        let error = result.expect_err("stalled source should fail the health check");
+        assert!(!app.motion_active());
+        assert!(error.to_string().contains("loop stalled"));
+        drop(handle);
+    }
+
+    #[test]
+    fn healthy_ticks_pass_health_check() {
+        let config = test_config();
+        let app = App::new(config.clone());
+        let (_tick_tx, tick_rx) = watch::channel(Instant::now());
+
+        check_runtime_health(
+            &config,
+            &app,
+            &config.sensors(),
+            &[tick_rx],
+            None,
+            &MqttRuntimeState::new(false),
+        )
+        .expect("fresh tick should be healthy");
+    }
+}
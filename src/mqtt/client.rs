@@ -1,20 +1,69 @@
 //! MQTT client wrapper using rumqttc.
 
-use rumqttc::{AsyncClient, Event, EventLoop, MqttOptions, Packet, QoS};
+use chrono::{DateTime, Local};
+use serde::Serialize;
 use std::{
     future::Future,
+    hash::{BuildHasher, RandomState},
     pin::Pin,
     time::{Duration, Instant},
 };
 use tokio::sync::{mpsc, watch};
 use tracing::{debug, error, info, warn};
 
-use crate::config::MqttConfig;
+use crate::config::{MqttConfig, MqttStateFormat};
+use crate::diagnostics::DiagnosticsSnapshot;
 use crate::error::MqttError;
-use crate::mqtt::discovery::HaDiscoveryPayload;
+use crate::mqtt::command::{CommandTopics, MqttCommand};
+use crate::mqtt::connection::{Connection, ConnectionLoop, Message, Polled};
+use crate::mqtt::discovery::{DiagnosticSensor, HaDiscoveryPayload, RadarSensor, StatsSensor};
+use crate::mqtt::topics::TopicLayout;
+use crate::outbox::MotionEventMessage;
+use crate::sensor::{RadarReading, SensorFault};
+use crate::stats::StatsSnapshot;
+use crate::time_events::NightModeOverride;
 
 const PUBLISH_TIMEOUT: Duration = Duration::from_secs(5);
 
+/// Payloads published on the availability topic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Availability {
+    /// Daemon running and reporting real sensor state
+    Online,
+    /// Daemon stopped or sensors still warming up (also used as the Last Will)
+    Offline,
+}
+
+impl Availability {
+    /// Payload string for this availability state.
+    #[must_use]
+    pub const fn payload(self) -> &'static str {
+        match self {
+            Self::Online => "online",
+            Self::Offline => "offline",
+        }
+    }
+}
+
+/// The combined motion state, with the context sent in JSON state mode.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MotionState {
+    /// Whether any sensor detects motion
+    pub motion: bool,
+    /// When the motion state last changed
+    pub last_changed: DateTime<Local>,
+    /// Names of the sensors, local and remote, currently detecting motion
+    pub sensors: Vec<String>,
+    /// Whether night mode is active
+    pub night_mode: bool,
+    /// Screen brightness (0-255), if the screen is controlled
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub brightness: Option<u8>,
+    /// Sensors still warming up; `motion` is not known yet
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub initializing: bool,
+}
+
 /// Boxed future used by the MQTT publisher trait.
 pub type MqttPublishFuture<'a> = Pin<Box<dyn Future<Output = Result<(), MqttError>> + Send + 'a>>;
 
@@ -24,22 +73,90 @@ pub trait MqttPublisher: Send + Sync {
     fn publish_discovery<'a>(&'a self, client_id: &'a str) -> MqttPublishFuture<'a>;
 
     /// Publish availability status.
-    fn publish_availability(&self, online: bool) -> MqttPublishFuture<'_>;
+    fn publish_availability(&self, availability: Availability) -> MqttPublishFuture<'_>;
 
     /// Publish motion state.
-    fn publish_motion(&self, motion_detected: bool) -> MqttPublishFuture<'_>;
+    fn publish_motion<'a>(&'a self, state: &'a MotionState) -> MqttPublishFuture<'a>;
+
+    /// Publish motion state for one of several PIR sensors.
+    fn publish_sensor_motion<'a>(
+        &'a self,
+        sensor: &'a str,
+        motion_detected: bool,
+    ) -> MqttPublishFuture<'a>;
+
+    /// Publish the sensor problem state: the faulty sensors by name.
+    fn publish_problem<'a>(&'a self, faults: &'a [(String, SensorFault)]) -> MqttPublishFuture<'a>;
+
+    /// Publish the occupancy state and its confidence.
+    fn publish_occupancy(&self, occupied: bool, confidence: f64) -> MqttPublishFuture<'_>;
+
+    /// Publish target readings for one of the radar sensors.
+    fn publish_radar<'a>(
+        &'a self,
+        sensor: &'a str,
+        reading: &'a RadarReading,
+    ) -> MqttPublishFuture<'a>;
+
+    /// Publish the screen light's state; brightness is 0-255.
+    fn publish_screen(&self, on: bool, brightness: u8) -> MqttPublishFuture<'_>;
+
+    /// Publish the night mode override.
+    fn publish_night_mode(&self, mode: NightModeOverride) -> MqttPublishFuture<'_>;
+
+    /// Publish whether the touchscreen or keyboard is in use.
+    fn publish_touch(&self, active: bool) -> MqttPublishFuture<'_>;
+
+    /// Publish motion statistics.
+    fn publish_stats<'a>(&'a self, stats: &'a StatsSnapshot) -> MqttPublishFuture<'a>;
+
+    /// Publish host diagnostics.
+    fn publish_diagnostics<'a>(
+        &'a self,
+        diagnostics: &'a DiagnosticsSnapshot,
+    ) -> MqttPublishFuture<'a>;
+
+    /// Publish a motion event message.
+    fn publish_motion_event<'a>(&'a self, event: &'a MotionEventMessage) -> MqttPublishFuture<'a>;
 
     /// Gracefully disconnect from MQTT.
     fn disconnect(&self) -> MqttPublishFuture<'_>;
 }
 
+/// Optional entities announced alongside the combined motion sensor.
+#[derive(Debug, Clone, Default)]
+#[allow(clippy::struct_excessive_bools)] // independent per-entity switches
+pub struct MqttEntities {
+    /// Per-sensor motion entities
+    pub sensors: Vec<String>,
+    /// Sensor problem entity
+    pub problem: bool,
+    /// Motion statistics sensors
+    pub stats: bool,
+    /// Occupancy entity
+    pub occupancy: bool,
+    /// Touch activity entity
+    pub touch: bool,
+    /// Radar sensors whose target readings get their own entities
+    pub radars: Vec<String>,
+    /// Screen light entity
+    pub screen: bool,
+    /// Host diagnostic sensors
+    pub diagnostics: bool,
+}
+
 /// MQTT client wrapper for motion sensor publishing.
 pub struct MqttClient {
-    client: AsyncClient,
+    connection: Connection,
+    message_expiry_secs: Option<u32>,
     device_name: String,
     display_name: String,
     ha_prefix: String,
+    topics: TopicLayout,
     ha_discovery: bool,
+    commands: bool,
+    state_format: MqttStateFormat,
+    entities: MqttEntities,
 }
 
 /// Events from the MQTT event loop.
@@ -48,56 +165,167 @@ pub enum MqttEvent {
     Connected,
     Disconnected,
     Error(String),
+    /// A message on one of the subscribed topics
+    Message {
+        topic: String,
+        payload: Vec<u8>,
+    },
+    /// A command from Home Assistant
+    Command(MqttCommand),
+    /// Home Assistant (re)started and needs discovery and state again
+    HomeAssistantOnline,
+}
+
+/// Subscribed topics with a meaning of their own to the client.
+struct IncomingTopics {
+    commands: Option<CommandTopics>,
+    ha_status: Option<HaStatus>,
+}
+
+impl IncomingTopics {
+    /// Turn an incoming message into a command or a plain message event.
+    ///
+    /// Home Assistant status messages are handled here; invalid commands are
+    /// logged and dropped.
+    fn event(&self, message: Message, event_tx: &mpsc::Sender<MqttEvent>) -> Option<MqttEvent> {
+        let Message {
+            topic,
+            payload,
+            retain,
+        } = message;
+        debug!(topic = %topic, payload_len = payload.len(), retain, "MQTT message received");
+        if let Some(ha_status) = self
+            .ha_status
+            .as_ref()
+            .filter(|status| status.topic == topic)
+        {
+            ha_status.announce(&payload, retain, event_tx);
+            return None;
+        }
+        let command = self
+            .commands
+            .as_ref()
+            .and_then(|commands| commands.parse(&topic, &payload));
+        match command {
+            Some(Ok(command)) => Some(MqttEvent::Command(command)),
+            Some(Err(e)) => {
+                warn!(error = %e, "Ignoring invalid Home Assistant command");
+                None
+            }
+            None => Some(MqttEvent::Message { topic, payload }),
+        }
+    }
+}
+
+/// Home Assistant's status topic, watched for its birth message.
+#[derive(Debug, Clone)]
+struct HaStatus {
+    topic: String,
+    max_delay_ms: u64,
+}
+
+impl HaStatus {
+    /// Schedule [`MqttEvent::HomeAssistantOnline`] if `payload` announces
+    /// Home Assistant as online.
+    ///
+    /// The event is sent after a random delay of up to `max_delay_ms`, so a
+    /// fleet of devices doesn't republish all at once. A retained status is
+    /// ignored: it arrives on every subscribe, right after our own connect
+    /// already republished everything.
+    fn announce(&self, payload: &[u8], retain: bool, event_tx: &mpsc::Sender<MqttEvent>) {
+        let status = String::from_utf8_lossy(payload);
+        if retain || status.trim() != "online" {
+            debug!(status = %status.trim(), retain, "Home Assistant status ignored");
+            return;
+        }
+
+        let delay = Duration::from_millis(
+            RandomState::new().hash_one(Instant::now()) % self.max_delay_ms.saturating_add(1),
+        );
+        info!(
+            delay_ms = delay.as_millis(),
+            "Home Assistant online; republishing after delay"
+        );
+        let event_tx = event_tx.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(delay).await;
+            if event_tx.send(MqttEvent::HomeAssistantOnline).await.is_err() {
+                error!("MQTT event receiver closed");
+            }
+        });
+    }
 }
 
 impl MqttClient {
     /// Create a new MQTT client and start the event loop.
     ///
-    /// Returns the client and a receiver for connection events.
+    /// Returns the client and a receiver for connection events and
+    /// messages. `entities` lists what to announce alongside the combined
+    /// motion entity; `subscriptions` are topic filters subscribed to on
+    /// every connect, along with the command topics when commands are
+    /// enabled.
     pub fn new(
         config: &MqttConfig,
         device_name: &str,
         display_name: &str,
         client_id: &str,
+        entities: MqttEntities,
+        subscriptions: Vec<String>,
     ) -> Result<(Self, mpsc::Receiver<MqttEvent>, watch::Receiver<Instant>), MqttError> {
-        let mut options = MqttOptions::new(client_id, &config.host, config.port);
-
-        options.set_keep_alive(Duration::from_secs(config.keep_alive_secs));
-
-        // Set credentials if provided
-        if let (Some(username), Some(password)) = (&config.username, &config.password) {
-            options.set_credentials(username, password);
-        }
-
         // Set last will for availability
-        let availability_topic = format!(
-            "{}/binary_sensor/{}/availability",
-            config.ha_discovery_prefix, device_name
-        );
-        options.set_last_will(rumqttc::LastWill::new(
+        let topics = TopicLayout::new(config, device_name, client_id);
+        let availability_topic = topics.availability();
+        let (connection, eventloop) = Connection::new(
+            config,
+            client_id,
             &availability_topic,
-            b"offline".to_vec(),
-            QoS::AtLeastOnce,
-            true,
-        ));
+            Availability::Offline.payload(),
+        )?;
         info!(topic = %availability_topic, retain = true, "Configured MQTT Last Will");
 
-        let (client, eventloop) = AsyncClient::new(options, 10);
-
         let (event_tx, event_rx) = mpsc::channel(10);
         let (progress_tx, progress_rx) = watch::channel(Instant::now());
 
+        let incoming = IncomingTopics {
+            commands: config.commands.then(|| CommandTopics::new(&topics)),
+            ha_status: config.ha_discovery.then(|| HaStatus {
+                topic: config.ha_status_topic(),
+                max_delay_ms: config.ha_status_max_delay_secs.saturating_mul(1000),
+            }),
+        };
+        let mut subscriptions = subscriptions;
+        if let Some(commands) = &incoming.commands {
+            subscriptions.extend(commands.all().map(str::to_string));
+        }
+        if let Some(ha_status) = &incoming.ha_status {
+            subscriptions.push(ha_status.topic.clone());
+        }
+
         // Spawn the event loop handler
+        let subscriber = connection.clone();
         tokio::spawn(async move {
-            Self::run_eventloop(eventloop, event_tx, progress_tx).await;
+            Self::run_eventloop(
+                eventloop,
+                &subscriber,
+                &subscriptions,
+                &incoming,
+                event_tx,
+                progress_tx,
+            )
+            .await;
         });
 
         let mqtt_client = Self {
-            client,
+            connection,
+            message_expiry_secs: config.message_expiry_secs,
             device_name: device_name.to_string(),
             display_name: display_name.to_string(),
             ha_prefix: config.ha_discovery_prefix.clone(),
+            topics,
             ha_discovery: config.ha_discovery,
+            commands: config.commands,
+            state_format: config.state_format,
+            entities,
         };
 
         Ok((mqtt_client, event_rx, progress_rx))
@@ -105,7 +333,10 @@ impl MqttClient {
 
     /// Run the MQTT event loop.
     async fn run_eventloop(
-        mut eventloop: EventLoop,
+        mut eventloop: ConnectionLoop,
+        connection: &Connection,
+        subscriptions: &[String],
+        incoming: &IncomingTopics,
         event_tx: mpsc::Sender<MqttEvent>,
         progress_tx: watch::Sender<Instant>,
     ) {
@@ -116,49 +347,59 @@ impl MqttClient {
 
         loop {
             match eventloop.poll().await {
-                Ok(Event::Incoming(Packet::ConnAck(ack))) => {
+                Ok(Polled::ConnAck(result)) => {
                     let _ = progress_tx.send(Instant::now());
                     consecutive_errors = 0;
 
-                    if ack.code == rumqttc::ConnectReturnCode::Success {
+                    if let Err(return_code) = result {
+                        warn!(return_code = %return_code, "MQTT connection failed");
+                        if let Err(e) = event_tx
+                            .send(MqttEvent::Error(format!(
+                                "MQTT connection rejected: {return_code}"
+                            )))
+                            .await
+                        {
+                            error!(error = %e, "MQTT event receiver closed");
+                            break;
+                        }
+                    } else {
                         let reconnect = has_connected_once && !connected;
                         info!(reconnect, "MQTT connected successfully");
                         connected = true;
                         has_connected_once = true;
                         reconnect_attempt = 0;
+                        Self::subscribe(connection, subscriptions);
                         if let Err(e) = event_tx.send(MqttEvent::Connected).await {
                             error!(error = %e, "MQTT event receiver closed");
                             break;
                         }
-                    } else {
-                        warn!(return_code = ?ack.code, "MQTT connection failed");
-                        if let Err(e) = event_tx
-                            .send(MqttEvent::Error(format!(
-                                "MQTT connection rejected: {:?}",
-                                ack.code
-                            )))
-                            .await
-                        {
-                            error!(error = %e, "MQTT event receiver closed");
-                            break;
-                        }
                     }
                 }
-                Ok(Event::Incoming(Packet::PubAck(_))) => {
+                Ok(Polled::Publish(message)) => {
+                    let _ = progress_tx.send(Instant::now());
+                    let Some(event) = incoming.event(message, &event_tx) else {
+                        continue;
+                    };
+                    if let Err(e) = event_tx.send(event).await {
+                        error!(error = %e, "MQTT event receiver closed");
+                        break;
+                    }
+                }
+                Ok(Polled::PubAck) => {
                     let _ = progress_tx.send(Instant::now());
                     debug!("MQTT publish acknowledged");
                 }
-                Ok(Event::Incoming(Packet::PingResp)) => {
+                Ok(Polled::PingResp) => {
                     let _ = progress_tx.send(Instant::now());
                     debug!("MQTT ping response");
                 }
-                Ok(Event::Outgoing(_)) => {
+                Ok(Polled::Outgoing) => {
                     let _ = progress_tx.send(Instant::now());
                     // Ignore outgoing events
                 }
-                Ok(event) => {
+                Ok(Polled::Other(event)) => {
                     let _ = progress_tx.send(Instant::now());
-                    debug!(?event, "MQTT event");
+                    debug!(event = %event, "MQTT event");
                 }
                 Err(e) => {
                     let _ = progress_tx.send(Instant::now());
@@ -204,26 +445,53 @@ impl MqttClient {
         }
     }
 
+    /// Subscribe to `topics`; the broker forgets subscriptions on reconnect.
+    fn subscribe(connection: &Connection, topics: &[String]) {
+        if topics.is_empty() {
+            return;
+        }
+        // The event loop is the one draining the request queue, so never wait on it
+        match connection.try_subscribe(topics) {
+            Ok(()) => info!(?topics, "Subscribed to MQTT topics"),
+            Err(e) => warn!(error = %e, "Failed to subscribe to MQTT topics"),
+        }
+    }
+
     async fn publish_payload(
         &self,
         topic: String,
         payload: &str,
         retain: bool,
         publish_type: &'static str,
+    ) -> Result<(), MqttError> {
+        self.publish_expiring(topic, payload, retain, publish_type, None)
+            .await
+    }
+
+    /// Publish with an MQTT 5 message expiry, after which the broker drops
+    /// the message (and a retained one stops being delivered).
+    async fn publish_expiring(
+        &self,
+        topic: String,
+        payload: &str,
+        retain: bool,
+        publish_type: &'static str,
+        expiry_secs: Option<u32>,
     ) -> Result<(), MqttError> {
         info!(
             topic = %topic,
             retain,
             publish_type,
             payload_len = payload.len(),
+            expiry_secs,
             "Queueing MQTT publish"
         );
         debug!(topic = %topic, publish_type, payload = %payload, "MQTT publish payload");
 
         match tokio::time::timeout(
             PUBLISH_TIMEOUT,
-            self.client
-                .publish(&topic, QoS::AtLeastOnce, retain, payload.as_bytes()),
+            self.connection
+                .publish(&topic, payload.as_bytes(), retain, expiry_secs),
         )
         .await
         {
@@ -233,7 +501,7 @@ impl MqttClient {
             }
             Ok(Err(e)) => {
                 warn!(topic = %topic, retain, publish_type, error = %e, "MQTT publish failed");
-                Err(MqttError::PublishFailed(e))
+                Err(e)
             }
             Err(_) => {
                 warn!(
@@ -252,17 +520,155 @@ impl MqttClient {
     }
 
     fn availability_topic(&self) -> String {
-        format!(
-            "{}/binary_sensor/{}/availability",
-            self.ha_prefix, self.device_name
-        )
+        self.topics.availability()
     }
 
     fn state_topic(&self) -> String {
-        format!(
-            "{}/binary_sensor/{}/state",
-            self.ha_prefix, self.device_name
-        )
+        self.topics.motion_state()
+    }
+
+    fn problem_topic(&self, suffix: &str) -> String {
+        self.topics
+            .topic("binary_sensor", &format!("problem/{suffix}"))
+    }
+
+    fn occupancy_topic(&self, suffix: &str) -> String {
+        self.topics
+            .topic("binary_sensor", &format!("occupancy/{suffix}"))
+    }
+
+    fn touch_state_topic(&self) -> String {
+        self.topics.topic("binary_sensor", "touch/state")
+    }
+
+    fn sensor_state_topic(&self, sensor: &str) -> String {
+        self.topics
+            .topic("binary_sensor", &format!("{sensor}/state"))
+    }
+
+    /// Publish discovery config for the screen light and the command-only
+    /// entities.
+    ///
+    /// The light is announced whenever the screen is managed, so its state
+    /// shows even with commands off; its command topic is then ignored.
+    async fn publish_command_discovery(&self, client_id: &str) -> Result<(), MqttError> {
+        let mut entities = Vec::new();
+        if self.commands {
+            entities.extend([
+                (
+                    "select",
+                    "night_mode",
+                    HaDiscoveryPayload::night_mode_select(
+                        &self.device_name,
+                        &self.display_name,
+                        client_id,
+                        &self.topics,
+                    ),
+                ),
+                (
+                    "button",
+                    "simulate_motion",
+                    HaDiscoveryPayload::simulate_motion_button(
+                        &self.device_name,
+                        &self.display_name,
+                        client_id,
+                        &self.topics,
+                    ),
+                ),
+            ]);
+        }
+        if self.entities.screen {
+            entities.push((
+                "light",
+                "screen",
+                HaDiscoveryPayload::screen_light(
+                    &self.device_name,
+                    &self.display_name,
+                    client_id,
+                    &self.topics,
+                ),
+            ));
+        }
+
+        for (component, entity, payload) in entities {
+            let topic = HaDiscoveryPayload::entity_config_topic(
+                component,
+                &self.device_name,
+                entity,
+                &self.ha_prefix,
+            );
+            let json = payload
+                .to_json()
+                .map_err(|e| MqttError::InvalidConfig(e.to_string()))?;
+
+            info!(topic = %topic, entity, retain = true, "Publishing HA command entity discovery");
+            debug!(payload = %json, "Discovery payload");
+            self.publish_payload(topic, &json, true, "discovery")
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Publish discovery config for the host diagnostic sensors.
+    async fn publish_diagnostics_discovery(&self, client_id: &str) -> Result<(), MqttError> {
+        if !self.entities.diagnostics {
+            return Ok(());
+        }
+
+        for diagnostic in DiagnosticSensor::ALL {
+            let payload = HaDiscoveryPayload::diagnostic_sensor(
+                &self.device_name,
+                &self.display_name,
+                diagnostic,
+                client_id,
+                &self.topics,
+            );
+            let topic = HaDiscoveryPayload::diagnostic_config_topic(
+                &self.device_name,
+                diagnostic,
+                &self.ha_prefix,
+            );
+            let json = payload
+                .to_json()
+                .map_err(|e| MqttError::InvalidConfig(e.to_string()))?;
+
+            info!(topic = %topic, diagnostic = diagnostic.key(), retain = true, "Publishing HA diagnostic discovery");
+            debug!(payload = %json, "Discovery payload");
+            self.publish_payload(topic, &json, true, "discovery")
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Publish discovery config for every reading of every radar sensor.
+    async fn publish_radar_discovery(&self, client_id: &str) -> Result<(), MqttError> {
+        for radar in &self.entities.radars {
+            for reading in RadarSensor::ALL {
+                let payload = HaDiscoveryPayload::radar_sensor(
+                    &self.device_name,
+                    &self.display_name,
+                    radar,
+                    reading,
+                    client_id,
+                    &self.topics,
+                );
+                let topic = HaDiscoveryPayload::radar_config_topic(
+                    &self.device_name,
+                    radar,
+                    reading,
+                    &self.ha_prefix,
+                );
+                let json = payload
+                    .to_json()
+                    .map_err(|e| MqttError::InvalidConfig(e.to_string()))?;
+
+                info!(topic = %topic, sensor = %radar, reading = reading.key(), retain = true, "Publishing HA radar discovery");
+                debug!(payload = %json, "Discovery payload");
+                self.publish_payload(topic, &json, true, "discovery")
+                    .await?;
+            }
+        }
+        Ok(())
     }
 
     /// Publish Home Assistant discovery config.
@@ -272,12 +678,35 @@ impl MqttClient {
             return Ok(());
         }
 
-        let payload = HaDiscoveryPayload::motion_sensor(
+        for sensor in &self.entities.sensors {
+            let payload = HaDiscoveryPayload::sensor_motion(
+                &self.device_name,
+                &self.display_name,
+                sensor,
+                client_id,
+                &self.topics,
+            );
+            let topic =
+                HaDiscoveryPayload::sensor_config_topic(&self.device_name, sensor, &self.ha_prefix);
+            let json = payload
+                .to_json()
+                .map_err(|e| MqttError::InvalidConfig(e.to_string()))?;
+
+            info!(topic = %topic, sensor = %sensor, retain = true, "Publishing HA sensor discovery");
+            debug!(payload = %json, "Discovery payload");
+            self.publish_payload(topic, &json, true, "discovery")
+                .await?;
+        }
+
+        let mut payload = HaDiscoveryPayload::motion_sensor(
             &self.device_name,
             &self.display_name,
             client_id,
-            &self.ha_prefix,
+            &self.topics,
         );
+        if self.state_format == MqttStateFormat::Json {
+            payload = payload.with_json_state();
+        }
 
         let topic = HaDiscoveryPayload::config_topic(&self.device_name, &self.ha_prefix);
         let json = payload
@@ -287,32 +716,261 @@ impl MqttClient {
         info!(topic = %topic, retain = true, "Publishing HA discovery");
         debug!(payload = %json, "Discovery payload");
 
+        self.publish_payload(topic, &json, true, "discovery")
+            .await?;
+
+        if self.entities.problem {
+            let payload = HaDiscoveryPayload::problem_sensor(
+                &self.device_name,
+                &self.display_name,
+                client_id,
+                &self.topics,
+            );
+            self.publish_entity_discovery("problem", &payload).await?;
+        }
+
+        if self.entities.occupancy {
+            let payload = HaDiscoveryPayload::occupancy_sensor(
+                &self.device_name,
+                &self.display_name,
+                client_id,
+                &self.topics,
+            );
+            self.publish_entity_discovery("occupancy", &payload).await?;
+        }
+
+        if self.entities.touch {
+            let payload = HaDiscoveryPayload::touch_sensor(
+                &self.device_name,
+                &self.display_name,
+                client_id,
+                &self.topics,
+            );
+            self.publish_entity_discovery("touch", &payload).await?;
+        }
+
+        self.publish_radar_discovery(client_id).await?;
+        self.publish_command_discovery(client_id).await?;
+        self.publish_diagnostics_discovery(client_id).await?;
+
+        if self.entities.stats {
+            for stat in StatsSensor::ALL {
+                let payload = HaDiscoveryPayload::stats_sensor(
+                    &self.device_name,
+                    &self.display_name,
+                    stat,
+                    client_id,
+                    &self.topics,
+                );
+                let topic = HaDiscoveryPayload::stats_config_topic(
+                    &self.device_name,
+                    stat,
+                    &self.ha_prefix,
+                );
+                let json = payload
+                    .to_json()
+                    .map_err(|e| MqttError::InvalidConfig(e.to_string()))?;
+
+                info!(topic = %topic, stat = stat.key(), retain = true, "Publishing HA stats discovery");
+                debug!(payload = %json, "Discovery payload");
+                self.publish_payload(topic, &json, true, "discovery")
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Publish the discovery config of a device-level binary sensor.
+    async fn publish_entity_discovery(
+        &self,
+        entity: &str,
+        payload: &HaDiscoveryPayload,
+    ) -> Result<(), MqttError> {
+        let topic =
+            HaDiscoveryPayload::sensor_config_topic(&self.device_name, entity, &self.ha_prefix);
+        let json = payload
+            .to_json()
+            .map_err(|e| MqttError::InvalidConfig(e.to_string()))?;
+
+        info!(topic = %topic, entity, retain = true, "Publishing HA entity discovery");
+        debug!(payload = %json, "Discovery payload");
         self.publish_payload(topic, &json, true, "discovery").await
     }
 
     /// Publish availability status.
-    pub async fn publish_availability(&self, online: bool) -> Result<(), MqttError> {
+    pub async fn publish_availability(&self, availability: Availability) -> Result<(), MqttError> {
         let topic = self.availability_topic();
-        let payload = if online { "online" } else { "offline" };
+        let payload = availability.payload();
 
         info!(topic = %topic, payload, retain = true, "Publishing availability");
         self.publish_payload(topic, payload, true, "availability")
             .await
     }
 
-    /// Publish motion state.
-    pub async fn publish_motion(&self, motion_detected: bool) -> Result<(), MqttError> {
+    /// Publish motion state, as `ON`/`OFF` or as JSON per `mqtt.state_format`.
+    pub async fn publish_motion(&self, state: &MotionState) -> Result<(), MqttError> {
         let topic = self.state_topic();
+        let payload = match self.state_format {
+            MqttStateFormat::Plain => if state.motion { "ON" } else { "OFF" }.to_string(),
+            MqttStateFormat::Json => {
+                serde_json::to_string(state).map_err(|e| MqttError::InvalidConfig(e.to_string()))?
+            }
+        };
+
+        info!(
+            topic = %topic,
+            payload = %payload,
+            retain = true,
+            "Publishing motion state"
+        );
+
+        self.publish_expiring(topic, &payload, true, "state", self.message_expiry_secs)
+            .await
+    }
+
+    /// Publish motion state for one of several PIR sensors.
+    pub async fn publish_sensor_motion(
+        &self,
+        sensor: &str,
+        motion_detected: bool,
+    ) -> Result<(), MqttError> {
+        let topic = self.sensor_state_topic(sensor);
         let payload = if motion_detected { "ON" } else { "OFF" };
 
         info!(
             topic = %topic,
+            sensor,
             payload,
             retain = true,
-            "Publishing motion state"
+            "Publishing sensor motion state"
         );
 
-        self.publish_payload(topic, payload, true, "state").await
+        self.publish_expiring(
+            topic,
+            payload,
+            true,
+            "sensor_state",
+            self.message_expiry_secs,
+        )
+        .await
+    }
+
+    /// Publish the sensor problem state and which sensors are faulty.
+    pub async fn publish_problem(&self, faults: &[(String, SensorFault)]) -> Result<(), MqttError> {
+        let attributes: serde_json::Map<String, serde_json::Value> = faults
+            .iter()
+            .map(|(sensor, fault)| (sensor.clone(), fault.as_str().into()))
+            .collect();
+        let attributes = serde_json::to_string(&attributes)
+            .map_err(|e| MqttError::InvalidConfig(e.to_string()))?;
+        self.publish_payload(
+            self.problem_topic("attributes"),
+            &attributes,
+            true,
+            "problem_attributes",
+        )
+        .await?;
+
+        let topic = self.problem_topic("state");
+        let payload = if faults.is_empty() { "OFF" } else { "ON" };
+        info!(topic = %topic, payload, retain = true, "Publishing sensor problem state");
+        self.publish_payload(topic, payload, true, "problem").await
+    }
+
+    /// Publish the occupancy state, with its confidence as an attribute.
+    pub async fn publish_occupancy(
+        &self,
+        occupied: bool,
+        confidence: f64,
+    ) -> Result<(), MqttError> {
+        let attributes = serde_json::json!({ "confidence": (confidence * 100.0).round() / 100.0 });
+        self.publish_payload(
+            self.occupancy_topic("attributes"),
+            &attributes.to_string(),
+            true,
+            "occupancy_attributes",
+        )
+        .await?;
+
+        let topic = self.occupancy_topic("state");
+        let payload = if occupied { "ON" } else { "OFF" };
+        info!(topic = %topic, payload, retain = true, "Publishing occupancy state");
+        self.publish_payload(topic, payload, true, "occupancy")
+            .await
+    }
+
+    /// Publish a radar's target readings as one JSON document.
+    pub async fn publish_radar(
+        &self,
+        sensor: &str,
+        reading: &RadarReading,
+    ) -> Result<(), MqttError> {
+        let topic = HaDiscoveryPayload::radar_state_topic(&self.topics, sensor);
+        let payload =
+            serde_json::to_string(reading).map_err(|e| MqttError::InvalidConfig(e.to_string()))?;
+
+        debug!(topic = %topic, payload = %payload, "Publishing radar readings");
+        self.publish_payload(topic, &payload, true, "radar").await
+    }
+
+    /// Publish the screen light's JSON state.
+    pub async fn publish_screen(&self, on: bool, brightness: u8) -> Result<(), MqttError> {
+        let topic = HaDiscoveryPayload::screen_state_topic(&self.topics);
+        let payload = serde_json::json!({
+            "state": if on { "ON" } else { "OFF" },
+            "brightness": brightness,
+        })
+        .to_string();
+
+        debug!(topic = %topic, payload = %payload, "Publishing screen state");
+        self.publish_payload(topic, &payload, true, "screen").await
+    }
+
+    /// Publish the night mode override.
+    pub async fn publish_night_mode(&self, mode: NightModeOverride) -> Result<(), MqttError> {
+        let topic = HaDiscoveryPayload::night_mode_state_topic(&self.topics);
+        self.publish_payload(topic, mode.as_str(), true, "night_mode")
+            .await
+    }
+
+    /// Publish the touch activity state.
+    pub async fn publish_touch(&self, active: bool) -> Result<(), MqttError> {
+        let topic = self.touch_state_topic();
+        let payload = if active { "ON" } else { "OFF" };
+        info!(topic = %topic, payload, retain = true, "Publishing touch activity state");
+        self.publish_payload(topic, payload, true, "touch").await
+    }
+
+    /// Publish motion statistics as one JSON document.
+    pub async fn publish_stats(&self, stats: &StatsSnapshot) -> Result<(), MqttError> {
+        let topic = HaDiscoveryPayload::stats_state_topic(&self.topics);
+        let payload =
+            serde_json::to_string(stats).map_err(|e| MqttError::InvalidConfig(e.to_string()))?;
+
+        self.publish_payload(topic, &payload, true, "stats").await
+    }
+
+    /// Publish host diagnostics as JSON.
+    pub async fn publish_diagnostics(
+        &self,
+        diagnostics: &DiagnosticsSnapshot,
+    ) -> Result<(), MqttError> {
+        let topic = HaDiscoveryPayload::diagnostics_state_topic(&self.topics);
+        let payload = serde_json::to_string(diagnostics)
+            .map_err(|e| MqttError::InvalidConfig(e.to_string()))?;
+
+        self.publish_payload(topic, &payload, true, "diagnostics")
+            .await
+    }
+
+    /// Publish a motion event message; events are not retained.
+    pub async fn publish_motion_event(&self, event: &MotionEventMessage) -> Result<(), MqttError> {
+        let topic = HaDiscoveryPayload::motion_events_topic(&self.topics);
+        let payload =
+            serde_json::to_string(event).map_err(|e| MqttError::InvalidConfig(e.to_string()))?;
+
+        self.publish_payload(topic, &payload, false, "event").await
     }
 
     /// Gracefully disconnect from the broker.
@@ -320,12 +978,11 @@ impl MqttClient {
         info!("Disconnecting from MQTT broker");
 
         // Publish offline status before disconnecting
-        if let Err(e) = self.publish_availability(false).await {
+        if let Err(e) = self.publish_availability(Availability::Offline).await {
             warn!("Failed to publish offline status: {}", e);
         }
 
-        self.client.disconnect().await?;
-        Ok(())
+        self.connection.disconnect().await
     }
 }
 
@@ -334,12 +991,63 @@ impl MqttPublisher for MqttClient {
         Box::pin(Self::publish_discovery(self, client_id))
     }
 
-    fn publish_availability(&self, online: bool) -> MqttPublishFuture<'_> {
-        Box::pin(Self::publish_availability(self, online))
+    fn publish_availability(&self, availability: Availability) -> MqttPublishFuture<'_> {
+        Box::pin(Self::publish_availability(self, availability))
+    }
+
+    fn publish_motion<'a>(&'a self, state: &'a MotionState) -> MqttPublishFuture<'a> {
+        Box::pin(Self::publish_motion(self, state))
+    }
+
+    fn publish_sensor_motion<'a>(
+        &'a self,
+        sensor: &'a str,
+        motion_detected: bool,
+    ) -> MqttPublishFuture<'a> {
+        Box::pin(Self::publish_sensor_motion(self, sensor, motion_detected))
+    }
+
+    fn publish_problem<'a>(&'a self, faults: &'a [(String, SensorFault)]) -> MqttPublishFuture<'a> {
+        Box::pin(Self::publish_problem(self, faults))
+    }
+
+    fn publish_occupancy(&self, occupied: bool, confidence: f64) -> MqttPublishFuture<'_> {
+        Box::pin(Self::publish_occupancy(self, occupied, confidence))
+    }
+
+    fn publish_radar<'a>(
+        &'a self,
+        sensor: &'a str,
+        reading: &'a RadarReading,
+    ) -> MqttPublishFuture<'a> {
+        Box::pin(Self::publish_radar(self, sensor, reading))
+    }
+
+    fn publish_screen(&self, on: bool, brightness: u8) -> MqttPublishFuture<'_> {
+        Box::pin(Self::publish_screen(self, on, brightness))
+    }
+
+    fn publish_night_mode(&self, mode: NightModeOverride) -> MqttPublishFuture<'_> {
+        Box::pin(Self::publish_night_mode(self, mode))
+    }
+
+    fn publish_touch(&self, active: bool) -> MqttPublishFuture<'_> {
+        Box::pin(Self::publish_touch(self, active))
+    }
+
+    fn publish_stats<'a>(&'a self, stats: &'a StatsSnapshot) -> MqttPublishFuture<'a> {
+        Box::pin(Self::publish_stats(self, stats))
     }
 
-    fn publish_motion(&self, motion_detected: bool) -> MqttPublishFuture<'_> {
-        Box::pin(Self::publish_motion(self, motion_detected))
+    fn publish_diagnostics<'a>(
+        &'a self,
+        diagnostics: &'a DiagnosticsSnapshot,
+    ) -> MqttPublishFuture<'a> {
+        Box::pin(Self::publish_diagnostics(self, diagnostics))
+    }
+
+    fn publish_motion_event<'a>(&'a self, event: &'a MotionEventMessage) -> MqttPublishFuture<'a> {
+        Box::pin(Self::publish_motion_event(self, event))
     }
 
     fn disconnect(&self) -> MqttPublishFuture<'_> {
@@ -350,21 +1058,47 @@ impl MqttPublisher for MqttClient {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use chrono::TimeZone;
     use rumqttc::Request;
 
     fn test_client() -> (MqttClient, flume::Receiver<Request>) {
         let (tx, rx) = flume::bounded(10);
         let client = MqttClient {
-            client: AsyncClient::from_senders(tx),
+            connection: Connection::V4(rumqttc::AsyncClient::from_senders(tx)),
+            message_expiry_secs: None,
             device_name: "officescreen".to_string(),
             display_name: "Office Screen".to_string(),
             ha_prefix: "homeassistant".to_string(),
+            topics: TopicLayout::with_hostname(
+                &MqttConfig::default(),
+                "officescreen",
+                "mrpir",
+                "pi4",
+            ),
             ha_discovery: true,
+            commands: false,
+            state_format: MqttStateFormat::Plain,
+            entities: MqttEntities::default(),
         };
 
         (client, rx)
     }
 
+    fn motion_state(motion: bool) -> MotionState {
+        MotionState {
+            motion,
+            last_changed: chrono::Utc
+                .timestamp_opt(1_700_000_000, 0)
+                .single()
+                .expect("valid timestamp")
+                .into(),
+            sensors: vec!["pir".to_string()],
+            night_mode: true,
+            brightness: Some(64),
+            initializing: false,
+        }
+    }
+
     async fn next_publish(rx: &flume::Receiver<Request>) -> rumqttc::mqttbytes::v4::Publish {
         match rx.recv_async().await.expect("request should be queued") {
             Request::Publish(publish) => publish,
@@ -372,12 +1106,72 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn home_assistant_birth_message_schedules_a_republish() {
+        let status = HaStatus {
+            topic: "homeassistant/status".to_string(),
+            max_delay_ms: 0,
+        };
+        let (event_tx, mut event_rx) = mpsc::channel(10);
+
+        status.announce(b"online", true, &event_tx);
+        status.announce(b"offline", false, &event_tx);
+        status.announce(b"online\n", false, &event_tx);
+
+        let event = tokio::time::timeout(Duration::from_secs(1), event_rx.recv())
+            .await
+            .expect("republish should be scheduled");
+        assert!(matches!(event, Some(MqttEvent::HomeAssistantOnline)));
+        tokio::task::yield_now().await;
+        assert!(event_rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn motion_events_are_not_retained() {
+        let (client, rx) = test_client();
+        let event = MotionEventMessage {
+            motion: false,
+            timestamp: chrono::Utc
+                .timestamp_opt(1_700_000_000, 0)
+                .single()
+                .expect("valid timestamp")
+                .into(),
+            sequence: 7,
+            reason: "pir_cleared".to_string(),
+            duration_secs: Some(42.5),
+        };
+
+        client
+            .publish_motion_event(&event)
+            .await
+            .expect("event publish should queue");
+
+        let publish = next_publish(&rx).await;
+        assert_eq!(
+            publish.topic,
+            "homeassistant/binary_sensor/officescreen/events"
+        );
+        assert!(!publish.retain);
+        let payload: serde_json::Value =
+            serde_json::from_slice(&publish.payload).expect("event payload is JSON");
+        assert_eq!(payload["motion"], false);
+        assert_eq!(payload["sequence"], 7);
+        assert_eq!(payload["reason"], "pir_cleared");
+        assert_eq!(payload["duration_secs"], 42.5);
+        let timestamp: chrono::DateTime<chrono::Utc> = payload["timestamp"]
+            .as_str()
+            .expect("timestamp string")
+            .parse()
+            .expect("RFC 3339 timestamp");
+        assert_eq!(timestamp.timestamp(), 1_700_000_000);
+    }
+
     #[tokio::test]
     async fn publish_motion_uses_retained_home_assistant_payload() {
         let (client, rx) = test_client();
 
         client
-            .publish_motion(true)
+            .publish_motion(&motion_state(true))
             .await
             .expect("motion publish should queue");
 
@@ -391,15 +1185,109 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn publish_discovery_and_availability_are_retained() {
+    async fn json_state_format_publishes_motion_with_context() {
         let (client, rx) = test_client();
+        let client = MqttClient {
+            state_format: MqttStateFormat::Json,
+            ..client
+        };
+
+        client
+            .publish_motion(&motion_state(false))
+            .await
+            .expect("motion publish should queue");
+
+        let publish = next_publish(&rx).await;
+        assert_eq!(
+            publish.topic,
+            "homeassistant/binary_sensor/officescreen/state"
+        );
+        assert!(publish.retain);
+        let payload: serde_json::Value =
+            serde_json::from_slice(&publish.payload).expect("state payload is JSON");
+        assert_eq!(payload["motion"], false);
+        assert_eq!(payload["sensors"], serde_json::json!(["pir"]));
+        assert_eq!(payload["night_mode"], true);
+        assert_eq!(payload["brightness"], 64);
+        let last_changed: chrono::DateTime<chrono::Utc> = payload["last_changed"]
+            .as_str()
+            .expect("timestamp string")
+            .parse()
+            .expect("RFC 3339 timestamp");
+        assert_eq!(last_changed.timestamp(), 1_700_000_000);
 
         client
             .publish_discovery("mrpir-officescreen")
             .await
             .expect("discovery publish should queue");
+        let discovery = std::iter::from_fn(|| rx.try_recv().ok())
+            .find_map(|request| match request {
+                Request::Publish(publish)
+                    if publish.topic == "homeassistant/binary_sensor/officescreen/config" =>
+                {
+                    Some(publish)
+                }
+                _ => None,
+            })
+            .expect("motion discovery should be published");
+        let discovery: serde_json::Value =
+            serde_json::from_slice(&discovery.payload).expect("discovery payload is JSON");
+        assert_eq!(
+            discovery["json_attributes_topic"],
+            "homeassistant/binary_sensor/officescreen/state"
+        );
+        assert!(discovery["value_template"].is_string());
+    }
+
+    #[tokio::test]
+    async fn mqtt5_motion_states_expire_and_carry_user_properties() {
+        let (tx, rx) = flume::bounded(10);
+        let user_properties = vec![("room".to_string(), "office".to_string())];
+        let client = MqttClient {
+            connection: Connection::V5 {
+                client: rumqttc::v5::AsyncClient::from_senders(tx),
+                user_properties: user_properties.clone(),
+            },
+            message_expiry_secs: Some(300),
+            ..test_client().0
+        };
+        let next_publish = || async {
+            match rx.recv_async().await.expect("request should be queued") {
+                rumqttc::v5::Request::Publish(publish) => publish,
+                request => panic!("expected publish request, got {request:?}"),
+            }
+        };
+
         client
-            .publish_availability(true)
+            .publish_motion(&motion_state(true))
+            .await
+            .expect("motion publish should queue");
+        let publish = next_publish().await;
+        assert_eq!(&publish.payload[..], b"ON");
+        let properties = publish.properties.expect("MQTT 5 properties");
+        assert_eq!(properties.message_expiry_interval, Some(300));
+        assert_eq!(properties.user_properties, user_properties);
+
+        client
+            .publish_availability(Availability::Online)
+            .await
+            .expect("availability publish should queue");
+        let properties = next_publish().await.properties.expect("MQTT 5 properties");
+        assert_eq!(properties.message_expiry_interval, None);
+        assert_eq!(properties.user_properties, user_properties);
+    }
+
+    #[tokio::test]
+    async fn publish_discovery_and_availability_are_retained() {
+        let (mut client, rx) = test_client();
+        client.entities.problem = true;
+
+        client
+            .publish_discovery("mrpir-officescreen")
+            .await
+            .expect("discovery publish should queue");
+        client
+            .publish_availability(Availability::Online)
             .await
             .expect("availability publish should queue");
 
@@ -410,6 +1298,12 @@ mod tests {
         );
         assert!(discovery.retain);
 
+        let problem = next_publish(&rx).await;
+        assert_eq!(
+            problem.topic,
+            "homeassistant/binary_sensor/officescreen/problem/config"
+        );
+
         let availability = next_publish(&rx).await;
         assert_eq!(
             availability.topic,
@@ -418,4 +1312,289 @@ mod tests {
         assert_eq!(&availability.payload[..], b"online");
         assert!(availability.retain);
     }
+
+    #[tokio::test]
+    async fn multiple_sensors_get_their_own_entities() {
+        let (mut client, rx) = test_client();
+        client.entities.sensors = vec!["door".to_string(), "window".to_string()];
+
+        client
+            .publish_discovery("mrpir-officescreen")
+            .await
+            .expect("discovery publish should queue");
+        client
+            .publish_sensor_motion("window", true)
+            .await
+            .expect("sensor motion publish should queue");
+
+        let door = next_publish(&rx).await;
+        assert_eq!(
+            door.topic,
+            "homeassistant/binary_sensor/officescreen/door/config"
+        );
+        let window = next_publish(&rx).await;
+        assert_eq!(
+            window.topic,
+            "homeassistant/binary_sensor/officescreen/window/config"
+        );
+        let combined = next_publish(&rx).await;
+        assert_eq!(
+            combined.topic,
+            "homeassistant/binary_sensor/officescreen/config"
+        );
+
+        let state = next_publish(&rx).await;
+        assert_eq!(
+            state.topic,
+            "homeassistant/binary_sensor/officescreen/window/state"
+        );
+        assert_eq!(&state.payload[..], b"ON");
+        assert!(state.retain);
+    }
+
+    #[tokio::test]
+    async fn publish_problem_names_faulty_sensors() {
+        let (client, rx) = test_client();
+
+        client
+            .publish_problem(&[("door".to_string(), SensorFault::StuckHigh)])
+            .await
+            .expect("problem publish should queue");
+
+        let attributes = next_publish(&rx).await;
+        assert_eq!(
+            attributes.topic,
+            "homeassistant/binary_sensor/officescreen/problem/attributes"
+        );
+        assert_eq!(&attributes.payload[..], br#"{"door":"stuck_high"}"#);
+
+        let state = next_publish(&rx).await;
+        assert_eq!(
+            state.topic,
+            "homeassistant/binary_sensor/officescreen/problem/state"
+        );
+        assert_eq!(&state.payload[..], b"ON");
+        assert!(state.retain);
+    }
+
+    #[tokio::test]
+    async fn stats_are_announced_and_published_as_json() {
+        let (mut client, rx) = test_client();
+        client.entities.stats = true;
+
+        client
+            .publish_discovery("mrpir-officescreen")
+            .await
+            .expect("discovery publish should queue");
+        let mut topics = Vec::new();
+        while let Ok(Request::Publish(publish)) = rx.try_recv() {
+            topics.push(publish.topic);
+        }
+        assert_eq!(
+            topics[1..],
+            [
+                "homeassistant/sensor/officescreen/last_motion/config",
+                "homeassistant/sensor/officescreen/motion_events_today/config",
+                "homeassistant/sensor/officescreen/active_minutes_last_hour/config",
+            ]
+        );
+
+        client
+            .publish_stats(&StatsSnapshot {
+                last_motion: None,
+                motion_events_today: 3,
+                active_minutes_last_hour: 12.5,
+            })
+            .await
+            .expect("stats publish should queue");
+        let stats = next_publish(&rx).await;
+        assert_eq!(stats.topic, "homeassistant/sensor/officescreen/stats/state");
+        assert_eq!(
+            &stats.payload[..],
+            br#"{"last_motion":null,"motion_events_today":3,"active_minutes_last_hour":12.5}"#
+        );
+        assert!(stats.retain);
+    }
+
+    #[tokio::test]
+    async fn occupancy_is_published_with_confidence() {
+        let (mut client, rx) = test_client();
+        client.entities.occupancy = true;
+
+        client
+            .publish_discovery("mrpir-officescreen")
+            .await
+            .expect("discovery publish should queue");
+        let mut topics = Vec::new();
+        while let Ok(Request::Publish(publish)) = rx.try_recv() {
+            topics.push(publish.topic);
+        }
+        assert_eq!(
+            topics.last().map(String::as_str),
+            Some("homeassistant/binary_sensor/officescreen/occupancy/config")
+        );
+
+        client
+            .publish_occupancy(true, 2.346)
+            .await
+            .expect("occupancy publish should queue");
+        let attributes = next_publish(&rx).await;
+        assert_eq!(&attributes.payload[..], br#"{"confidence":2.35}"#);
+        let state = next_publish(&rx).await;
+        assert_eq!(
+            state.topic,
+            "homeassistant/binary_sensor/officescreen/occupancy/state"
+        );
+        assert_eq!(&state.payload[..], b"ON");
+    }
+
+    #[tokio::test]
+    async fn touch_activity_is_announced_and_published() {
+        let (mut client, rx) = test_client();
+        client.entities.touch = true;
+
+        client
+            .publish_discovery("mrpir-officescreen")
+            .await
+            .expect("discovery publish should queue");
+        let mut topics = Vec::new();
+        while let Ok(Request::Publish(publish)) = rx.try_recv() {
+            topics.push(publish.topic);
+        }
+        assert_eq!(
+            topics.last().map(String::as_str),
+            Some("homeassistant/binary_sensor/officescreen/touch/config")
+        );
+
+        client
+            .publish_touch(true)
+            .await
+            .expect("touch publish should queue");
+        let state = next_publish(&rx).await;
+        assert_eq!(
+            state.topic,
+            "homeassistant/binary_sensor/officescreen/touch/state"
+        );
+        assert_eq!(&state.payload[..], b"ON");
+        assert!(state.retain);
+    }
+
+    #[tokio::test]
+    async fn screen_light_is_announced_without_commands() {
+        let (mut client, rx) = test_client();
+        client.entities.screen = true;
+
+        client
+            .publish_discovery("mrpir-officescreen")
+            .await
+            .expect("discovery publish should queue");
+        let mut topics = Vec::new();
+        while let Ok(Request::Publish(publish)) = rx.try_recv() {
+            topics.push(publish.topic);
+        }
+        assert_eq!(
+            topics.last().map(String::as_str),
+            Some("homeassistant/light/officescreen/screen/config")
+        );
+        assert!(!topics.iter().any(|topic| topic.contains("night_mode")));
+
+        client
+            .publish_screen(true, 128)
+            .await
+            .expect("screen publish should queue");
+        let state = next_publish(&rx).await;
+        assert_eq!(state.topic, "homeassistant/light/officescreen/screen/state");
+        let json: serde_json::Value =
+            serde_json::from_slice(&state.payload).expect("screen state should be JSON");
+        assert_eq!(json["state"], "ON");
+        assert_eq!(json["brightness"], 128);
+        assert!(state.retain);
+    }
+
+    #[tokio::test]
+    async fn diagnostics_are_announced_and_published_as_json() {
+        let (mut client, rx) = test_client();
+        client.entities.diagnostics = true;
+
+        client
+            .publish_discovery("mrpir-officescreen")
+            .await
+            .expect("discovery publish should queue");
+        let mut topics = Vec::new();
+        while let Ok(Request::Publish(publish)) = rx.try_recv() {
+            topics.push(publish.topic);
+        }
+        assert!(topics.contains(&"homeassistant/sensor/officescreen/wifi_rssi/config".to_string()));
+        assert_eq!(
+            topics.last().map(String::as_str),
+            Some("homeassistant/sensor/officescreen/memory_used_percent/config")
+        );
+
+        let snapshot = DiagnosticsSnapshot {
+            uptime_secs: 3600,
+            ip_address: Some("192.168.1.20".to_string()),
+            cpu_temperature: Some(48.3),
+            wifi_rssi: None,
+            load_average: Some(0.52),
+            memory_used_percent: Some(25.0),
+        };
+        client
+            .publish_diagnostics(&snapshot)
+            .await
+            .expect("diagnostics publish should queue");
+        let state = next_publish(&rx).await;
+        assert_eq!(
+            state.topic,
+            "homeassistant/sensor/officescreen/diagnostics/state"
+        );
+        let json: serde_json::Value =
+            serde_json::from_slice(&state.payload).expect("diagnostics should be JSON");
+        assert_eq!(json["uptime_secs"], 3600);
+        assert_eq!(json["ip_address"], "192.168.1.20");
+        assert!(json["wifi_rssi"].is_null());
+        assert!(state.retain);
+    }
+
+    #[tokio::test]
+    async fn radar_readings_are_announced_and_published() {
+        let (mut client, rx) = test_client();
+        client.entities.radars = vec!["desk".to_string()];
+
+        client
+            .publish_discovery("mrpir-officescreen")
+            .await
+            .expect("discovery publish should queue");
+        let mut topics = Vec::new();
+        while let Ok(Request::Publish(publish)) = rx.try_recv() {
+            topics.push(publish.topic);
+        }
+        assert!(topics.contains(
+            &"homeassistant/sensor/officescreen/desk_moving_distance_cm/config".to_string()
+        ));
+        assert!(topics
+            .contains(&"homeassistant/sensor/officescreen/desk_static_energy/config".to_string()));
+
+        let reading = RadarReading {
+            moving: true,
+            stationary: false,
+            moving_distance_cm: 150,
+            moving_energy: 40,
+            static_distance_cm: 0,
+            static_energy: 0,
+            detection_distance_cm: 150,
+        };
+        client
+            .publish_radar("desk", &reading)
+            .await
+            .expect("radar publish should queue");
+        let state = next_publish(&rx).await;
+        assert_eq!(
+            state.topic,
+            "homeassistant/sensor/officescreen/desk_radar/state"
+        );
+        assert_eq!(
+            &state.payload[..],
+            br#"{"moving":true,"stationary":false,"moving_distance_cm":150,"moving_energy":40,"static_distance_cm":0,"static_energy":0,"detection_distance_cm":150}"#
+        );
+    }
 }
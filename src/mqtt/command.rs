@@ -0,0 +1,151 @@
+//! Commands received from Home Assistant on the device's command topics.
+
+use serde::Deserialize;
+
+use crate::error::MqttError;
+use crate::mqtt::discovery::HaDiscoveryPayload;
+use crate::mqtt::topics::TopicLayout;
+use crate::time_events::NightModeOverride;
+
+/// A command from Home Assistant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MqttCommand {
+    /// Turn the screen on (optionally at a brightness, 0-255) or off
+    Screen { on: bool, brightness: Option<u8> },
+    /// Force night mode on or off, or follow the schedule again
+    NightMode(NightModeOverride),
+    /// Act as if a sensor briefly detected motion
+    SimulateMotion,
+}
+
+/// Body of a JSON-schema light command, e.g. `{"state": "ON", "brightness": 128}`.
+#[derive(Debug, Deserialize)]
+struct LightCommand {
+    state: Option<String>,
+    brightness: Option<u8>,
+}
+
+/// The device's command topics.
+#[derive(Debug, Clone)]
+pub struct CommandTopics {
+    screen: String,
+    night_mode: String,
+    simulate_motion: String,
+}
+
+impl CommandTopics {
+    /// Build the command topics for a device.
+    pub fn new(topics: &TopicLayout) -> Self {
+        Self {
+            screen: HaDiscoveryPayload::screen_command_topic(topics),
+            night_mode: HaDiscoveryPayload::night_mode_command_topic(topics),
+            simulate_motion: HaDiscoveryPayload::simulate_motion_command_topic(topics),
+        }
+    }
+
+    /// Every command topic, for subscribing.
+    pub fn all(&self) -> [&str; 3] {
+        [&self.screen, &self.night_mode, &self.simulate_motion]
+    }
+
+    /// Parse a message, returning `None` when `topic` is not a command topic.
+    pub fn parse(&self, topic: &str, payload: &[u8]) -> Option<Result<MqttCommand, MqttError>> {
+        let invalid = |reason: String| MqttError::InvalidCommand {
+            topic: topic.to_string(),
+            reason,
+        };
+        let text = String::from_utf8_lossy(payload);
+        let text = text.trim();
+
+        let command = if topic == self.screen {
+            serde_json::from_str::<LightCommand>(text)
+                .map_err(|e| invalid(e.to_string()))
+                .and_then(|light| {
+                    let on = match light.state.as_deref() {
+                        Some("ON") => true,
+                        Some("OFF") => false,
+                        None if light.brightness.is_some() => true,
+                        other => return Err(invalid(format!("unknown state {other:?}"))),
+                    };
+                    Ok(MqttCommand::Screen {
+                        on,
+                        brightness: light.brightness,
+                    })
+                })
+        } else if topic == self.night_mode {
+            NightModeOverride::parse(text)
+                .map(MqttCommand::NightMode)
+                .ok_or_else(|| invalid(format!("unknown night mode {text:?}")))
+        } else if topic == self.simulate_motion {
+            Ok(MqttCommand::SimulateMotion)
+        } else {
+            return None;
+        };
+        Some(command)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::MqttConfig;
+
+    fn parse(topic: &str, payload: &str) -> Option<Result<MqttCommand, MqttError>> {
+        let topics =
+            TopicLayout::with_hostname(&MqttConfig::default(), "officescreen", "mrpir", "pi4");
+        CommandTopics::new(&topics).parse(topic, payload.as_bytes())
+    }
+
+    #[test]
+    fn light_commands_are_parsed() {
+        let topic = "homeassistant/light/officescreen/screen/set";
+
+        assert_eq!(
+            parse(topic, r#"{"state": "ON", "brightness": 128}"#)
+                .expect("screen topic")
+                .expect("valid command"),
+            MqttCommand::Screen {
+                on: true,
+                brightness: Some(128)
+            }
+        );
+        assert_eq!(
+            parse(topic, r#"{"state": "OFF"}"#)
+                .expect("screen topic")
+                .expect("valid command"),
+            MqttCommand::Screen {
+                on: false,
+                brightness: None
+            }
+        );
+        assert!(parse(topic, "ON").expect("screen topic").is_err());
+        assert!(parse(topic, r#"{"brightness": 300}"#)
+            .expect("screen topic")
+            .is_err());
+    }
+
+    #[test]
+    fn night_mode_and_button_commands_are_parsed() {
+        assert_eq!(
+            parse("homeassistant/select/officescreen/night_mode/set", "on")
+                .expect("night mode topic")
+                .expect("valid command"),
+            MqttCommand::NightMode(NightModeOverride::On)
+        );
+        assert!(
+            parse("homeassistant/select/officescreen/night_mode/set", "movie")
+                .expect("night mode topic")
+                .is_err()
+        );
+        assert_eq!(
+            parse(
+                "homeassistant/button/officescreen/simulate_motion/press",
+                "PRESS"
+            )
+            .expect("button topic")
+            .expect("valid command"),
+            MqttCommand::SimulateMotion
+        );
+        assert!(parse("zigbee2mqtt/front_door", "{}").is_none());
+    }
+}
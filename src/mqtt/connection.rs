@@ -0,0 +1,272 @@
+//! Protocol-specific side of the broker connection (MQTT 3.1.1 or 5).
+//!
+//! rumqttc has separate clients for each protocol version. This module
+//! builds the right one from `mqtt.protocol_version` and hides the
+//! difference from the rest of the client: MQTT 5 publishes carry the
+//! configured user properties, and motion states their message expiry.
+
+use rumqttc::v5::mqttbytes::v5::{
+    ConnectReturnCode as ConnectReturnCodeV5, Filter, LastWill as LastWillV5, Packet as PacketV5,
+    PublishProperties,
+};
+use rumqttc::v5::mqttbytes::QoS as QoSV5;
+use rumqttc::{
+    v5, AsyncClient, ConnectReturnCode, Event, EventLoop, LastWill, MqttOptions, Packet, QoS,
+    SubscribeFilter, Transport,
+};
+use std::time::Duration;
+use tracing::info;
+
+use crate::config::MqttConfig;
+use crate::error::MqttError;
+use crate::mqtt::{tls, websocket};
+
+/// Request queue capacity of the rumqttc client.
+const REQUEST_CAPACITY: usize = 10;
+
+/// Client half of the connection, used to queue requests.
+#[derive(Clone)]
+pub enum Connection {
+    /// MQTT 3.1.1
+    V4(AsyncClient),
+    /// MQTT 5
+    V5 {
+        client: v5::AsyncClient,
+        user_properties: Vec<(String, String)>,
+    },
+}
+
+/// Event loop half of the connection, polled to drive it.
+pub enum ConnectionLoop {
+    V4(Box<EventLoop>),
+    V5(Box<v5::EventLoop>),
+}
+
+/// A message received on a subscribed topic.
+#[derive(Debug)]
+pub struct Message {
+    pub topic: String,
+    pub payload: Vec<u8>,
+    /// Delivered from the broker's retained store rather than live
+    pub retain: bool,
+}
+
+/// What polling the event loop produced, for either protocol.
+#[derive(Debug)]
+pub enum Polled {
+    /// The broker answered the connect; `Err` holds the refusal reason
+    ConnAck(Result<(), String>),
+    /// A message on a subscribed topic
+    Publish(Message),
+    PubAck,
+    PingResp,
+    Outgoing,
+    /// Any other packet, formatted for logging
+    Other(String),
+}
+
+impl Connection {
+    /// Build the client for `config.protocol_version`, with the last will
+    /// `will_payload` retained on `will_topic`.
+    pub fn new(
+        config: &MqttConfig,
+        client_id: &str,
+        will_topic: &str,
+        will_payload: &str,
+    ) -> Result<(Self, ConnectionLoop), MqttError> {
+        let (host, transport) = transport(config)?;
+        let keep_alive = Duration::from_secs(config.keep_alive_secs);
+
+        if config.protocol_version == 5 {
+            let mut options = v5::MqttOptions::new(client_id, host, config.port);
+            options.set_keep_alive(keep_alive);
+            if let Some(transport) = transport {
+                options.set_transport(transport);
+            }
+            #[cfg(feature = "websocket")]
+            if let Some(modifier) = websocket::request_modifier(config)? {
+                options.set_request_modifier(modifier);
+            }
+            if let (Some(username), Some(password)) = (&config.username, &config.password) {
+                options.set_credentials(username, password);
+            }
+            options.set_last_will(LastWillV5::new(
+                will_topic,
+                will_payload.as_bytes().to_vec(),
+                QoSV5::AtLeastOnce,
+                true,
+                None,
+            ));
+
+            let user_properties: Vec<(String, String)> = config
+                .user_properties
+                .iter()
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect();
+            options.set_user_properties(user_properties.clone());
+            if let Some(secs) = config.session_expiry_secs {
+                // A session that outlives the connection needs a persistent start
+                options.set_session_expiry_interval(Some(secs));
+                options.set_clean_start(false);
+            }
+            info!(
+                session_expiry_secs = ?config.session_expiry_secs,
+                message_expiry_secs = ?config.message_expiry_secs,
+                user_properties = user_properties.len(),
+                "Using MQTT 5"
+            );
+
+            let (client, eventloop) = v5::AsyncClient::new(options, REQUEST_CAPACITY);
+            return Ok((
+                Self::V5 {
+                    client,
+                    user_properties,
+                },
+                ConnectionLoop::V5(Box::new(eventloop)),
+            ));
+        }
+
+        let mut options = MqttOptions::new(client_id, host, config.port);
+        options.set_keep_alive(keep_alive);
+        if let Some(transport) = transport {
+            options.set_transport(transport);
+        }
+        #[cfg(feature = "websocket")]
+        if let Some(modifier) = websocket::request_modifier(config)? {
+            options.set_request_modifier(modifier);
+        }
+        if let (Some(username), Some(password)) = (&config.username, &config.password) {
+            options.set_credentials(username, password);
+        }
+        options.set_last_will(LastWill::new(
+            will_topic,
+            will_payload.as_bytes().to_vec(),
+            QoS::AtLeastOnce,
+            true,
+        ));
+
+        let (client, eventloop) = AsyncClient::new(options, REQUEST_CAPACITY);
+        Ok((Self::V4(client), ConnectionLoop::V4(Box::new(eventloop))))
+    }
+
+    /// Queue an at-least-once publish. `expiry_secs` is ignored by MQTT 3.1.1.
+    pub async fn publish(
+        &self,
+        topic: &str,
+        payload: &[u8],
+        retain: bool,
+        expiry_secs: Option<u32>,
+    ) -> Result<(), MqttError> {
+        match self {
+            Self::V4(client) => {
+                client
+                    .publish(topic, QoS::AtLeastOnce, retain, payload)
+                    .await?;
+            }
+            Self::V5 {
+                client,
+                user_properties,
+            } => {
+                let properties = PublishProperties {
+                    message_expiry_interval: expiry_secs,
+                    user_properties: user_properties.clone(),
+                    ..PublishProperties::default()
+                };
+                client
+                    .publish_with_properties(
+                        topic,
+                        QoSV5::AtLeastOnce,
+                        retain,
+                        payload.to_vec(),
+                        properties,
+                    )
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Queue a subscription to `topics` without waiting on the request queue.
+    pub fn try_subscribe(&self, topics: &[String]) -> Result<(), MqttError> {
+        match self {
+            Self::V4(client) => client.try_subscribe_many(
+                topics
+                    .iter()
+                    .map(|topic| SubscribeFilter::new(topic.clone(), QoS::AtLeastOnce)),
+            )?,
+            Self::V5 { client, .. } => client.try_subscribe_many(
+                topics
+                    .iter()
+                    .map(|topic| Filter::new(topic.clone(), QoSV5::AtLeastOnce)),
+            )?,
+        }
+        Ok(())
+    }
+
+    /// Queue a disconnect.
+    pub async fn disconnect(&self) -> Result<(), MqttError> {
+        match self {
+            Self::V4(client) => client.disconnect().await?,
+            Self::V5 { client, .. } => client.disconnect().await?,
+        }
+        Ok(())
+    }
+}
+
+impl ConnectionLoop {
+    /// Drive the connection until the next event.
+    pub async fn poll(&mut self) -> Result<Polled, MqttError> {
+        Ok(match self {
+            Self::V4(eventloop) => match eventloop.poll().await? {
+                Event::Incoming(Packet::ConnAck(ack)) => Polled::ConnAck(
+                    (ack.code == ConnectReturnCode::Success)
+                        .then_some(())
+                        .ok_or_else(|| format!("{:?}", ack.code)),
+                ),
+                Event::Incoming(Packet::Publish(publish)) => Polled::Publish(Message {
+                    topic: publish.topic,
+                    payload: publish.payload.to_vec(),
+                    retain: publish.retain,
+                }),
+                Event::Incoming(Packet::PubAck(_)) => Polled::PubAck,
+                Event::Incoming(Packet::PingResp) => Polled::PingResp,
+                Event::Incoming(packet) => Polled::Other(format!("{packet:?}")),
+                Event::Outgoing(_) => Polled::Outgoing,
+            },
+            Self::V5(eventloop) => match eventloop.poll().await? {
+                v5::Event::Incoming(PacketV5::ConnAck(ack)) => Polled::ConnAck(
+                    (ack.code == ConnectReturnCodeV5::Success)
+                        .then_some(())
+                        .ok_or_else(|| format!("{:?}", ack.code)),
+                ),
+                v5::Event::Incoming(PacketV5::Publish(publish)) => Polled::Publish(Message {
+                    topic: String::from_utf8_lossy(&publish.topic).into_owned(),
+                    payload: publish.payload.to_vec(),
+                    retain: publish.retain,
+                }),
+                v5::Event::Incoming(PacketV5::PubAck(_)) => Polled::PubAck,
+                v5::Event::Incoming(PacketV5::PingResp(_)) => Polled::PingResp,
+                v5::Event::Incoming(packet) => Polled::Other(format!("{packet:?}")),
+                v5::Event::Outgoing(_) => Polled::Outgoing,
+            },
+        })
+    }
+}
+
+/// Broker address and transport for `config`: a URL for the WebSocket
+/// transports, otherwise the host, with TLS when enabled.
+fn transport(config: &MqttConfig) -> Result<(String, Option<Transport>), MqttError> {
+    if let Some(url) = config.websocket_url() {
+        info!(url = %url, headers = config.ws_headers.len(), "MQTT over WebSocket");
+        return Ok((url, Some(websocket::transport(config)?)));
+    }
+    if config.tls.enabled {
+        info!(
+            ca_file = ?config.tls.ca_file,
+            client_cert = config.tls.client_cert.is_some(),
+            "MQTT TLS enabled"
+        );
+        return Ok((config.host.clone(), Some(tls::transport(&config.tls)?)));
+    }
+    Ok((config.host.clone(), None))
+}
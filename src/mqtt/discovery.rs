@@ -2,6 +2,9 @@
 
 use serde::Serialize;
 
+use crate::mqtt::topics::TopicLayout;
+use crate::time_events::NightModeOverride;
+
 /// Home Assistant device information.
 #[derive(Debug, Clone, Serialize)]
 pub struct HaDevice {
@@ -39,21 +42,150 @@ pub struct HaOrigin {
     pub url: Option<String>,
 }
 
-/// Home Assistant MQTT Discovery payload for a binary sensor.
+/// Motion statistics published as Home Assistant sensors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatsSensor {
+    /// Timestamp motion last started
+    LastMotion,
+    /// Motion events since midnight
+    MotionEventsToday,
+    /// Minutes of motion in the last hour
+    ActiveMinutesLastHour,
+}
+
+impl StatsSensor {
+    /// Every statistics sensor.
+    pub const ALL: [Self; 3] = [
+        Self::LastMotion,
+        Self::MotionEventsToday,
+        Self::ActiveMinutesLastHour,
+    ];
+
+    /// Field name in the stats JSON payload.
+    pub const fn key(self) -> &'static str {
+        match self {
+            Self::LastMotion => "last_motion",
+            Self::MotionEventsToday => "motion_events_today",
+            Self::ActiveMinutesLastHour => "active_minutes_last_hour",
+        }
+    }
+}
+
+/// Radar target readings published as Home Assistant sensors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RadarSensor {
+    /// Distance to the moving target
+    MovingDistance,
+    /// Moving target energy
+    MovingEnergy,
+    /// Distance to the stationary target
+    StaticDistance,
+    /// Stationary target energy
+    StaticEnergy,
+}
+
+impl RadarSensor {
+    /// Every radar reading sensor.
+    pub const ALL: [Self; 4] = [
+        Self::MovingDistance,
+        Self::MovingEnergy,
+        Self::StaticDistance,
+        Self::StaticEnergy,
+    ];
+
+    /// Field name in the radar JSON payload.
+    pub const fn key(self) -> &'static str {
+        match self {
+            Self::MovingDistance => "moving_distance_cm",
+            Self::MovingEnergy => "moving_energy",
+            Self::StaticDistance => "static_distance_cm",
+            Self::StaticEnergy => "static_energy",
+        }
+    }
+}
+
+/// Host diagnostics published as Home Assistant sensors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiagnosticSensor {
+    /// Seconds since the daemon started
+    Uptime,
+    /// Host IP address
+    IpAddress,
+    /// SoC temperature
+    CpuTemperature,
+    /// Wi-Fi signal strength
+    WifiSignal,
+    /// One-minute load average
+    LoadAverage,
+    /// Memory in use
+    MemoryUsed,
+}
+
+impl DiagnosticSensor {
+    /// Every diagnostic sensor.
+    pub const ALL: [Self; 6] = [
+        Self::Uptime,
+        Self::IpAddress,
+        Self::CpuTemperature,
+        Self::WifiSignal,
+        Self::LoadAverage,
+        Self::MemoryUsed,
+    ];
+
+    /// Field name in the diagnostics JSON payload.
+    pub const fn key(self) -> &'static str {
+        match self {
+            Self::Uptime => "uptime_secs",
+            Self::IpAddress => "ip_address",
+            Self::CpuTemperature => "cpu_temperature",
+            Self::WifiSignal => "wifi_rssi",
+            Self::LoadAverage => "load_average",
+            Self::MemoryUsed => "memory_used_percent",
+        }
+    }
+}
+
+/// Home Assistant MQTT Discovery payload for a binary sensor or sensor.
 #[derive(Debug, Clone, Serialize)]
 pub struct HaDiscoveryPayload {
     /// Sensor name displayed in Home Assistant
     pub name: String,
 
     /// Device class (motion, occupancy, etc.)
-    pub device_class: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub device_class: Option<String>,
 
     /// Unique identifier for this entity
     pub unique_id: String,
 
-    /// MQTT topic where state updates are published
+    /// MQTT topic where state updates are published (empty for buttons)
+    #[serde(skip_serializing_if = "String::is_empty")]
     pub state_topic: String,
 
+    /// MQTT topic Home Assistant sends commands to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub command_topic: Option<String>,
+
+    /// Light payload schema (`json`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub schema: Option<String>,
+
+    /// Whether the light supports brightness
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub brightness: Option<bool>,
+
+    /// Brightness value that means full brightness
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub brightness_scale: Option<u8>,
+
+    /// Options offered by a select
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub options: Option<Vec<String>>,
+
+    /// Payload sent when a button is pressed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub payload_press: Option<String>,
+
     /// Payload that indicates motion detected
     #[serde(skip_serializing_if = "Option::is_none")]
     pub payload_on: Option<String>,
@@ -85,6 +217,26 @@ pub struct HaDiscoveryPayload {
     /// Icon override
     #[serde(skip_serializing_if = "Option::is_none")]
     pub icon: Option<String>,
+
+    /// Topic with a JSON object of extra state attributes
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub json_attributes_topic: Option<String>,
+
+    /// Entity category (`diagnostic` or `config`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub entity_category: Option<String>,
+
+    /// Template extracting the state from a JSON payload
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value_template: Option<String>,
+
+    /// Unit of the state value
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unit_of_measurement: Option<String>,
+
+    /// State class for long-term statistics (`measurement`, `total_increasing`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state_class: Option<String>,
 }
 
 impl HaDiscoveryPayload {
@@ -93,17 +245,23 @@ impl HaDiscoveryPayload {
         device_name: &str,
         display_name: &str,
         client_id: &str,
-        ha_prefix: &str,
+        topics: &TopicLayout,
     ) -> Self {
-        let state_topic = format!("{ha_prefix}/binary_sensor/{device_name}/state");
-        let availability_topic = format!("{ha_prefix}/binary_sensor/{device_name}/availability");
+        let state_topic = topics.motion_state();
+        let availability_topic = topics.availability();
 
         Self {
             name: format!("{display_name} Motion"),
-            device_class: "motion".to_string(),
+            device_class: Some("motion".to_string()),
             // Match Python format for seamless migration: pir_{device}_id_{device}_id
             unique_id: format!("pir_{device_name}_id_{device_name}_id"),
             state_topic,
+            command_topic: None,
+            schema: None,
+            brightness: None,
+            brightness_scale: None,
+            options: None,
+            payload_press: None,
             payload_on: Some("ON".to_string()),
             payload_off: Some("OFF".to_string()),
             availability_topic: Some(availability_topic),
@@ -122,14 +280,371 @@ impl HaDiscoveryPayload {
                 url: Some("https://github.com/MichaelRegan/rust-mrpir".to_string()),
             },
             icon: Some("mdi:motion-sensor".to_string()),
+            json_attributes_topic: None,
+            entity_category: None,
+            value_template: None,
+            unit_of_measurement: None,
+            state_class: None,
         }
     }
 
+    /// Read the motion state from a JSON state payload, and its other
+    /// fields as entity attributes.
+    #[must_use]
+    pub fn with_json_state(mut self) -> Self {
+        self.value_template = Some("{{ 'ON' if value_json.motion else 'OFF' }}".to_string());
+        self.json_attributes_topic = Some(self.state_topic.clone());
+        self
+    }
+
+    /// Create a discovery payload for one of several PIR sensors on the device.
+    pub fn sensor_motion(
+        device_name: &str,
+        display_name: &str,
+        sensor_name: &str,
+        client_id: &str,
+        topics: &TopicLayout,
+    ) -> Self {
+        let mut payload = Self::motion_sensor(device_name, display_name, client_id, topics);
+        payload.name = format!("{display_name} {sensor_name} Motion");
+        payload.unique_id = format!("pir_{device_name}_{sensor_name}_id");
+        payload.state_topic = topics.topic("binary_sensor", &format!("{sensor_name}/state"));
+        payload
+    }
+
+    /// Create the discovery payload for the sensor fault (problem) entity.
+    pub fn problem_sensor(
+        device_name: &str,
+        display_name: &str,
+        client_id: &str,
+        topics: &TopicLayout,
+    ) -> Self {
+        let mut payload = Self::motion_sensor(device_name, display_name, client_id, topics);
+        payload.name = format!("{display_name} Sensor Problem");
+        payload.device_class = Some("problem".to_string());
+        payload.unique_id = format!("pir_{device_name}_problem_id");
+        payload.state_topic = topics.topic("binary_sensor", "problem/state");
+        payload.json_attributes_topic = Some(topics.topic("binary_sensor", "problem/attributes"));
+        payload.entity_category = Some("diagnostic".to_string());
+        payload.icon = None;
+        payload
+    }
+
+    /// Create the discovery payload for the occupancy entity.
+    pub fn occupancy_sensor(
+        device_name: &str,
+        display_name: &str,
+        client_id: &str,
+        topics: &TopicLayout,
+    ) -> Self {
+        let mut payload = Self::motion_sensor(device_name, display_name, client_id, topics);
+        payload.name = format!("{display_name} Occupancy");
+        payload.device_class = Some("occupancy".to_string());
+        payload.unique_id = format!("pir_{device_name}_occupancy_id");
+        payload.state_topic = topics.topic("binary_sensor", "occupancy/state");
+        payload.json_attributes_topic = Some(topics.topic("binary_sensor", "occupancy/attributes"));
+        payload.icon = Some("mdi:home-account".to_string());
+        payload
+    }
+
+    /// Create the discovery payload for the touch activity entity.
+    pub fn touch_sensor(
+        device_name: &str,
+        display_name: &str,
+        client_id: &str,
+        topics: &TopicLayout,
+    ) -> Self {
+        let mut payload = Self::motion_sensor(device_name, display_name, client_id, topics);
+        payload.name = format!("{display_name} Touch Activity");
+        payload.device_class = None;
+        payload.unique_id = format!("pir_{device_name}_touch_id");
+        payload.state_topic = topics.topic("binary_sensor", "touch/state");
+        payload.icon = Some("mdi:gesture-tap".to_string());
+        payload
+    }
+
+    /// Create the discovery payload for the screen as a dimmable light.
+    ///
+    /// Brightness 1 is the dimmed screen and 255 the fully bright one.
+    pub fn screen_light(
+        device_name: &str,
+        display_name: &str,
+        client_id: &str,
+        topics: &TopicLayout,
+    ) -> Self {
+        let mut payload = Self::motion_sensor(device_name, display_name, client_id, topics);
+        payload.name = format!("{display_name} Screen");
+        payload.device_class = None;
+        payload.unique_id = format!("pir_{device_name}_screen_id");
+        payload.state_topic = Self::screen_state_topic(topics);
+        payload.command_topic = Some(Self::screen_command_topic(topics));
+        payload.schema = Some("json".to_string());
+        payload.brightness = Some(true);
+        payload.brightness_scale = Some(u8::MAX);
+        payload.payload_on = None;
+        payload.payload_off = None;
+        payload.icon = Some("mdi:monitor".to_string());
+        payload
+    }
+
+    /// Create the discovery payload for the night mode select.
+    pub fn night_mode_select(
+        device_name: &str,
+        display_name: &str,
+        client_id: &str,
+        topics: &TopicLayout,
+    ) -> Self {
+        let mut payload = Self::motion_sensor(device_name, display_name, client_id, topics);
+        payload.name = format!("{display_name} Night Mode");
+        payload.device_class = None;
+        payload.unique_id = format!("pir_{device_name}_night_mode_id");
+        payload.state_topic = Self::night_mode_state_topic(topics);
+        payload.command_topic = Some(Self::night_mode_command_topic(topics));
+        payload.options = Some(
+            NightModeOverride::ALL
+                .iter()
+                .map(|mode| mode.as_str().to_string())
+                .collect(),
+        );
+        payload.payload_on = None;
+        payload.payload_off = None;
+        payload.entity_category = Some("config".to_string());
+        payload.icon = Some("mdi:weather-night".to_string());
+        payload
+    }
+
+    /// Create the discovery payload for the simulate motion button.
+    pub fn simulate_motion_button(
+        device_name: &str,
+        display_name: &str,
+        client_id: &str,
+        topics: &TopicLayout,
+    ) -> Self {
+        let mut payload = Self::motion_sensor(device_name, display_name, client_id, topics);
+        payload.name = format!("{display_name} Simulate Motion");
+        payload.device_class = None;
+        payload.unique_id = format!("pir_{device_name}_simulate_motion_id");
+        payload.state_topic = String::new();
+        payload.command_topic = Some(Self::simulate_motion_command_topic(topics));
+        payload.payload_press = Some("PRESS".to_string());
+        payload.payload_on = None;
+        payload.payload_off = None;
+        payload.icon = Some("mdi:motion-play".to_string());
+        payload
+    }
+
+    /// Get the screen light's JSON state topic.
+    pub fn screen_state_topic(topics: &TopicLayout) -> String {
+        topics.topic("light", "screen/state")
+    }
+
+    /// Get the screen light's command topic.
+    pub fn screen_command_topic(topics: &TopicLayout) -> String {
+        topics.topic("light", "screen/set")
+    }
+
+    /// Get the night mode select's state topic.
+    pub fn night_mode_state_topic(topics: &TopicLayout) -> String {
+        topics.topic("select", "night_mode/state")
+    }
+
+    /// Get the night mode select's command topic.
+    pub fn night_mode_command_topic(topics: &TopicLayout) -> String {
+        topics.topic("select", "night_mode/set")
+    }
+
+    /// Get the simulate motion button's command topic.
+    pub fn simulate_motion_command_topic(topics: &TopicLayout) -> String {
+        topics.topic("button", "simulate_motion/press")
+    }
+
+    /// Create the discovery payload for one motion statistics sensor.
+    ///
+    /// All statistics share one JSON state topic; `stat` names the field.
+    pub fn stats_sensor(
+        device_name: &str,
+        display_name: &str,
+        stat: StatsSensor,
+        client_id: &str,
+        topics: &TopicLayout,
+    ) -> Self {
+        let mut payload = Self::motion_sensor(device_name, display_name, client_id, topics);
+        let (name, device_class, unit, state_class, icon) = match stat {
+            StatsSensor::LastMotion => ("Last Motion", Some("timestamp"), None, None, None),
+            StatsSensor::MotionEventsToday => (
+                "Motion Events Today",
+                None,
+                None,
+                Some("total_increasing"),
+                Some("mdi:counter"),
+            ),
+            StatsSensor::ActiveMinutesLastHour => (
+                "Activity Last Hour",
+                Some("duration"),
+                Some("min"),
+                Some("measurement"),
+                Some("mdi:timer-outline"),
+            ),
+        };
+        let key = stat.key();
+
+        payload.name = format!("{display_name} {name}");
+        payload.device_class = device_class.map(str::to_string);
+        payload.unique_id = format!("pir_{device_name}_{key}_id");
+        payload.state_topic = Self::stats_state_topic(topics);
+        payload.payload_on = None;
+        payload.payload_off = None;
+        payload.value_template = Some(format!("{{{{ value_json.{key} }}}}"));
+        payload.unit_of_measurement = unit.map(str::to_string);
+        payload.state_class = state_class.map(str::to_string);
+        payload.icon = icon.map(str::to_string);
+        payload
+    }
+
+    /// Get the state topic shared by the motion statistics sensors.
+    pub fn stats_state_topic(topics: &TopicLayout) -> String {
+        topics.topic("sensor", "stats/state")
+    }
+
+    /// Get the discovery config topic for a motion statistics sensor.
+    pub fn stats_config_topic(device_name: &str, stat: StatsSensor, ha_prefix: &str) -> String {
+        format!("{ha_prefix}/sensor/{device_name}/{}/config", stat.key())
+    }
+
+    /// Create the discovery payload for one reading of a radar sensor.
+    ///
+    /// A radar's readings share one JSON state topic; `reading` names the field.
+    pub fn radar_sensor(
+        device_name: &str,
+        display_name: &str,
+        sensor_name: &str,
+        reading: RadarSensor,
+        client_id: &str,
+        topics: &TopicLayout,
+    ) -> Self {
+        let mut payload = Self::motion_sensor(device_name, display_name, client_id, topics);
+        let (name, device_class, unit, icon) = match reading {
+            RadarSensor::MovingDistance => ("Moving Distance", Some("distance"), "cm", None),
+            RadarSensor::MovingEnergy => ("Moving Energy", None, "%", Some("mdi:signal")),
+            RadarSensor::StaticDistance => ("Static Distance", Some("distance"), "cm", None),
+            RadarSensor::StaticEnergy => ("Static Energy", None, "%", Some("mdi:signal")),
+        };
+        let key = reading.key();
+
+        payload.name = format!("{display_name} {sensor_name} {name}");
+        payload.device_class = device_class.map(str::to_string);
+        payload.unique_id = format!("pir_{device_name}_{sensor_name}_{key}_id");
+        payload.state_topic = Self::radar_state_topic(topics, sensor_name);
+        payload.payload_on = None;
+        payload.payload_off = None;
+        payload.value_template = Some(format!("{{{{ value_json.{key} }}}}"));
+        payload.unit_of_measurement = Some(unit.to_string());
+        payload.state_class = Some("measurement".to_string());
+        payload.icon = icon.map(str::to_string);
+        payload
+    }
+
+    /// Get the state topic shared by a radar's reading sensors.
+    pub fn radar_state_topic(topics: &TopicLayout, sensor_name: &str) -> String {
+        topics.topic("sensor", &format!("{sensor_name}_radar/state"))
+    }
+
+    /// Get the discovery config topic for one reading of a radar sensor.
+    pub fn radar_config_topic(
+        device_name: &str,
+        sensor_name: &str,
+        reading: RadarSensor,
+        ha_prefix: &str,
+    ) -> String {
+        format!(
+            "{ha_prefix}/sensor/{device_name}/{sensor_name}_{}/config",
+            reading.key()
+        )
+    }
+
+    /// Create the discovery payload for one host diagnostic sensor.
+    ///
+    /// All diagnostics share one JSON state topic; `diagnostic` names the field.
+    pub fn diagnostic_sensor(
+        device_name: &str,
+        display_name: &str,
+        diagnostic: DiagnosticSensor,
+        client_id: &str,
+        topics: &TopicLayout,
+    ) -> Self {
+        let mut payload = Self::motion_sensor(device_name, display_name, client_id, topics);
+        let (name, device_class, unit, icon) = match diagnostic {
+            DiagnosticSensor::Uptime => ("Uptime", Some("duration"), Some("s"), None),
+            DiagnosticSensor::IpAddress => ("IP Address", None, None, Some("mdi:ip-network")),
+            DiagnosticSensor::CpuTemperature => {
+                ("CPU Temperature", Some("temperature"), Some("°C"), None)
+            }
+            DiagnosticSensor::WifiSignal => {
+                ("Wi-Fi Signal", Some("signal_strength"), Some("dBm"), None)
+            }
+            DiagnosticSensor::LoadAverage => ("Load Average", None, None, Some("mdi:cpu-64-bit")),
+            DiagnosticSensor::MemoryUsed => ("Memory Used", None, Some("%"), Some("mdi:memory")),
+        };
+        let key = diagnostic.key();
+
+        payload.name = format!("{display_name} {name}");
+        payload.device_class = device_class.map(str::to_string);
+        payload.unique_id = format!("pir_{device_name}_{key}_id");
+        payload.state_topic = Self::diagnostics_state_topic(topics);
+        payload.payload_on = None;
+        payload.payload_off = None;
+        payload.entity_category = Some("diagnostic".to_string());
+        payload.value_template = Some(format!("{{{{ value_json.{key} }}}}"));
+        payload.unit_of_measurement = unit.map(str::to_string);
+        payload.state_class =
+            (diagnostic != DiagnosticSensor::IpAddress).then(|| "measurement".to_string());
+        payload.icon = icon.map(str::to_string);
+        payload
+    }
+
+    /// Get the (not retained) topic motion event messages are published on.
+    pub fn motion_events_topic(topics: &TopicLayout) -> String {
+        topics.topic("binary_sensor", "events")
+    }
+
+    /// Get the state topic shared by the diagnostic sensors.
+    pub fn diagnostics_state_topic(topics: &TopicLayout) -> String {
+        topics.topic("sensor", "diagnostics/state")
+    }
+
+    /// Get the discovery config topic for a diagnostic sensor.
+    pub fn diagnostic_config_topic(
+        device_name: &str,
+        diagnostic: DiagnosticSensor,
+        ha_prefix: &str,
+    ) -> String {
+        format!(
+            "{ha_prefix}/sensor/{device_name}/{}/config",
+            diagnostic.key()
+        )
+    }
+
+    /// Get the discovery config topic for a device entity of any component
+    /// (`light`, `select`, ...).
+    pub fn entity_config_topic(
+        component: &str,
+        device_name: &str,
+        entity: &str,
+        ha_prefix: &str,
+    ) -> String {
+        format!("{ha_prefix}/{component}/{device_name}/{entity}/config")
+    }
+
     /// Get the discovery config topic.
     pub fn config_topic(device_name: &str, ha_prefix: &str) -> String {
         format!("{ha_prefix}/binary_sensor/{device_name}/config")
     }
 
+    /// Get the discovery config topic for one of several PIR sensors.
+    pub fn sensor_config_topic(device_name: &str, sensor_name: &str, ha_prefix: &str) -> String {
+        format!("{ha_prefix}/binary_sensor/{device_name}/{sensor_name}/config")
+    }
+
     /// Serialize to JSON.
     pub fn to_json(&self) -> Result<String, serde_json::Error> {
         serde_json::to_string(self)
@@ -139,18 +654,19 @@ impl HaDiscoveryPayload {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::MqttConfig;
+
+    fn topics() -> TopicLayout {
+        TopicLayout::with_hostname(&MqttConfig::default(), "bedroom", "mrpir-bedroom", "pi4")
+    }
 
     #[test]
     fn test_discovery_payload() {
-        let payload = HaDiscoveryPayload::motion_sensor(
-            "bedroom",
-            "Bedroom",
-            "mrpir-bedroom",
-            "homeassistant",
-        );
+        let payload =
+            HaDiscoveryPayload::motion_sensor("bedroom", "Bedroom", "mrpir-bedroom", &topics());
 
         assert_eq!(payload.name, "Bedroom Motion");
-        assert_eq!(payload.device_class, "motion");
+        assert_eq!(payload.device_class.as_deref(), Some("motion"));
         assert_eq!(
             payload.state_topic,
             "homeassistant/binary_sensor/bedroom/state"
@@ -164,6 +680,292 @@ mod tests {
         assert!(json.contains(r#""o":"#));
     }
 
+    #[test]
+    fn test_sensor_discovery_payload() {
+        let payload = HaDiscoveryPayload::sensor_motion(
+            "bedroom",
+            "Bedroom",
+            "door",
+            "mrpir-bedroom",
+            &topics(),
+        );
+
+        assert_eq!(payload.name, "Bedroom door Motion");
+        assert_eq!(payload.unique_id, "pir_bedroom_door_id");
+        assert_eq!(
+            payload.state_topic,
+            "homeassistant/binary_sensor/bedroom/door/state"
+        );
+        assert_eq!(
+            payload.availability_topic.as_deref(),
+            Some("homeassistant/binary_sensor/bedroom/availability")
+        );
+        assert_eq!(
+            HaDiscoveryPayload::sensor_config_topic("bedroom", "door", "homeassistant"),
+            "homeassistant/binary_sensor/bedroom/door/config"
+        );
+    }
+
+    #[test]
+    fn test_problem_discovery_payload() {
+        let payload =
+            HaDiscoveryPayload::problem_sensor("bedroom", "Bedroom", "mrpir-bedroom", &topics());
+
+        assert_eq!(payload.device_class.as_deref(), Some("problem"));
+        assert_eq!(
+            payload.state_topic,
+            "homeassistant/binary_sensor/bedroom/problem/state"
+        );
+
+        let json = payload.to_json().unwrap();
+        assert!(json.contains(r#""entity_category":"diagnostic""#));
+        assert!(json.contains(
+            r#""json_attributes_topic":"homeassistant/binary_sensor/bedroom/problem/attributes""#
+        ));
+        assert!(!json.contains("icon"));
+    }
+
+    #[test]
+    fn test_occupancy_discovery_payload() {
+        let payload =
+            HaDiscoveryPayload::occupancy_sensor("bedroom", "Bedroom", "mrpir-bedroom", &topics());
+
+        assert_eq!(payload.name, "Bedroom Occupancy");
+        assert_eq!(payload.device_class.as_deref(), Some("occupancy"));
+        assert_eq!(
+            payload.state_topic,
+            "homeassistant/binary_sensor/bedroom/occupancy/state"
+        );
+        assert_eq!(payload.payload_on.as_deref(), Some("ON"));
+    }
+
+    #[test]
+    fn test_touch_discovery_payload() {
+        let payload =
+            HaDiscoveryPayload::touch_sensor("bedroom", "Bedroom", "mrpir-bedroom", &topics());
+
+        assert_eq!(payload.name, "Bedroom Touch Activity");
+        assert!(payload.device_class.is_none());
+        assert_eq!(
+            payload.state_topic,
+            "homeassistant/binary_sensor/bedroom/touch/state"
+        );
+        assert_eq!(payload.unique_id, "pir_bedroom_touch_id");
+    }
+
+    #[test]
+    fn test_radar_discovery_payloads() {
+        let payload = HaDiscoveryPayload::radar_sensor(
+            "bedroom",
+            "Bedroom",
+            "desk",
+            RadarSensor::StaticDistance,
+            "mrpir-bedroom",
+            &topics(),
+        );
+
+        assert_eq!(payload.name, "Bedroom desk Static Distance");
+        assert_eq!(payload.device_class.as_deref(), Some("distance"));
+        assert_eq!(payload.unit_of_measurement.as_deref(), Some("cm"));
+        assert_eq!(
+            payload.state_topic,
+            "homeassistant/sensor/bedroom/desk_radar/state"
+        );
+        assert_eq!(
+            payload.value_template.as_deref(),
+            Some("{{ value_json.static_distance_cm }}")
+        );
+        assert!(payload.payload_on.is_none());
+        assert_eq!(
+            HaDiscoveryPayload::radar_config_topic(
+                "bedroom",
+                "desk",
+                RadarSensor::StaticDistance,
+                "homeassistant"
+            ),
+            "homeassistant/sensor/bedroom/desk_static_distance_cm/config"
+        );
+    }
+
+    #[test]
+    fn test_command_discovery_payloads() {
+        let light =
+            HaDiscoveryPayload::screen_light("bedroom", "Bedroom", "mrpir-bedroom", &topics());
+        let json = light.to_json().expect("light should serialize");
+        assert!(json.contains(r#""schema":"json""#));
+        assert!(json.contains(r#""brightness":true"#));
+        assert!(json.contains(r#""brightness_scale":255"#));
+        assert!(json.contains(r#""command_topic":"homeassistant/light/bedroom/screen/set""#));
+        assert!(!json.contains("payload_on"));
+
+        let select =
+            HaDiscoveryPayload::night_mode_select("bedroom", "Bedroom", "mrpir-bedroom", &topics());
+        assert_eq!(
+            select.options,
+            Some(vec![
+                "auto".to_string(),
+                "on".to_string(),
+                "off".to_string()
+            ])
+        );
+        assert_eq!(
+            select.state_topic,
+            "homeassistant/select/bedroom/night_mode/state"
+        );
+
+        let button = HaDiscoveryPayload::simulate_motion_button(
+            "bedroom",
+            "Bedroom",
+            "mrpir-bedroom",
+            &topics(),
+        );
+        let json = button.to_json().expect("button should serialize");
+        assert!(!json.contains("state_topic"));
+        assert!(json.contains(r#""payload_press":"PRESS""#));
+        assert_eq!(
+            HaDiscoveryPayload::entity_config_topic(
+                "button",
+                "bedroom",
+                "simulate_motion",
+                "homeassistant"
+            ),
+            "homeassistant/button/bedroom/simulate_motion/config"
+        );
+    }
+
+    #[test]
+    fn test_diagnostic_discovery_payloads() {
+        let payload = HaDiscoveryPayload::diagnostic_sensor(
+            "bedroom",
+            "Bedroom",
+            DiagnosticSensor::WifiSignal,
+            "mrpir-bedroom",
+            &topics(),
+        );
+
+        assert_eq!(payload.entity_category.as_deref(), Some("diagnostic"));
+        assert_eq!(
+            payload.state_topic,
+            "homeassistant/sensor/bedroom/diagnostics/state"
+        );
+        assert_eq!(
+            payload.value_template.as_deref(),
+            Some("{{ value_json.wifi_rssi }}")
+        );
+        assert_eq!(payload.unit_of_measurement.as_deref(), Some("dBm"));
+        assert_eq!(
+            HaDiscoveryPayload::diagnostic_config_topic(
+                "bedroom",
+                DiagnosticSensor::IpAddress,
+                "homeassistant"
+            ),
+            "homeassistant/sensor/bedroom/ip_address/config"
+        );
+
+        let ip = HaDiscoveryPayload::diagnostic_sensor(
+            "bedroom",
+            "Bedroom",
+            DiagnosticSensor::IpAddress,
+            "mrpir-bedroom",
+            &topics(),
+        );
+        assert!(ip.state_class.is_none());
+        assert!(ip.unit_of_measurement.is_none());
+    }
+
+    #[test]
+    fn test_stats_discovery_payloads() {
+        let payload = HaDiscoveryPayload::stats_sensor(
+            "bedroom",
+            "Bedroom",
+            StatsSensor::MotionEventsToday,
+            "mrpir-bedroom",
+            &topics(),
+        );
+
+        assert_eq!(
+            payload.state_topic,
+            "homeassistant/sensor/bedroom/stats/state"
+        );
+        assert_eq!(
+            payload.value_template.as_deref(),
+            Some("{{ value_json.motion_events_today }}")
+        );
+        assert_eq!(payload.state_class.as_deref(), Some("total_increasing"));
+        assert_eq!(
+            HaDiscoveryPayload::stats_config_topic(
+                "bedroom",
+                StatsSensor::LastMotion,
+                "homeassistant"
+            ),
+            "homeassistant/sensor/bedroom/last_motion/config"
+        );
+
+        let json = HaDiscoveryPayload::stats_sensor(
+            "bedroom",
+            "Bedroom",
+            StatsSensor::LastMotion,
+            "mrpir-bedroom",
+            &topics(),
+        )
+        .to_json()
+        .unwrap();
+        assert!(json.contains(r#""device_class":"timestamp""#));
+        assert!(!json.contains("payload_on"));
+    }
+
+    #[test]
+    fn test_json_state_discovery_payload() {
+        let payload =
+            HaDiscoveryPayload::motion_sensor("bedroom", "Bedroom", "mrpir-bedroom", &topics())
+                .with_json_state();
+
+        assert_eq!(
+            payload.json_attributes_topic.as_deref(),
+            Some("homeassistant/binary_sensor/bedroom/state")
+        );
+        assert_eq!(payload.payload_on.as_deref(), Some("ON"));
+        let json = payload.to_json().unwrap();
+        assert!(json.contains(r#""value_template":"{{ 'ON' if value_json.motion else 'OFF' }}""#));
+    }
+
+    #[test]
+    fn test_discovery_follows_topic_layout() {
+        let config = MqttConfig {
+            base_topic: "mrpir/{hostname}/{device}".to_string(),
+            ..MqttConfig::default()
+        };
+        let topics = TopicLayout::with_hostname(&config, "bedroom", "mrpir-bedroom", "pi4");
+
+        let motion =
+            HaDiscoveryPayload::motion_sensor("bedroom", "Bedroom", "mrpir-bedroom", &topics);
+        assert_eq!(motion.state_topic, "mrpir/pi4/bedroom/state");
+        assert_eq!(
+            motion.availability_topic.as_deref(),
+            Some("mrpir/pi4/bedroom/availability")
+        );
+
+        let problem =
+            HaDiscoveryPayload::problem_sensor("bedroom", "Bedroom", "mrpir-bedroom", &topics);
+        assert_eq!(
+            problem.json_attributes_topic.as_deref(),
+            Some("mrpir/pi4/bedroom/problem/attributes")
+        );
+
+        let light =
+            HaDiscoveryPayload::screen_light("bedroom", "Bedroom", "mrpir-bedroom", &topics);
+        assert_eq!(
+            light.command_topic.as_deref(),
+            Some("mrpir/pi4/bedroom/screen/set")
+        );
+
+        // Discovery configs stay under the discovery prefix
+        assert_eq!(
+            HaDiscoveryPayload::config_topic("bedroom", "homeassistant"),
+            "homeassistant/binary_sensor/bedroom/config"
+        );
+    }
+
     #[test]
     fn test_config_topic() {
         let topic = HaDiscoveryPayload::config_topic("bedroom", "homeassistant");
@@ -1,8 +1,14 @@
 //! MQTT client and Home Assistant discovery for mrpir.
 
 mod client;
+mod command;
+mod connection;
 mod discovery;
+mod tls;
+mod topics;
+mod websocket;
 
 #[cfg(test)]
 pub use client::MqttPublishFuture;
-pub use client::{MqttClient, MqttEvent, MqttPublisher};
+pub use client::{Availability, MotionState, MqttClient, MqttEntities, MqttEvent, MqttPublisher};
+pub use command::MqttCommand;
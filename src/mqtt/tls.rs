@@ -0,0 +1,362 @@
+//! TLS transport for the broker connection (rustls).
+//!
+//! The broker is verified against `ca_file`, or the system roots when none is
+//! given. A client certificate and key enable mutual TLS.
+
+use rumqttc::{TlsConfiguration, Transport};
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::client::WebPkiServerVerifier;
+use rustls::crypto::{verify_tls12_signature, verify_tls13_signature, CryptoProvider};
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName, UnixTime};
+use rustls::{ClientConfig, DigitallySignedStruct, RootCertStore, SignatureScheme};
+use std::path::Path;
+use std::sync::Arc;
+use tracing::{info, warn};
+
+use crate::config::MqttTlsConfig;
+use crate::error::MqttError;
+
+/// Build the TLS transport described by `config`.
+pub fn transport(config: &MqttTlsConfig) -> Result<Transport, MqttError> {
+    Ok(Transport::tls_with_config(configuration(config)?))
+}
+
+/// Build the TLS settings described by `config`, for TLS or WebSocket transports.
+pub fn configuration(config: &MqttTlsConfig) -> Result<TlsConfiguration, MqttError> {
+    Ok(TlsConfiguration::Rustls(Arc::new(client_config(config)?)))
+}
+
+fn client_config(config: &MqttTlsConfig) -> Result<ClientConfig, MqttError> {
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let builder = ClientConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()?;
+
+    let builder = if config.insecure_skip_verify {
+        warn!("MQTT TLS certificate verification is disabled");
+        builder
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(NoVerification(provider)))
+    } else {
+        let roots = Arc::new(root_store(config.ca_file.as_deref())?);
+        match &config.server_name {
+            Some(name) => {
+                let name = ServerName::try_from(name.clone()).map_err(|e| {
+                    MqttError::InvalidConfig(format!("invalid TLS server name {name:?}: {e}"))
+                })?;
+                let inner = WebPkiServerVerifier::builder_with_provider(roots, provider)
+                    .build()
+                    .map_err(|e| MqttError::InvalidConfig(e.to_string()))?;
+                builder
+                    .dangerous()
+                    .with_custom_certificate_verifier(Arc::new(ServerNameOverride { inner, name }))
+            }
+            None => builder.with_root_certificates(roots),
+        }
+    };
+
+    let mut client_config = match (&config.client_cert, &config.client_key) {
+        (Some(cert), Some(key)) => {
+            info!(cert = %cert.display(), "Using MQTT TLS client certificate");
+            builder.with_client_auth_cert(load_certs(cert)?, load_key(key)?)?
+        }
+        _ => builder.with_no_client_auth(),
+    };
+    client_config.alpn_protocols = config
+        .alpn
+        .iter()
+        .map(|protocol| protocol.as_bytes().to_vec())
+        .collect();
+    client_config.enable_sni = config.sni;
+    Ok(client_config)
+}
+
+/// Trust anchors from `ca_file`, or the system's certificate store.
+fn root_store(ca_file: Option<&Path>) -> Result<RootCertStore, MqttError> {
+    let mut roots = RootCertStore::empty();
+    if let Some(path) = ca_file {
+        let (added, _) = roots.add_parsable_certificates(load_certs(path)?);
+        if added == 0 {
+            return Err(MqttError::InvalidCertificate {
+                path: path.display().to_string(),
+                reason: "no usable CA certificate".to_string(),
+            });
+        }
+    } else {
+        let native = rustls_native_certs::load_native_certs();
+        for error in &native.errors {
+            warn!(error = %error, "Failed to load a system CA certificate");
+        }
+        roots.add_parsable_certificates(native.certs);
+    }
+    Ok(roots)
+}
+
+fn read(path: &Path) -> Result<Vec<u8>, MqttError> {
+    std::fs::read(path).map_err(|source| MqttError::TlsFile {
+        path: path.display().to_string(),
+        source,
+    })
+}
+
+/// Every certificate in a PEM file.
+fn load_certs(path: &Path) -> Result<Vec<CertificateDer<'static>>, MqttError> {
+    let invalid = |reason: String| MqttError::InvalidCertificate {
+        path: path.display().to_string(),
+        reason,
+    };
+    let certs = CertificateDer::pem_slice_iter(&read(path)?)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| invalid(e.to_string()))?;
+    if certs.is_empty() {
+        return Err(invalid("no PEM certificates found".to_string()));
+    }
+    Ok(certs)
+}
+
+/// The first private key (PKCS#8, PKCS#1 or SEC1) in a PEM file.
+fn load_key(path: &Path) -> Result<PrivateKeyDer<'static>, MqttError> {
+    PrivateKeyDer::from_pem_slice(&read(path)?).map_err(|e| MqttError::InvalidPrivateKey {
+        path: path.display().to_string(),
+        reason: e.to_string(),
+    })
+}
+
+/// Verifies the broker's certificate against a fixed name instead of the
+/// host connected to, e.g. when the broker is reached by IP address.
+#[derive(Debug)]
+struct ServerNameOverride {
+    inner: Arc<WebPkiServerVerifier>,
+    name: ServerName<'static>,
+}
+
+impl ServerCertVerifier for ServerNameOverride {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        self.inner
+            .verify_server_cert(end_entity, intermediates, &self.name, ocsp_response, now)
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.inner.verify_tls12_signature(message, cert, dss)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.inner.verify_tls13_signature(message, cert, dss)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.inner.supported_verify_schemes()
+    }
+}
+
+/// Accepts any broker certificate (`insecure_skip_verify`).
+///
+/// Handshake signatures are still checked, so the connection is encrypted,
+/// but the broker is not authenticated.
+#[derive(Debug)]
+struct NoVerification(Arc<CryptoProvider>);
+
+impl ServerCertVerifier for NoVerification {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rumqttc::{AsyncClient, Event, MqttOptions, Packet};
+    use rustls::server::WebPkiClientVerifier;
+    use rustls::ServerConfig;
+    use std::path::PathBuf;
+    use std::time::Duration;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+    use tokio_rustls::TlsAcceptor;
+
+    fn fixture(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/tls")
+            .join(name)
+    }
+
+    fn mutual_tls() -> MqttTlsConfig {
+        MqttTlsConfig {
+            enabled: true,
+            ca_file: Some(fixture("ca.pem")),
+            client_cert: Some(fixture("client.pem")),
+            client_key: Some(fixture("client.key")),
+            server_name: Some("broker.local".to_string()),
+            ..MqttTlsConfig::default()
+        }
+    }
+
+    /// Accept one TLS connection that must present a client certificate,
+    /// answer its CONNECT with a CONNACK, and return the port.
+    async fn spawn_broker() -> u16 {
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let roots = Arc::new(root_store(Some(&fixture("ca.pem"))).expect("test CA"));
+        let client_verifier = WebPkiClientVerifier::builder_with_provider(roots, provider.clone())
+            .build()
+            .expect("client verifier");
+        let server = ServerConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()
+            .expect("protocol versions")
+            .with_client_cert_verifier(client_verifier)
+            .with_single_cert(
+                load_certs(&fixture("server.pem")).expect("server certificate"),
+                load_key(&fixture("server.key")).expect("server key"),
+            )
+            .expect("server config");
+        let acceptor = TlsAcceptor::from(Arc::new(server));
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+        let port = listener.local_addr().expect("local address").port();
+
+        tokio::spawn(async move {
+            let (tcp, _) = listener.accept().await.expect("accept");
+            let Ok(mut stream) = acceptor.accept(tcp).await else {
+                return;
+            };
+            let mut connect = [0u8; 256];
+            if stream.read(&mut connect).await.is_ok() {
+                let _ = stream.write_all(&[0x20, 0x02, 0x00, 0x00]).await;
+                let _ = stream.flush().await;
+            }
+            // Hold the connection open until the client is done
+            let _ = stream.read(&mut connect).await;
+        });
+        port
+    }
+
+    async fn first_event(config: &MqttTlsConfig, port: u16) -> Result<Event, String> {
+        let mut options = MqttOptions::new("mrpir-test", "127.0.0.1", port);
+        options.set_transport(transport(config).map_err(|e| e.to_string())?);
+        let (_client, mut eventloop) = AsyncClient::new(options, 10);
+        tokio::time::timeout(Duration::from_secs(5), eventloop.poll())
+            .await
+            .map_err(|_| "timed out".to_string())?
+            .map_err(|e| e.to_string())
+    }
+
+    #[tokio::test]
+    async fn mutual_tls_connects_to_the_broker() {
+        let port = spawn_broker().await;
+        let event = first_event(&mutual_tls(), port).await;
+        assert!(
+            matches!(event, Ok(Event::Incoming(Packet::ConnAck(_)))),
+            "unexpected event {event:?}"
+        );
+    }
+
+    #[tokio::test]
+    async fn broker_certificate_must_match_the_host() {
+        let port = spawn_broker().await;
+        let config = MqttTlsConfig {
+            server_name: None,
+            ..mutual_tls()
+        };
+        let error = first_event(&config, port)
+            .await
+            .expect_err("127.0.0.1 is not in the broker certificate");
+        assert!(error.contains("certificate"), "unexpected error {error}");
+    }
+
+    #[test]
+    fn alpn_and_sni_are_applied() {
+        let config = MqttTlsConfig {
+            alpn: vec!["mqtt".to_string()],
+            sni: false,
+            insecure_skip_verify: true,
+            ..MqttTlsConfig::default()
+        };
+        let client = client_config(&config).expect("insecure config needs no files");
+        assert_eq!(client.alpn_protocols, vec![b"mqtt".to_vec()]);
+        assert!(!client.enable_sni);
+    }
+
+    #[test]
+    fn unreadable_or_invalid_files_are_reported() {
+        let missing = MqttTlsConfig {
+            ca_file: Some(fixture("missing.pem")),
+            ..MqttTlsConfig::default()
+        };
+        assert!(matches!(
+            client_config(&missing),
+            Err(MqttError::TlsFile { .. })
+        ));
+
+        let key_as_ca = MqttTlsConfig {
+            ca_file: Some(fixture("client.key")),
+            ..MqttTlsConfig::default()
+        };
+        assert!(matches!(
+            client_config(&key_as_ca),
+            Err(MqttError::InvalidCertificate { .. })
+        ));
+
+        let cert_as_key = MqttTlsConfig {
+            client_key: Some(fixture("client.pem")),
+            ..mutual_tls()
+        };
+        assert!(matches!(
+            client_config(&cert_as_key),
+            Err(MqttError::InvalidPrivateKey { .. })
+        ));
+    }
+}
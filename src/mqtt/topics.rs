@@ -0,0 +1,106 @@
+//! Layout of the runtime topics: state, availability, attributes and commands.
+//!
+//! Runtime topics live under `mqtt.base_topic`, which may contain
+//! `{prefix}`, `{component}`, `{device}`, `{client_id}` and `{hostname}`.
+//! The default, `{prefix}/{component}/{device}`, keeps them next to the
+//! discovery configs; brokers whose ACLs keep the discovery namespace
+//! read-only can move them elsewhere. Discovery config topics always stay
+//! under the discovery prefix.
+
+use crate::config::MqttConfig;
+
+/// Placeholder left in the resolved base topic.
+const COMPONENT: &str = "{component}";
+
+/// The device's base topic, resolved except for `{component}`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TopicLayout {
+    base: String,
+}
+
+impl TopicLayout {
+    /// Resolve `config.base_topic` for a device on this host.
+    pub fn new(config: &MqttConfig, device_name: &str, client_id: &str) -> Self {
+        let hostname = hostname::get()
+            .ok()
+            .and_then(|h| h.into_string().ok())
+            .unwrap_or_else(|| device_name.to_string());
+        Self::with_hostname(config, device_name, client_id, &hostname)
+    }
+
+    /// Resolve `config.base_topic` with an explicit hostname.
+    pub fn with_hostname(
+        config: &MqttConfig,
+        device_name: &str,
+        client_id: &str,
+        hostname: &str,
+    ) -> Self {
+        let values = [
+            ("prefix", config.ha_discovery_prefix.as_str()),
+            ("device", device_name),
+            ("client_id", client_id),
+            ("hostname", hostname),
+        ];
+        let base = values
+            .iter()
+            .fold(config.base_topic.clone(), |topic, (name, value)| {
+                topic.replace(&format!("{{{name}}}"), value)
+            });
+        Self { base }
+    }
+
+    /// Topic `path` below the base topic of `component` (`binary_sensor`,
+    /// `sensor`, `light`, ...).
+    pub fn topic(&self, component: &str, path: &str) -> String {
+        format!("{}/{path}", self.base.replace(COMPONENT, component))
+    }
+
+    /// Retained online/offline topic, also used as the last will.
+    pub fn availability(&self) -> String {
+        self.topic("binary_sensor", "availability")
+    }
+
+    /// Combined motion state topic.
+    pub fn motion_state(&self) -> String {
+        self.topic("binary_sensor", "state")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layout(base_topic: &str) -> TopicLayout {
+        let config = MqttConfig {
+            base_topic: base_topic.to_string(),
+            ..MqttConfig::default()
+        };
+        TopicLayout::with_hostname(&config, "bedroom", "mrpir-bedroom", "pi4")
+    }
+
+    #[test]
+    fn default_layout_is_under_the_discovery_prefix() {
+        let topics = layout(&MqttConfig::default().base_topic);
+        assert_eq!(
+            topics.motion_state(),
+            "homeassistant/binary_sensor/bedroom/state"
+        );
+        assert_eq!(
+            topics.topic("light", "screen/set"),
+            "homeassistant/light/bedroom/screen/set"
+        );
+    }
+
+    #[test]
+    fn placeholders_are_resolved() {
+        let topics = layout("site/{hostname}/{client_id}/{device}");
+        assert_eq!(
+            topics.availability(),
+            "site/pi4/mrpir-bedroom/bedroom/availability"
+        );
+        assert_eq!(
+            topics.topic("sensor", "stats/state"),
+            "site/pi4/mrpir-bedroom/bedroom/stats/state"
+        );
+    }
+}
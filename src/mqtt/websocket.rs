@@ -0,0 +1,107 @@
+//! MQTT over a WebSocket, for brokers behind an HTTP(S) reverse proxy.
+//!
+//! The broker is reached at `mqtt.websocket_url()`; `wss` takes its TLS
+//! settings from `mqtt.tls`. Only available with the `websocket` feature.
+
+use rumqttc::Transport;
+
+use crate::config::MqttConfig;
+use crate::error::MqttError;
+
+#[cfg(feature = "websocket")]
+use crate::config::MqttTransport;
+#[cfg(feature = "websocket")]
+use crate::mqtt::tls;
+#[cfg(feature = "websocket")]
+use http::{HeaderMap, HeaderName, HeaderValue, Request};
+#[cfg(feature = "websocket")]
+use std::collections::BTreeMap;
+#[cfg(feature = "websocket")]
+use std::future::Ready;
+
+/// Handshake request modifier passed to rumqttc.
+#[cfg(feature = "websocket")]
+pub type RequestModifier = Box<dyn Fn(Request<()>) -> Ready<Request<()>> + Send + Sync>;
+
+/// The WebSocket transport for `mqtt.transport`.
+#[cfg(feature = "websocket")]
+pub fn transport(config: &MqttConfig) -> Result<Transport, MqttError> {
+    Ok(match config.transport {
+        MqttTransport::Wss => Transport::Wss(tls::configuration(&config.tls)?),
+        MqttTransport::Tcp | MqttTransport::Ws => Transport::Ws,
+    })
+}
+
+#[cfg(not(feature = "websocket"))]
+pub fn transport(_config: &MqttConfig) -> Result<Transport, MqttError> {
+    Err(MqttError::InvalidConfig(
+        "WebSocket support not compiled in. Rebuild with --features websocket".to_string(),
+    ))
+}
+
+/// Adds `mqtt.ws_headers` to the handshake request, if any are configured.
+#[cfg(feature = "websocket")]
+pub fn request_modifier(config: &MqttConfig) -> Result<Option<RequestModifier>, MqttError> {
+    let headers = headers(&config.ws_headers)?;
+    let configured = !headers.is_empty();
+    let modifier: RequestModifier = Box::new(move |mut request: Request<()>| {
+        request.headers_mut().extend(headers.clone());
+        std::future::ready(request)
+    });
+    Ok(configured.then_some(modifier))
+}
+
+/// Parse the configured handshake headers up front, so a typo fails at
+/// startup rather than on every reconnect.
+#[cfg(feature = "websocket")]
+fn headers(config: &BTreeMap<String, String>) -> Result<HeaderMap, MqttError> {
+    config
+        .iter()
+        .map(|(name, value)| {
+            let invalid = |reason: String| {
+                MqttError::InvalidConfig(format!("WebSocket header {name:?}: {reason}"))
+            };
+            Ok((
+                HeaderName::from_bytes(name.as_bytes()).map_err(|e| invalid(e.to_string()))?,
+                HeaderValue::from_str(value).map_err(|e| invalid(e.to_string()))?,
+            ))
+        })
+        .collect()
+}
+
+#[cfg(all(test, feature = "websocket"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn handshake_headers_are_validated() {
+        let mut config = BTreeMap::from([(
+            "Proxy-Authorization".to_string(),
+            "Basic bXJwaXI6c2VjcmV0".to_string(),
+        )]);
+        let parsed = headers(&config).expect("valid headers");
+        assert_eq!(
+            parsed.get("proxy-authorization").map(HeaderValue::as_bytes),
+            Some(b"Basic bXJwaXI6c2VjcmV0".as_slice())
+        );
+
+        config.insert("Bad Header".to_string(), "x".to_string());
+        assert!(headers(&config).is_err());
+
+        config.remove("Bad Header");
+        config.insert("X-Token".to_string(), "line\nbreak".to_string());
+        assert!(headers(&config).is_err());
+    }
+
+    #[test]
+    fn wss_uses_the_tls_settings() {
+        let config = MqttConfig {
+            transport: MqttTransport::Wss,
+            ..MqttConfig::default()
+        };
+        assert!(matches!(transport(&config), Ok(Transport::Wss(_))));
+        assert!(request_modifier(&config)
+            .expect("no headers to parse")
+            .is_none());
+    }
+}
@@ -0,0 +1,164 @@
+//! Occupancy derived from raw motion with a decaying confidence.
+//!
+//! Every motion event adds one to a confidence score that halves every
+//! `half_life_secs`. When motion clears, the room stays occupied for
+//! `min_hold_secs` times the confidence, capped at `max_hold_secs`: a single
+//! brief trigger holds for the minimum, while frequent recent motion keeps
+//! the room occupied for longer.
+
+use std::time::{Duration, Instant};
+use tracing::info;
+
+use crate::config::OccupancyConfig;
+
+/// Tracks occupancy from changes in the combined motion state.
+#[derive(Debug)]
+pub struct OccupancyTracker {
+    min_hold: Duration,
+    max_hold: Duration,
+    half_life: Duration,
+    score: f64,
+    scored_at: Option<Instant>,
+    occupied: bool,
+    vacant_at: Option<Instant>,
+}
+
+impl OccupancyTracker {
+    /// Create a tracker from the occupancy configuration.
+    pub const fn new(config: &OccupancyConfig) -> Self {
+        Self {
+            min_hold: Duration::from_secs(config.min_hold_secs),
+            max_hold: Duration::from_secs(config.max_hold_secs),
+            half_life: Duration::from_secs(config.half_life_secs),
+            score: 0.0,
+            scored_at: None,
+            occupied: false,
+            vacant_at: None,
+        }
+    }
+
+    /// Whether the room is occupied.
+    pub const fn occupied(&self) -> bool {
+        self.occupied
+    }
+
+    /// Decayed confidence at `now`: roughly the number of recent motion events.
+    pub fn confidence(&self, now: Instant) -> f64 {
+        self.scored_at.map_or(0.0, |scored_at| {
+            let half_lives = now.saturating_duration_since(scored_at).as_secs_f64()
+                / self.half_life.as_secs_f64();
+            self.score * 0.5_f64.powf(half_lives)
+        })
+    }
+
+    /// Feed a change in the motion state, returning the new occupancy if it changed.
+    pub fn motion_changed(&mut self, motion_detected: bool, now: Instant) -> Option<bool> {
+        if motion_detected {
+            self.score = self.confidence(now) + 1.0;
+            self.scored_at = Some(now);
+            self.vacant_at = None;
+            if self.occupied {
+                return None;
+            }
+            self.occupied = true;
+            return Some(true);
+        }
+
+        if self.occupied {
+            let hold = self
+                .min_hold
+                .mul_f64(self.confidence(now).max(1.0))
+                .min(self.max_hold);
+            info!(
+                hold_secs = hold.as_secs(),
+                confidence = self.confidence(now),
+                "Occupancy hold started"
+            );
+            self.vacant_at = Some(now + hold);
+        }
+        None
+    }
+
+    /// When the room becomes vacant, if the hold is running.
+    pub const fn next_deadline(&self) -> Option<Instant> {
+        self.vacant_at
+    }
+
+    /// Fire the hold at `now`, returning `Some(false)` if the room became vacant.
+    pub fn poll(&mut self, now: Instant) -> Option<bool> {
+        match self.vacant_at {
+            Some(vacant_at) if vacant_at <= now => {
+                self.vacant_at = None;
+                self.occupied = false;
+                Some(false)
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tracker() -> OccupancyTracker {
+        OccupancyTracker::new(&OccupancyConfig {
+            enabled: true,
+            drive_screen: false,
+            min_hold_secs: 60,
+            max_hold_secs: 1800,
+            half_life_secs: 600,
+        })
+    }
+
+    #[test]
+    fn single_trigger_holds_for_the_minimum() {
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+        let mut occupancy = tracker();
+
+        assert_eq!(occupancy.motion_changed(true, at(0)), Some(true));
+        assert_eq!(occupancy.motion_changed(false, at(5)), None);
+        // A single event never holds for less than the minimum
+        assert_eq!(occupancy.next_deadline(), Some(at(65)));
+        assert_eq!(occupancy.poll(at(64)), None);
+        assert_eq!(occupancy.poll(at(65)), Some(false));
+        assert!(!occupancy.occupied());
+    }
+
+    #[test]
+    fn frequent_motion_holds_longer() {
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+        let mut occupancy = tracker();
+
+        assert_eq!(occupancy.motion_changed(true, at(0)), Some(true));
+        for minute in 1..=5 {
+            assert_eq!(occupancy.motion_changed(false, at(minute * 60 - 30)), None);
+            assert_eq!(occupancy.motion_changed(true, at(minute * 60)), None);
+        }
+        assert_eq!(occupancy.motion_changed(false, at(305)), None);
+
+        let hold = occupancy
+            .next_deadline()
+            .expect("hold should be running")
+            .duration_since(at(305));
+        assert!(hold > Duration::from_mins(4), "hold was {hold:?}");
+        assert!(occupancy.confidence(at(305)) > 4.5);
+        assert_eq!(occupancy.poll(at(305) + hold), Some(false));
+    }
+
+    #[test]
+    fn motion_during_hold_keeps_room_occupied() {
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+        let mut occupancy = tracker();
+
+        occupancy.motion_changed(true, at(0));
+        occupancy.motion_changed(false, at(5));
+        assert_eq!(occupancy.motion_changed(true, at(30)), None);
+        assert!(occupancy.next_deadline().is_none());
+        assert_eq!(occupancy.poll(at(100)), None);
+        assert!(occupancy.occupied());
+    }
+}
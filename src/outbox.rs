@@ -0,0 +1,293 @@
+//! Outbox for motion event messages.
+//!
+//! Every motion transition becomes an event message that is kept here until
+//! it has been handed to the MQTT client, so transitions during a broker
+//! outage are published in order, with their original timestamps, after
+//! reconnecting. With a file, the outbox is saved as JSON lines after every
+//! change so unsent events also survive a restart. It is bounded: when full,
+//! the oldest event is dropped. The last sequence number is always saved
+//! next to the outbox file, so event numbers keep counting up across
+//! restarts even once every event has been sent.
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::io;
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+use crate::error::OutboxError;
+
+/// A motion transition, as published on the event topic.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MotionEventMessage {
+    /// Whether motion started (`true`) or ended
+    pub motion: bool,
+    /// When the transition happened
+    pub timestamp: DateTime<Local>,
+    /// Event number, counting up across restarts
+    #[serde(default)]
+    pub sequence: u64,
+    /// What caused the transition (`pir_detected`, `remote_cleared`, `startup`, ...)
+    #[serde(default)]
+    pub reason: String,
+    /// How long motion lasted, on motion end
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_secs: Option<f64>,
+}
+
+/// Bounded queue of unsent event messages, optionally mirrored to a file.
+#[derive(Debug)]
+pub struct Outbox {
+    path: Option<PathBuf>,
+    sequence_path: PathBuf,
+    max_events: usize,
+    events: VecDeque<MotionEventMessage>,
+    last_sequence: u64,
+}
+
+impl Outbox {
+    /// Load unsent events from `path`, starting empty if it does not exist.
+    pub fn load(path: &Path, max_events: usize) -> Result<Self, OutboxError> {
+        let events = match std::fs::read_to_string(path) {
+            Ok(contents) => contents
+                .lines()
+                .filter(|line| !line.trim().is_empty())
+                .map(serde_json::from_str)
+                .collect::<Result<_, _>>()?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => VecDeque::new(),
+            Err(e) => return Err(e.into()),
+        };
+
+        let events: VecDeque<MotionEventMessage> = events;
+        let sequence_path = path.with_extension("seq");
+        let last_sequence = events
+            .iter()
+            .map(|event| event.sequence)
+            .fold(load_sequence(&sequence_path), u64::max);
+        let mut outbox = Self {
+            path: Some(path.to_path_buf()),
+            sequence_path,
+            max_events,
+            events,
+            last_sequence,
+        };
+        if outbox.trim() > 0 {
+            outbox.save()?;
+        }
+        Ok(outbox)
+    }
+
+    /// An outbox that keeps unsent events in memory only; just the
+    /// sequence number is saved, next to `path`.
+    pub fn in_memory(path: &Path, max_events: usize) -> Self {
+        let sequence_path = path.with_extension("seq");
+        Self {
+            path: None,
+            last_sequence: load_sequence(&sequence_path),
+            sequence_path,
+            max_events,
+            events: VecDeque::new(),
+        }
+    }
+
+    /// Number of unsent events.
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    /// The oldest unsent event.
+    pub fn front(&self) -> Option<&MotionEventMessage> {
+        self.events.front()
+    }
+
+    /// Sequence number for the next event.
+    pub const fn next_sequence(&self) -> u64 {
+        self.last_sequence.saturating_add(1)
+    }
+
+    /// Queue an event, dropping the oldest if the outbox is full.
+    pub fn push(&mut self, event: MotionEventMessage) -> Result<(), OutboxError> {
+        if event.sequence > self.last_sequence {
+            self.last_sequence = event.sequence;
+            write_atomically(
+                &self.sequence_path,
+                self.last_sequence.to_string().as_bytes(),
+            )?;
+        }
+        self.events.push_back(event);
+        self.trim();
+        self.save()
+    }
+
+    /// Remove the oldest event once it has been sent.
+    pub fn pop_front(&mut self) -> Result<(), OutboxError> {
+        self.events.pop_front();
+        self.save()
+    }
+
+    /// Drop the oldest events beyond `max_events`, returning how many.
+    fn trim(&mut self) -> usize {
+        let excess = self.events.len().saturating_sub(self.max_events);
+        if excess > 0 {
+            self.events.drain(..excess);
+            warn!(
+                dropped = excess,
+                max_events = self.max_events,
+                "Outbox full; oldest motion events dropped"
+            );
+        }
+        excess
+    }
+
+    /// Save the events, replacing the file atomically.
+    fn save(&self) -> Result<(), OutboxError> {
+        let Some(path) = self.path.as_ref() else {
+            return Ok(());
+        };
+        let mut contents = Vec::new();
+        for event in &self.events {
+            serde_json::to_writer(&mut contents, event)?;
+            contents.push(b'\n');
+        }
+        write_atomically(path, &contents)
+    }
+}
+
+/// Read the last saved sequence number, or 0 if there is none.
+fn load_sequence(path: &Path) -> u64 {
+    match std::fs::read_to_string(path) {
+        Ok(contents) => contents.trim().parse().unwrap_or_else(|e| {
+            warn!(path = %path.display(), error = %e, "Invalid motion event sequence file; ignored");
+            0
+        }),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => 0,
+        Err(e) => {
+            warn!(path = %path.display(), error = %e, "Failed to read motion event sequence file");
+            0
+        }
+    }
+}
+
+/// Replace `path` with `contents` through a temporary file.
+fn write_atomically(path: &Path, contents: &[u8]) -> Result<(), OutboxError> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    std::fs::write(&tmp, contents)?;
+    std::fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn event(motion: bool, secs: i64) -> MotionEventMessage {
+        MotionEventMessage {
+            motion,
+            timestamp: Local
+                .timestamp_opt(1_700_000_000 + secs, 0)
+                .single()
+                .expect("valid timestamp"),
+            sequence: secs.unsigned_abs() + 1,
+            reason: if motion {
+                "pir_detected"
+            } else {
+                "pir_cleared"
+            }
+            .to_string(),
+            duration_secs: (!motion).then_some(1.5),
+        }
+    }
+
+    #[test]
+    fn events_survive_a_restart_in_order() {
+        let dir = tempfile::tempdir().expect("temp dir should be created");
+        let path = dir.path().join("outbox.jsonl");
+
+        let mut outbox = Outbox::load(&path, 10).expect("missing file starts empty");
+        assert_eq!(outbox.len(), 0);
+        outbox.push(event(true, 0)).expect("push");
+        outbox.push(event(false, 30)).expect("push");
+        outbox.push(event(true, 60)).expect("push");
+        outbox.pop_front().expect("pop");
+
+        let mut reloaded = Outbox::load(&path, 10).expect("saved outbox loads");
+        assert_eq!(reloaded.len(), 2);
+        assert_eq!(reloaded.next_sequence(), 62);
+        assert_eq!(reloaded.front(), Some(&event(false, 30)));
+        reloaded.pop_front().expect("pop");
+        assert_eq!(reloaded.front(), Some(&event(true, 60)));
+    }
+
+    #[test]
+    fn events_without_sequence_or_reason_still_load() {
+        let dir = tempfile::tempdir().expect("temp dir should be created");
+        let path = dir.path().join("outbox.jsonl");
+        std::fs::write(
+            &path,
+            "{\"motion\":true,\"timestamp\":\"2026-03-01T08:15:02+01:00\"}\n",
+        )
+        .expect("write outbox");
+
+        let outbox = Outbox::load(&path, 10).expect("older outbox loads");
+        let event = outbox.front().expect("one event");
+        assert_eq!(event.sequence, 0);
+        assert!(event.reason.is_empty());
+        assert_eq!(event.duration_secs, None);
+        assert_eq!(outbox.next_sequence(), 1);
+    }
+
+    #[test]
+    fn sequence_keeps_counting_after_the_outbox_drained() {
+        let dir = tempfile::tempdir().expect("temp dir should be created");
+        let path = dir.path().join("outbox.jsonl");
+
+        let mut outbox = Outbox::load(&path, 10).expect("missing file starts empty");
+        outbox.push(event(true, 40)).expect("push");
+        outbox.pop_front().expect("pop");
+
+        let reloaded = Outbox::load(&path, 10).expect("saved outbox loads");
+        assert_eq!(reloaded.len(), 0);
+        assert_eq!(reloaded.next_sequence(), 42);
+        assert_eq!(Outbox::in_memory(&path, 10).next_sequence(), 42);
+    }
+
+    #[test]
+    fn in_memory_outbox_queues_without_a_file() {
+        let dir = tempfile::tempdir().expect("temp dir should be created");
+        let path = dir.path().join("outbox.jsonl");
+        let mut outbox = Outbox::in_memory(&path, 2);
+        for secs in 0..3 {
+            outbox.push(event(secs % 2 == 0, secs)).expect("push");
+        }
+        assert_eq!(outbox.len(), 2);
+        assert_eq!(outbox.next_sequence(), 4);
+        assert_eq!(outbox.front(), Some(&event(false, 1)));
+        outbox.pop_front().expect("pop");
+        assert_eq!(outbox.front(), Some(&event(true, 2)));
+        assert!(!path.exists());
+        assert_eq!(Outbox::in_memory(&path, 2).next_sequence(), 4);
+    }
+
+    #[test]
+    fn oldest_events_are_dropped_when_full() {
+        let dir = tempfile::tempdir().expect("temp dir should be created");
+        let path = dir.path().join("outbox.jsonl");
+
+        let mut outbox = Outbox::load(&path, 2).expect("missing file starts empty");
+        for secs in 0..3 {
+            outbox.push(event(secs % 2 == 0, secs)).expect("push");
+        }
+        assert_eq!(outbox.len(), 2);
+        assert_eq!(outbox.front(), Some(&event(false, 1)));
+
+        let reloaded = Outbox::load(&path, 1).expect("saved outbox loads");
+        assert_eq!(reloaded.len(), 1);
+        assert_eq!(reloaded.front(), Some(&event(true, 2)));
+    }
+}
@@ -0,0 +1,210 @@
+//! Motion sources on other devices, read from subscribed MQTT topics.
+//!
+//! Each remote sensor matches messages on its topic (or one JSON field of
+//! them) against lists of motion and clear payloads. Sources that only ever
+//! report motion, such as a door contact's "open" event, clear `hold_secs`
+//! after their last motion payload.
+
+use serde_json::Value;
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+use crate::config::RemoteSensorConfig;
+
+/// Motion state of one remote sensor.
+#[derive(Debug)]
+pub struct RemoteSensor {
+    config: RemoteSensorConfig,
+    active: bool,
+    clear_at: Option<Instant>,
+}
+
+impl RemoteSensor {
+    /// Create an inactive remote sensor.
+    pub const fn new(config: RemoteSensorConfig) -> Self {
+        Self {
+            config,
+            active: false,
+            clear_at: None,
+        }
+    }
+
+    /// Name used in logs and traces.
+    pub fn name(&self) -> String {
+        self.config.name()
+    }
+
+    /// Topic filter to subscribe to.
+    pub fn topic(&self) -> &str {
+        &self.config.topic
+    }
+
+    /// Whether the remote sensor currently reports motion.
+    pub const fn active(&self) -> bool {
+        self.active
+    }
+
+    /// When the hold ends, if motion is being held.
+    pub const fn next_deadline(&self) -> Option<Instant> {
+        self.clear_at
+    }
+
+    /// Apply a message, returning the new state if it changed.
+    ///
+    /// Messages on other topics, or with payloads matching neither list, are
+    /// ignored.
+    pub fn handle_message(&mut self, topic: &str, payload: &[u8], now: Instant) -> Option<bool> {
+        if !rumqttc::matches(topic, &self.config.topic) {
+            return None;
+        }
+        let Some(value) = payload_value(payload, self.config.json_field.as_deref()) else {
+            debug!(remote = %self.name(), topic, "Remote payload has no state value");
+            return None;
+        };
+
+        if self.config.on_payloads.contains(&value) {
+            self.clear_at = (self.config.hold_secs > 0)
+                .then(|| now + Duration::from_secs(self.config.hold_secs));
+            self.set_active(true)
+        } else if self.config.off_payloads.contains(&value) {
+            self.clear_at = None;
+            self.set_active(false)
+        } else {
+            debug!(remote = %self.name(), topic, value, "Remote payload matched no state");
+            None
+        }
+    }
+
+    /// Clear motion once the hold has passed, returning `Some(false)` if it did.
+    pub fn poll(&mut self, now: Instant) -> Option<bool> {
+        if self.clear_at.is_some_and(|at| at <= now) {
+            self.clear_at = None;
+            return self.set_active(false);
+        }
+        None
+    }
+
+    fn set_active(&mut self, active: bool) -> Option<bool> {
+        let changed = self.active != active;
+        self.active = active;
+        changed.then_some(active)
+    }
+}
+
+/// The state value of a payload: the whole payload, or a field of its JSON.
+///
+/// JSON strings are compared without quotes; other JSON values as written,
+/// e.g. `true` or `1`.
+fn payload_value(payload: &[u8], json_field: Option<&str>) -> Option<String> {
+    let text = std::str::from_utf8(payload).ok()?.trim();
+    let Some(path) = json_field else {
+        return Some(text.to_string());
+    };
+
+    let json: Value = serde_json::from_str(text).ok()?;
+    let value = path
+        .split('.')
+        .try_fold(&json, |value, key| value.get(key))?;
+    match value {
+        Value::String(text) => Some(text.clone()),
+        Value::Null | Value::Array(_) | Value::Object(_) => None,
+        other => Some(other.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn remote(toml: &str) -> RemoteSensor {
+        use figment::providers::{Format, Toml};
+
+        RemoteSensor::new(
+            figment::Figment::from(Toml::string(toml))
+                .extract()
+                .expect("remote sensor config should parse"),
+        )
+    }
+
+    #[test]
+    fn plain_payloads_set_and_clear_motion() {
+        let mut remote = remote("topic = \"mrpir/hallway/motion\"");
+        let now = Instant::now();
+
+        assert_eq!(
+            remote.handle_message("mrpir/hallway/motion", b"ON", now),
+            Some(true)
+        );
+        assert_eq!(
+            remote.handle_message("mrpir/hallway/motion", b"ON", now),
+            None
+        );
+        assert_eq!(
+            remote.handle_message("mrpir/kitchen/motion", b"OFF", now),
+            None
+        );
+        assert_eq!(
+            remote.handle_message("mrpir/hallway/motion", b"unknown", now),
+            None
+        );
+        assert_eq!(
+            remote.handle_message("mrpir/hallway/motion", b"OFF\n", now),
+            Some(false)
+        );
+        assert!(remote.next_deadline().is_none());
+    }
+
+    #[test]
+    fn json_fields_are_matched_through_wildcards() {
+        let mut remote = remote(
+            "topic = \"zigbee2mqtt/+\"\njson_field = \"state.contact\"\non_payloads = [\"false\"]\noff_payloads = [\"true\"]",
+        );
+        let now = Instant::now();
+
+        assert_eq!(
+            remote.handle_message(
+                "zigbee2mqtt/front_door",
+                br#"{"battery": 97, "state": {"contact": false}}"#,
+                now
+            ),
+            Some(true)
+        );
+        assert_eq!(
+            remote.handle_message("zigbee2mqtt/front_door", br#"{"battery": 97}"#, now),
+            None
+        );
+        assert_eq!(
+            remote.handle_message("zigbee2mqtt/front_door", b"not json", now),
+            None
+        );
+        assert_eq!(
+            remote.handle_message(
+                "zigbee2mqtt/front_door",
+                br#"{"state": {"contact": true}}"#,
+                now
+            ),
+            Some(false)
+        );
+    }
+
+    #[test]
+    fn motion_without_off_payload_clears_after_hold() {
+        let mut remote = remote("topic = \"doorbell/ring\"\non_payloads = [\"pressed\"]\noff_payloads = []\nhold_secs = 30");
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+
+        assert_eq!(
+            remote.handle_message("doorbell/ring", b"pressed", start),
+            Some(true)
+        );
+        assert_eq!(
+            remote.handle_message("doorbell/ring", b"pressed", at(20)),
+            None
+        );
+        assert_eq!(remote.next_deadline(), Some(at(50)));
+        assert_eq!(remote.poll(at(49)), None);
+        assert_eq!(remote.poll(at(50)), Some(false));
+        assert!(!remote.active());
+        assert!(remote.next_deadline().is_none());
+    }
+}
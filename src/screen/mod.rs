@@ -134,18 +134,7 @@ impl ScreenManager {
 
         // Turn on and set to bright
         self.controller.turn_on().await?;
-
-        if self.config.transition_time_secs > 0 {
-            self.transition_brightness(self.config.bright_brightness)
-                .await?;
-        } else {
-            self.controller
-                .set_brightness(self.config.bright_brightness)
-                .await?;
-            self.current_brightness = self.config.bright_brightness;
-        }
-
-        Ok(())
+        self.apply_brightness(self.config.bright_brightness).await
     }
 
     /// Handle motion timeout - dim screen.
@@ -155,33 +144,90 @@ impl ScreenManager {
         }
 
         info!("Motion timeout: dimming screen");
+        self.apply_brightness(self.config.dim_brightness).await
+    }
 
-        if self.config.transition_time_secs > 0 {
-            self.transition_brightness(self.config.dim_brightness)
-                .await?;
-        } else {
-            self.controller
-                .set_brightness(self.config.dim_brightness)
-                .await?;
-            self.current_brightness = self.config.dim_brightness;
+    /// Handle night mode - turn off screen.
+    pub async fn on_night_mode(&mut self) -> Result<(), ScreenError> {
+        if !self.config.enabled {
+            return Ok(());
         }
 
+        info!("Night mode: turning off screen");
+        self.controller.turn_off().await?;
+        self.current_brightness = 0;
+
         Ok(())
     }
 
-    /// Handle night mode - turn off screen.
-    pub async fn on_night_mode(&mut self) -> Result<(), ScreenError> {
+    /// Turn the screen on at `level` (0-255) on request.
+    pub async fn set_level(&mut self, level: u8) -> Result<(), ScreenError> {
         if !self.config.enabled {
             return Ok(());
         }
 
-        info!("Night mode: turning off screen");
+        info!(level, "Screen brightness set on request");
+        self.controller.turn_on().await?;
+        self.apply_brightness(level).await
+    }
+
+    /// Turn the screen off on request.
+    pub async fn turn_off(&mut self) -> Result<(), ScreenError> {
+        if !self.config.enabled {
+            return Ok(());
+        }
+
+        info!("Screen turned off on request");
         self.controller.turn_off().await?;
         self.current_brightness = 0;
 
         Ok(())
     }
 
+    /// Current brightness on Home Assistant's 1-255 scale, or 0 when off.
+    ///
+    /// 1 is `dim_brightness` (or the dimmest visible level when dimming
+    /// turns the screen off) and 255 is `bright_brightness`.
+    pub fn scaled_brightness(&self) -> u8 {
+        if self.current_brightness == 0 {
+            return 0;
+        }
+        let (low, high) = self.scale_range();
+        if high == low {
+            return u8::MAX;
+        }
+        let offset = u32::from(self.current_brightness.clamp(low, high) - low);
+        let span = u32::from(high - low);
+        let scaled = 1 + (offset * 254 + span / 2) / span;
+        u8::try_from(scaled).unwrap_or(u8::MAX)
+    }
+
+    /// Brightness level for a value on Home Assistant's 1-255 scale.
+    pub fn level_for_scaled(&self, scaled: u8) -> u8 {
+        let (low, high) = self.scale_range();
+        let offset = u32::from(scaled.max(1) - 1);
+        let span = u32::from(high - low);
+        let level = u32::from(low) + (offset * span + 127) / 254;
+        u8::try_from(level).unwrap_or(high)
+    }
+
+    /// Levels at the bottom and top of Home Assistant's brightness scale.
+    fn scale_range(&self) -> (u8, u8) {
+        let low = self.config.dim_brightness.max(1);
+        (low, self.config.bright_brightness.max(low))
+    }
+
+    /// Set the brightness, transitioning smoothly if configured.
+    async fn apply_brightness(&mut self, target: u8) -> Result<(), ScreenError> {
+        if self.config.transition_time_secs > 0 {
+            self.transition_brightness(target).await
+        } else {
+            self.controller.set_brightness(target).await?;
+            self.current_brightness = target;
+            Ok(())
+        }
+    }
+
     /// Smoothly transition to a target brightness.
     async fn transition_brightness(&mut self, target: u8) -> Result<(), ScreenError> {
         let steps = 20u32;
@@ -211,3 +257,46 @@ impl ScreenManager {
         self.controller.is_available()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manager(dim_brightness: u8, bright_brightness: u8) -> ScreenManager {
+        let config = ScreenConfig {
+            dim_brightness,
+            bright_brightness,
+            ..ScreenConfig::default()
+        };
+        ScreenManager::new(&config).expect("no-op screen manager")
+    }
+
+    #[test]
+    fn brightness_scale_spans_dim_to_bright() {
+        let mut screen = manager(30, 230);
+        assert_eq!(screen.scaled_brightness(), 255);
+        screen.current_brightness = 30;
+        assert_eq!(screen.scaled_brightness(), 1);
+        screen.current_brightness = 0;
+        assert_eq!(screen.scaled_brightness(), 0);
+
+        assert_eq!(screen.level_for_scaled(255), 230);
+        assert_eq!(screen.level_for_scaled(1), 30);
+        for level in [30, 31, 80, 129, 229, 230] {
+            screen.current_brightness = level;
+            assert_eq!(screen.level_for_scaled(screen.scaled_brightness()), level);
+        }
+    }
+
+    #[test]
+    fn dimming_to_zero_reads_as_off() {
+        let mut screen = manager(0, 230);
+        screen.current_brightness = 0;
+        assert_eq!(screen.scaled_brightness(), 0);
+        assert_eq!(screen.level_for_scaled(1), 1);
+
+        let fixed = manager(200, 200);
+        assert_eq!(fixed.scaled_brightness(), 255);
+        assert_eq!(fixed.level_for_scaled(10), 200);
+    }
+}
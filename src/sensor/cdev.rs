@@ -0,0 +1,229 @@
+//! GPIO input through the Linux character device (`/dev/gpiochipN`).
+//!
+//! Works on any board with a kernel GPIO driver, and with the `gpio-sim`
+//! module for exercising the sensor without hardware.
+
+use futures_util::StreamExt;
+use gpio_cdev::{
+    AsyncLineEventHandle, Chip, EventRequestFlags, EventType, Line, LineHandle, LineRequestFlags,
+};
+use std::future;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+use crate::config::{GpioLine, Polarity, PullMode, SensorConfig};
+use crate::error::SensorError;
+
+/// Consumer label shown by `gpioinfo` for requested lines.
+const CONSUMER: &str = "mrpir";
+
+/// Directory holding the GPIO character devices.
+const DEV_DIR: &str = "/dev";
+
+/// How the line is currently requested from the kernel.
+enum LineRequest {
+    /// Level reads only
+    Plain(LineHandle),
+    /// Level reads plus edge events
+    Events(AsyncLineEventHandle),
+    /// Between requests (a line can only be requested once)
+    Released,
+}
+
+/// A sensor input on a GPIO character-device line.
+pub struct CdevInput {
+    line: Line,
+    active_low: bool,
+    request: LineRequest,
+}
+
+impl CdevInput {
+    /// Request the configured chip and line as an input.
+    pub fn open(config: &SensorConfig) -> Result<Self, SensorError> {
+        let mut chip = open_chip(&config.chip)?;
+        let offset = match config.cdev_line() {
+            GpioLine::Offset(offset) => offset,
+            GpioLine::Name(name) => find_line(&chip, &name)?,
+        };
+        let line = chip.get_line(offset)?;
+
+        // The v1 character-device ABI has no bias flags
+        if config.pull != PullMode::None {
+            warn!(
+                pull = ?config.pull,
+                "Pull resistor cannot be set through the GPIO character device; \
+                 configure bias in the device tree or fit an external resistor"
+            );
+        }
+
+        let handle = line.request(LineRequestFlags::INPUT, 0, CONSUMER)?;
+        info!(
+            chip = chip.name(),
+            label = chip.label(),
+            offset,
+            "GPIO line requested"
+        );
+
+        Ok(Self {
+            line,
+            active_low: config.polarity == Polarity::ActiveLow,
+            request: LineRequest::Plain(handle),
+        })
+    }
+
+    /// Read the line, returning `true` at the active level.
+    pub fn read(&self) -> Result<bool, SensorError> {
+        let value = match &self.request {
+            LineRequest::Plain(handle) => handle.get_value()?,
+            LineRequest::Events(events) => events.as_ref().get_value()?,
+            LineRequest::Released => {
+                return Err(SensorError::ReadFailed("GPIO line released".to_string()))
+            }
+        };
+        Ok((value != 0) != self.active_low)
+    }
+
+    /// Re-request the line with edge events on both edges.
+    ///
+    /// On failure the line is requested for plain reads again.
+    pub fn enable_edges(&mut self) -> Result<(), SensorError> {
+        if matches!(self.request, LineRequest::Events(_)) {
+            return Ok(());
+        }
+
+        self.request = LineRequest::Released;
+        let events = self
+            .line
+            .events(
+                LineRequestFlags::INPUT,
+                EventRequestFlags::BOTH_EDGES,
+                CONSUMER,
+            )
+            .and_then(AsyncLineEventHandle::new);
+
+        match events {
+            Ok(events) => {
+                self.request = LineRequest::Events(events);
+                Ok(())
+            }
+            Err(e) => {
+                let handle = self.line.request(LineRequestFlags::INPUT, 0, CONSUMER)?;
+                self.request = LineRequest::Plain(handle);
+                Err(e.into())
+            }
+        }
+    }
+
+    /// Wait for the next edge, returning whether the line became active.
+    ///
+    /// Never completes unless edges are enabled.
+    pub async fn next_edge(&mut self) -> Option<Result<bool, SensorError>> {
+        let LineRequest::Events(events) = &mut self.request else {
+            return future::pending().await;
+        };
+
+        let event = events.next().await?;
+        Some(
+            event
+                .map(|event| (event.event_type() == EventType::RisingEdge) != self.active_low)
+                .map_err(SensorError::from),
+        )
+    }
+}
+
+/// Path of a chip given as a path or a `/dev` entry name.
+fn chip_path(chip: &str) -> PathBuf {
+    if chip.contains('/') {
+        PathBuf::from(chip)
+    } else {
+        Path::new(DEV_DIR).join(chip)
+    }
+}
+
+/// Open a chip by path, device name, or label.
+fn open_chip(chip: &str) -> Result<Chip, SensorError> {
+    let path = chip_path(chip);
+    if path.exists() {
+        return Ok(Chip::new(path)?);
+    }
+
+    // gpio-sim and hot-plugged controllers get unpredictable chip numbers
+    for found in gpio_cdev::chips()? {
+        let found = found?;
+        if found.label() == chip {
+            return Ok(found);
+        }
+    }
+
+    Err(SensorError::ChipNotFound(chip.to_string()))
+}
+
+/// Find a line offset by its name.
+fn find_line(chip: &Chip, name: &str) -> Result<u32, SensorError> {
+    for line in chip.lines() {
+        if line.info()?.name() == Some(name) {
+            return Ok(line.offset());
+        }
+    }
+
+    Err(SensorError::LineNotFound {
+        chip: chip.name().to_string(),
+        line: name.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn chip_names_resolve_under_dev() {
+        assert_eq!(chip_path("gpiochip2"), PathBuf::from("/dev/gpiochip2"));
+        assert_eq!(chip_path("/dev/gpiochip0"), PathBuf::from("/dev/gpiochip0"));
+    }
+
+    /// Drive a `gpio-sim` line and watch it through the sensor input.
+    ///
+    /// Needs root and a simulated chip, e.g.:
+    ///
+    /// ```sh
+    /// modprobe gpio-sim
+    /// mkdir -p /sys/kernel/config/gpio-sim/mrpir/bank0/line0
+    /// echo 1 > /sys/kernel/config/gpio-sim/mrpir/live
+    /// MRPIR_GPIO_SIM_CHIP=$(cat /sys/kernel/config/gpio-sim/mrpir/bank0/chip_name) \
+    ///     cargo test -- --ignored gpio_sim
+    /// ```
+    #[tokio::test]
+    #[ignore = "needs a gpio-sim chip named by MRPIR_GPIO_SIM_CHIP"]
+    async fn gpio_sim_line_reports_levels_and_edges() {
+        let chip = std::env::var("MRPIR_GPIO_SIM_CHIP").expect("MRPIR_GPIO_SIM_CHIP not set");
+        let pull = Path::new("/sys/bus/gpio/devices")
+            .join(&chip)
+            .join("sim_gpio0/pull");
+        let set_pull =
+            |value: &str| std::fs::write(&pull, value).expect("failed to drive gpio-sim");
+
+        let config = SensorConfig {
+            backend: crate::config::SensorBackend::Cdev,
+            chip,
+            line: Some(GpioLine::Offset(0)),
+            pull: PullMode::None,
+            ..SensorConfig::default()
+        };
+
+        set_pull("pull-down");
+        let mut input = CdevInput::open(&config).expect("failed to open gpio-sim line");
+        assert!(!input.read().expect("read failed"));
+
+        input.enable_edges().expect("failed to enable edges");
+        set_pull("pull-up");
+        let edge = tokio::time::timeout(Duration::from_secs(1), input.next_edge())
+            .await
+            .expect("no edge within 1s")
+            .expect("edge stream ended")
+            .expect("edge read failed");
+        assert!(edge);
+        assert!(input.read().expect("read failed"));
+    }
+}
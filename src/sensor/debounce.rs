@@ -0,0 +1,157 @@
+//! Glitch filter for the raw PIR input.
+//!
+//! Only the rising side is filtered: the input must stay active for
+//! `min_pulse_ms`, and/or be active in `filter_threshold` of the last
+//! `filter_window` samples, before it counts as motion. Going inactive is
+//! passed straight through so the no-motion delay keeps its meaning.
+
+use std::time::{Duration, Instant};
+use tracing::{debug, info};
+
+use crate::config::SensorConfig;
+
+/// Debounces the raw sensor level before it reaches the motion tracker.
+#[derive(Debug)]
+pub struct GlitchFilter {
+    min_pulse: Duration,
+    window: u32,
+    threshold: u32,
+    history: u64,
+    active_since: Option<Instant>,
+    output: bool,
+    rejected: u64,
+}
+
+impl GlitchFilter {
+    /// Create a filter from the sensor configuration.
+    ///
+    /// `initial_state` is treated as already confirmed.
+    pub fn new(config: &SensorConfig, initial_state: bool) -> Self {
+        Self {
+            min_pulse: Duration::from_millis(config.min_pulse_ms),
+            window: u32::from(config.filter_window),
+            threshold: u32::from(config.filter_threshold),
+            history: 0,
+            active_since: None,
+            output: initial_state,
+            rejected: 0,
+        }
+    }
+
+    /// Feed a raw level, returning the filtered level.
+    pub fn sample(&mut self, raw: bool, now: Instant) -> bool {
+        if self.window > 0 {
+            let mask = u64::MAX >> (64 - self.window);
+            self.history = ((self.history << 1) | u64::from(raw)) & mask;
+        }
+
+        if !raw {
+            if self.active_since.take().is_some() && !self.output {
+                self.rejected = self.rejected.saturating_add(1);
+                info!(rejected_total = self.rejected, "PIR glitch rejected");
+            }
+            self.output = false;
+            return false;
+        }
+
+        if self.output {
+            return true;
+        }
+
+        let active_since = *self.active_since.get_or_insert(now);
+        let pulse_ok = now.duration_since(active_since) >= self.min_pulse;
+        let votes_ok = self.window == 0 || self.history.count_ones() >= self.threshold;
+
+        if pulse_ok && votes_ok {
+            if self.min_pulse > Duration::ZERO || self.window > 0 {
+                debug!(
+                    pulse_ms = u64::try_from(now.duration_since(active_since).as_millis())
+                        .unwrap_or(u64::MAX),
+                    votes = self.history.count_ones(),
+                    "PIR pulse confirmed"
+                );
+            }
+            self.output = true;
+        }
+
+        self.output
+    }
+
+    /// When a pending pulse becomes long enough to confirm, if one is pending.
+    pub fn confirm_deadline(&self) -> Option<Instant> {
+        if self.output || self.min_pulse.is_zero() {
+            return None;
+        }
+
+        self.active_since
+            .map(|active_since| active_since + self.min_pulse)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(min_pulse_ms: u64, filter_window: u8, filter_threshold: u8) -> GlitchFilter {
+        let config = SensorConfig {
+            min_pulse_ms,
+            filter_window,
+            filter_threshold,
+            ..SensorConfig::default()
+        };
+        GlitchFilter::new(&config, false)
+    }
+
+    #[test]
+    fn disabled_filter_passes_input_through() {
+        let start = Instant::now();
+        let mut filter = filter(0, 0, 0);
+
+        assert!(filter.sample(true, start));
+        assert!(!filter.sample(false, start));
+        assert!(filter.confirm_deadline().is_none());
+        assert_eq!(filter.rejected, 0);
+    }
+
+    #[test]
+    fn short_pulse_is_rejected() {
+        let start = Instant::now();
+        let mut filter = filter(300, 0, 0);
+
+        assert!(!filter.sample(true, start));
+        assert_eq!(
+            filter.confirm_deadline(),
+            Some(start + Duration::from_millis(300))
+        );
+        assert!(!filter.sample(false, start + Duration::from_millis(100)));
+        assert_eq!(filter.rejected, 1);
+        assert!(filter.confirm_deadline().is_none());
+    }
+
+    #[test]
+    fn long_pulse_is_confirmed_and_clears_immediately() {
+        let start = Instant::now();
+        let mut filter = filter(300, 0, 0);
+
+        assert!(!filter.sample(true, start));
+        assert!(filter.sample(true, start + Duration::from_millis(300)));
+        assert!(filter.confirm_deadline().is_none());
+        assert!(!filter.sample(false, start + Duration::from_millis(400)));
+        assert_eq!(filter.rejected, 0);
+    }
+
+    #[test]
+    fn single_sample_spike_fails_vote() {
+        let start = Instant::now();
+        let mut filter = filter(0, 5, 3);
+
+        assert!(!filter.sample(true, start));
+        assert!(!filter.sample(false, start));
+        assert_eq!(filter.rejected, 1);
+
+        // H L H L H: the third active sample in the window confirms
+        assert!(!filter.sample(true, start));
+        assert!(!filter.sample(false, start));
+        assert!(filter.sample(true, start));
+    }
+}
@@ -0,0 +1,249 @@
+//! Fault heuristics for the raw PIR input.
+//!
+//! A PIR that stays active for `stuck_high_secs` is considered dead, and one
+//! with `flap_max_edges` rising edges within `flap_window_secs` is considered
+//! failing. A stuck fault clears when the input goes inactive; a flapping
+//! fault clears after a full window without a rising edge. Stuck detection
+//! is skipped for the LD2410 radar, which reports presence for as long as
+//! someone stays in the room.
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use crate::config::{SensorBackend, SensorConfig};
+
+/// A suspected sensor hardware fault.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SensorFault {
+    /// Input active for longer than `stuck_high_secs`
+    StuckHigh,
+    /// Input toggling faster than `flap_max_edges` per `flap_window_secs`
+    Flapping,
+}
+
+impl SensorFault {
+    /// Short name used in logs, traces and MQTT attributes.
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::StuckHigh => "stuck_high",
+            Self::Flapping => "flapping",
+        }
+    }
+}
+
+/// A change in a sensor's fault state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FaultChange {
+    /// The fault was detected (replacing any other fault)
+    Raised(SensorFault),
+    /// The fault went away
+    Cleared(SensorFault),
+}
+
+/// Watches the raw sensor level for stuck and flapping inputs.
+#[derive(Debug)]
+pub struct FaultDetector {
+    stuck_after: Duration,
+    flap_max_edges: usize,
+    flap_window: Duration,
+    last_raw: Option<bool>,
+    high_since: Option<Instant>,
+    rising_edges: VecDeque<Instant>,
+    stuck: bool,
+    flapping: bool,
+    reported: Option<SensorFault>,
+}
+
+impl FaultDetector {
+    /// Create a detector from the sensor configuration.
+    pub fn new(config: &SensorConfig) -> Self {
+        let stuck_high_secs = if config.backend == SensorBackend::Ld2410 {
+            0
+        } else {
+            config.stuck_high_secs
+        };
+        Self {
+            stuck_after: Duration::from_secs(stuck_high_secs),
+            flap_max_edges: usize::from(config.flap_max_edges),
+            flap_window: Duration::from_secs(config.flap_window_secs),
+            last_raw: None,
+            high_since: None,
+            rising_edges: VecDeque::new(),
+            stuck: false,
+            flapping: false,
+            reported: None,
+        }
+    }
+
+    /// Feed a raw level, returning a change in the fault state.
+    pub fn update(&mut self, raw: bool, now: Instant) -> Option<FaultChange> {
+        let rising = raw && self.last_raw == Some(false);
+        self.last_raw = Some(raw);
+
+        if raw {
+            let high_since = *self.high_since.get_or_insert(now);
+            if !self.stuck_after.is_zero() && now.duration_since(high_since) >= self.stuck_after {
+                self.stuck = true;
+            }
+        } else {
+            self.high_since = None;
+            self.stuck = false;
+        }
+
+        if self.flap_max_edges > 0 {
+            if rising {
+                self.rising_edges.push_back(now);
+            }
+            while self
+                .rising_edges
+                .front()
+                .is_some_and(|edge| now.duration_since(*edge) >= self.flap_window)
+            {
+                self.rising_edges.pop_front();
+            }
+
+            if self.rising_edges.len() >= self.flap_max_edges {
+                self.flapping = true;
+            } else if self.rising_edges.is_empty() {
+                self.flapping = false;
+            }
+        }
+
+        self.report()
+    }
+
+    /// Whether the input is currently stuck active.
+    pub const fn stuck(&self) -> bool {
+        self.stuck
+    }
+
+    /// When the fault state may change without an edge: the stuck threshold
+    /// or the end of a quiet flap window.
+    pub fn next_deadline(&self) -> Option<Instant> {
+        let stuck = self
+            .high_since
+            .filter(|_| !self.stuck && !self.stuck_after.is_zero())
+            .map(|high_since| high_since + self.stuck_after);
+        let flap = self
+            .rising_edges
+            .back()
+            .filter(|_| self.flapping)
+            .map(|edge| *edge + self.flap_window);
+
+        match (stuck, flap) {
+            (Some(stuck), Some(flap)) => Some(stuck.min(flap)),
+            (stuck, flap) => stuck.or(flap),
+        }
+    }
+
+    fn report(&mut self) -> Option<FaultChange> {
+        let current = if self.stuck {
+            Some(SensorFault::StuckHigh)
+        } else if self.flapping {
+            Some(SensorFault::Flapping)
+        } else {
+            None
+        };
+        if current == self.reported {
+            return None;
+        }
+
+        let previous = std::mem::replace(&mut self.reported, current);
+        match (current, previous) {
+            (Some(fault), _) => Some(FaultChange::Raised(fault)),
+            (None, Some(fault)) => Some(FaultChange::Cleared(fault)),
+            (None, None) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn detector(stuck_high_secs: u64, flap_max_edges: u8) -> FaultDetector {
+        let config = SensorConfig {
+            stuck_high_secs,
+            flap_max_edges,
+            flap_window_secs: 60,
+            ..SensorConfig::default()
+        };
+        FaultDetector::new(&config)
+    }
+
+    #[test]
+    fn input_active_for_too_long_is_stuck() {
+        let start = Instant::now();
+        let mut detector = detector(600, 0);
+        let at = |secs| start + Duration::from_secs(secs);
+
+        assert_eq!(detector.update(true, start), None);
+        assert_eq!(detector.next_deadline(), Some(at(600)));
+        assert_eq!(
+            detector.update(true, at(600)),
+            Some(FaultChange::Raised(SensorFault::StuckHigh))
+        );
+        assert!(detector.stuck());
+        assert!(detector.next_deadline().is_none());
+
+        assert_eq!(
+            detector.update(false, at(700)),
+            Some(FaultChange::Cleared(SensorFault::StuckHigh))
+        );
+        assert!(!detector.stuck());
+    }
+
+    #[test]
+    fn radar_presence_is_never_stuck() {
+        let start = Instant::now();
+        let config = SensorConfig {
+            backend: SensorBackend::Ld2410,
+            stuck_high_secs: 600,
+            ..SensorConfig::default()
+        };
+        let mut detector = FaultDetector::new(&config);
+        let at = |secs| start + Duration::from_secs(secs);
+
+        assert_eq!(detector.update(true, start), None);
+        assert!(detector.next_deadline().is_none());
+        assert_eq!(detector.update(true, at(86_400)), None);
+        assert!(!detector.stuck());
+    }
+
+    #[test]
+    fn rapid_toggling_is_flapping_until_a_quiet_window() {
+        let start = Instant::now();
+        let mut detector = detector(0, 5);
+        let at = |secs| start + Duration::from_secs(secs);
+
+        assert_eq!(detector.update(false, start), None);
+        let mut changes = Vec::new();
+        for secs in 1..=5 {
+            changes.extend(detector.update(true, at(secs * 2)));
+            changes.extend(detector.update(false, at(secs * 2 + 1)));
+        }
+        assert_eq!(changes, vec![FaultChange::Raised(SensorFault::Flapping)]);
+
+        // Quiet for a full window after the last rising edge at 10s
+        assert_eq!(detector.next_deadline(), Some(at(70)));
+        assert_eq!(detector.update(false, at(69)), None);
+        assert_eq!(
+            detector.update(false, at(70)),
+            Some(FaultChange::Cleared(SensorFault::Flapping))
+        );
+        assert!(detector.next_deadline().is_none());
+    }
+
+    #[test]
+    fn normal_motion_raises_no_fault() {
+        let start = Instant::now();
+        let mut detector = detector(3600, 20);
+
+        for secs in 0..30 {
+            let now = start + Duration::from_secs(secs * 10);
+            assert_eq!(detector.update(secs % 2 == 0, now), None);
+        }
+    }
+}
@@ -0,0 +1,665 @@
+//! HLK-LD2410 mmWave radar on a UART.
+//!
+//! The module streams a report frame about ten times a second:
+//!
+//! ```text
+//! F4 F3 F2 F1  len:u16  02 AA  state  moving_cm:u16 moving_energy
+//!     static_cm:u16 static_energy  detection_cm:u16  55 00  F8 F7 F6 F5
+//! ```
+//!
+//! Engineering-mode reports (type `01`) append per-gate energies to the same
+//! target fields, which are all that is read. Any target counts as the raw
+//! active level fed to the motion logic. Gate settings are written at startup
+//! with command frames (`FD FC FB FA len:u16 command:u16 value.. 04 03 02 01`),
+//! each answered by an ACK frame with `command | 0x0100`.
+
+use serde::Serialize;
+use std::future;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::sync::{mpsc, watch};
+use tokio_serial::{SerialPortBuilderExt, SerialStream};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, warn};
+
+use super::{
+    process_level, send_event, MotionLogic, MotionSource, MotionSourceFuture, SensorEvent,
+    EDGE_HEALTH_INTERVAL,
+};
+use crate::config::{RadarConfig, SensorConfig};
+use crate::error::SensorError;
+use crate::trace::{TraceEvent, TraceRecorder};
+
+const REPORT_HEADER: [u8; 4] = [0xF4, 0xF3, 0xF2, 0xF1];
+const REPORT_FOOTER: [u8; 4] = [0xF8, 0xF7, 0xF6, 0xF5];
+const COMMAND_HEADER: [u8; 4] = [0xFD, 0xFC, 0xFB, 0xFA];
+const COMMAND_FOOTER: [u8; 4] = [0x04, 0x03, 0x02, 0x01];
+
+/// Longest frame body accepted; a longer length field is line noise.
+const MAX_BODY_LEN: usize = 64;
+
+const CMD_ENABLE_CONFIG: u16 = 0x00FF;
+const CMD_END_CONFIG: u16 = 0x00FE;
+const CMD_MAX_GATES: u16 = 0x0060;
+const CMD_GATE_SENSITIVITY: u16 = 0x0064;
+
+/// Seconds the module itself keeps reporting a target that has gone (its
+/// default); `no_motion_delay_secs` does the real holding.
+const MODULE_HOLD_SECS: u32 = 5;
+
+/// How long to wait for a command to be acknowledged.
+const ACK_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Without a report for this long the radar counts as stalled, and health
+/// ticks stop so the health monitor restarts the service.
+const REPORT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Shortest time between published readings.
+const READING_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Target readings from one radar report.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct RadarReading {
+    /// A moving target is detected
+    pub moving: bool,
+    /// A stationary target is detected
+    pub stationary: bool,
+    /// Distance to the moving target in cm
+    pub moving_distance_cm: u16,
+    /// Moving target energy (0-100)
+    pub moving_energy: u8,
+    /// Distance to the stationary target in cm
+    pub static_distance_cm: u16,
+    /// Stationary target energy (0-100)
+    pub static_energy: u8,
+    /// Detection distance in cm
+    pub detection_distance_cm: u16,
+}
+
+impl RadarReading {
+    /// Whether anyone is present, moving or not.
+    pub const fn present(&self) -> bool {
+        self.moving || self.stationary
+    }
+}
+
+/// A frame received from the module.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Frame {
+    /// Periodic target report
+    Report(RadarReading),
+    /// Reply to a command
+    Ack { command: u16, success: bool },
+}
+
+/// Splits the serial byte stream into frames, skipping noise.
+#[derive(Debug, Default)]
+struct FrameParser {
+    buf: Vec<u8>,
+}
+
+impl FrameParser {
+    fn push(&mut self, bytes: &[u8]) {
+        self.buf.extend_from_slice(bytes);
+    }
+
+    /// The next complete frame, or `None` until more bytes arrive.
+    fn next_frame(&mut self) -> Option<Frame> {
+        loop {
+            let Some(start) = self.buf.windows(4).position(|window| {
+                window == REPORT_HEADER.as_slice() || window == COMMAND_HEADER.as_slice()
+            }) else {
+                // Keep a possible partial header
+                let keep = self.buf.len().min(3);
+                self.buf.drain(..self.buf.len() - keep);
+                return None;
+            };
+            self.buf.drain(..start);
+            if self.buf.len() < 6 {
+                return None;
+            }
+
+            let body_len = usize::from(u16::from_le_bytes([self.buf[4], self.buf[5]]));
+            let footer = if self.buf[..4] == REPORT_HEADER {
+                REPORT_FOOTER
+            } else {
+                COMMAND_FOOTER
+            };
+            if body_len > MAX_BODY_LEN {
+                self.buf.drain(..1);
+                continue;
+            }
+            let frame_len = 6 + body_len + 4;
+            if self.buf.len() < frame_len {
+                return None;
+            }
+            if self.buf[6 + body_len..frame_len] != footer {
+                self.buf.drain(..1);
+                continue;
+            }
+
+            let body = &self.buf[6..6 + body_len];
+            let frame = if footer == REPORT_FOOTER {
+                parse_report(body).map(Frame::Report)
+            } else {
+                parse_ack(body)
+            };
+            self.buf.drain(..frame_len);
+            if frame.is_some() {
+                return frame;
+            }
+        }
+    }
+}
+
+fn parse_report(body: &[u8]) -> Option<RadarReading> {
+    // Type, head, 9 bytes of targets, ..., tail, check
+    if body.len() < 13
+        || !matches!(body[0], 0x01 | 0x02)
+        || body[1] != 0xAA
+        || body[body.len() - 2] != 0x55
+    {
+        return None;
+    }
+
+    let u16_at = |at: usize| u16::from_le_bytes([body[at], body[at + 1]]);
+    let state = body[2];
+    Some(RadarReading {
+        moving: state & 0x01 != 0,
+        stationary: state & 0x02 != 0,
+        moving_distance_cm: u16_at(3),
+        moving_energy: body[5],
+        static_distance_cm: u16_at(6),
+        static_energy: body[8],
+        detection_distance_cm: u16_at(9),
+    })
+}
+
+fn parse_ack(body: &[u8]) -> Option<Frame> {
+    if body.len() < 4 {
+        return None;
+    }
+    let word = u16::from_le_bytes([body[0], body[1]]);
+    if word & 0x0100 == 0 {
+        return None;
+    }
+    Some(Frame::Ack {
+        command: word & !0x0100,
+        success: u16::from_le_bytes([body[2], body[3]]) == 0,
+    })
+}
+
+/// Encode a command frame.
+fn command_frame(command: u16, value: &[u8]) -> Vec<u8> {
+    let body_len = u16::try_from(2 + value.len()).unwrap_or(u16::MAX);
+    let mut frame = Vec::with_capacity(12 + value.len());
+    frame.extend_from_slice(&COMMAND_HEADER);
+    frame.extend_from_slice(&body_len.to_le_bytes());
+    frame.extend_from_slice(&command.to_le_bytes());
+    frame.extend_from_slice(value);
+    frame.extend_from_slice(&COMMAND_FOOTER);
+    frame
+}
+
+/// Encode `(parameter word, value)` pairs as a command value.
+fn parameters(params: &[(u16, u32)]) -> Vec<u8> {
+    params
+        .iter()
+        .flat_map(|(word, value)| word.to_le_bytes().into_iter().chain(value.to_le_bytes()))
+        .collect()
+}
+
+/// The commands that apply `config`, without entering or leaving
+/// configuration mode.
+fn setup_commands(config: &RadarConfig) -> Vec<(u16, Vec<u8>)> {
+    let mut commands = Vec::new();
+
+    if config.max_moving_gate.is_some() || config.max_static_gate.is_some() {
+        let moving = config.max_moving_gate.unwrap_or(RadarConfig::MAX_GATE);
+        let stationary = config.max_static_gate.unwrap_or(RadarConfig::MAX_GATE);
+        commands.push((
+            CMD_MAX_GATES,
+            parameters(&[
+                (0x0000, u32::from(moving)),
+                (0x0001, u32::from(stationary)),
+                (0x0002, MODULE_HOLD_SECS),
+            ]),
+        ));
+    }
+
+    for (gate, (moving, stationary)) in config
+        .moving_sensitivity
+        .iter()
+        .zip(&config.static_sensitivity)
+        .enumerate()
+    {
+        commands.push((
+            CMD_GATE_SENSITIVITY,
+            parameters(&[
+                (0x0000, u32::try_from(gate).unwrap_or(u32::MAX)),
+                (0x0001, u32::from(*moving)),
+                (0x0002, u32::from(*stationary)),
+            ]),
+        ));
+    }
+
+    commands
+}
+
+/// An LD2410 radar on a serial port.
+pub struct Ld2410Sensor {
+    index: usize,
+    config: SensorConfig,
+    port: SerialStream,
+    parser: FrameParser,
+    trace: TraceRecorder,
+}
+
+impl Ld2410Sensor {
+    /// Open the configured serial port.
+    ///
+    /// `index` identifies the sensor in the events it sends; presence changes
+    /// are recorded to `trace`.
+    pub fn open(
+        config: &SensorConfig,
+        index: usize,
+        trace: TraceRecorder,
+    ) -> Result<Self, SensorError> {
+        info!(
+            sensor = %config.name(),
+            port = %config.port,
+            baud_rate = config.baud_rate,
+            "Opening LD2410 radar"
+        );
+        let port = tokio_serial::new(&config.port, config.baud_rate).open_native_async()?;
+        Ok(Self::with_port(config, index, trace, port))
+    }
+
+    fn with_port(
+        config: &SensorConfig,
+        index: usize,
+        trace: TraceRecorder,
+        port: SerialStream,
+    ) -> Self {
+        Self {
+            index,
+            config: config.clone(),
+            port,
+            parser: FrameParser::default(),
+            trace,
+        }
+    }
+
+    /// Write the configured gate settings, if any.
+    async fn configure(&mut self) -> Result<(), SensorError> {
+        let commands = setup_commands(&self.config.radar);
+        if commands.is_empty() {
+            return Ok(());
+        }
+
+        self.command(CMD_ENABLE_CONFIG, &1_u16.to_le_bytes())
+            .await?;
+        let mut result = Ok(());
+        for (command, value) in &commands {
+            result = self.command(*command, value).await;
+            if result.is_err() {
+                break;
+            }
+        }
+        // Always leave configuration mode so the module resumes reporting
+        let ended = self.command(CMD_END_CONFIG, &[]).await;
+        result.and(ended)?;
+
+        info!(radar = ?self.config.radar, "LD2410 radar configured");
+        Ok(())
+    }
+
+    /// Send a command and wait for its ACK, skipping reports meanwhile.
+    async fn command(&mut self, command: u16, value: &[u8]) -> Result<(), SensorError> {
+        let failed = |reason: String| SensorError::RadarCommand { command, reason };
+
+        self.port
+            .write_all(&command_frame(command, value))
+            .await
+            .map_err(|e| failed(e.to_string()))?;
+
+        let deadline = tokio::time::Instant::now() + ACK_TIMEOUT;
+        let mut buf = [0_u8; 64];
+        loop {
+            while let Some(frame) = self.parser.next_frame() {
+                match frame {
+                    Frame::Ack {
+                        command: acked,
+                        success,
+                    } if acked == command => {
+                        return if success {
+                            Ok(())
+                        } else {
+                            Err(failed("rejected by the module".to_string()))
+                        };
+                    }
+                    _ => {}
+                }
+            }
+
+            let read = tokio::time::timeout_at(deadline, self.port.read(&mut buf))
+                .await
+                .map_err(|_| failed("no acknowledgement".to_string()))?;
+            match read {
+                Ok(0) => return Err(failed("serial port closed".to_string())),
+                Ok(n) => self.parser.push(&buf[..n]),
+                Err(e) => return Err(failed(e.to_string())),
+            }
+        }
+    }
+
+    /// Run the radar loop, sending events to the provided channel.
+    ///
+    /// The first report is sent as [`SensorEvent::Ready`]; afterwards
+    /// presence goes through the motion logic like a PIR level, and target
+    /// readings are sent at most once per second when they change. This
+    /// method runs indefinitely until cancelled.
+    pub async fn run(
+        mut self,
+        tx: mpsc::Sender<SensorEvent>,
+        shutdown: CancellationToken,
+        health_tx: watch::Sender<Instant>,
+        initial_state: Option<bool>,
+    ) {
+        if let Err(e) = self.configure().await {
+            warn!(error = %e, "Failed to configure LD2410 radar; using its stored settings");
+        }
+
+        let mut logic = initial_state.map(|state| MotionLogic::new(&self.config, state));
+        let mut health_tick = tokio::time::interval(EDGE_HEALTH_INTERVAL);
+        health_tick.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        let mut last_report = Instant::now();
+        let mut last_published: Option<(Instant, RadarReading)> = None;
+        let mut buf = [0_u8; 256];
+
+        info!(
+            no_motion_delay_secs = self.config.no_motion_delay_secs,
+            "Starting LD2410 radar"
+        );
+
+        loop {
+            // Configuration reads can leave reports behind in the parser
+            while let Some(frame) = self.parser.next_frame() {
+                let Frame::Report(reading) = frame else {
+                    continue;
+                };
+                let now = Instant::now();
+                last_report = now;
+
+                let present = reading.present();
+                if let Some(logic) = logic.as_mut() {
+                    if !process_level(self.index, &self.trace, logic, present, now, &tx).await {
+                        return;
+                    }
+                } else {
+                    info!(present, "First LD2410 report received");
+                    let ready = SensorEvent::Ready {
+                        sensor: self.index,
+                        motion_detected: present,
+                    };
+                    if !send_event(&tx, ready).await {
+                        return;
+                    }
+                    self.trace.record(TraceEvent::Raw {
+                        sensor: self.index,
+                        active: present,
+                    });
+                    logic = Some(MotionLogic::new(&self.config, present));
+                }
+
+                let due = last_published.is_none_or(|(at, published)| {
+                    published != reading && now.duration_since(at) >= READING_INTERVAL
+                });
+                if due {
+                    last_published = Some((now, reading));
+                    let event = SensorEvent::Radar {
+                        sensor: self.index,
+                        reading,
+                    };
+                    if !send_event(&tx, event).await {
+                        return;
+                    }
+                }
+            }
+
+            let deadline = logic.as_ref().and_then(MotionLogic::next_deadline);
+
+            tokio::select! {
+                () = shutdown.cancelled() => {
+                    info!("LD2410 radar shutting down");
+                    break;
+                }
+                read = self.port.read(&mut buf) => match read {
+                    Ok(0) => {
+                        error!("LD2410 serial port closed");
+                        break;
+                    }
+                    Ok(n) => self.parser.push(&buf[..n]),
+                    Err(e) => {
+                        error!(error = %e, "Failed to read LD2410 serial port");
+                        break;
+                    }
+                },
+                () = async {
+                    match deadline {
+                        Some(deadline) => tokio::time::sleep_until(deadline.into()).await,
+                        None => future::pending().await,
+                    }
+                } => {
+                    if let Some(logic) = logic.as_mut() {
+                        let raw = logic.raw();
+                        if !process_level(self.index, &self.trace, logic, raw, Instant::now(), &tx).await {
+                            break;
+                        }
+                    }
+                }
+                _ = health_tick.tick() => {
+                    if last_report.elapsed() < REPORT_TIMEOUT {
+                        if health_tx.send(Instant::now()).is_err() {
+                            debug!("Radar health receiver dropped");
+                        }
+                    } else {
+                        warn!(
+                            silent_secs = last_report.elapsed().as_secs(),
+                            "No reports from LD2410 radar"
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl MotionSource for Ld2410Sensor {
+    /// Presence is unknown until the first report arrives.
+    fn initial_state(&self) -> Option<bool> {
+        None
+    }
+
+    fn run(
+        self: Box<Self>,
+        tx: mpsc::Sender<SensorEvent>,
+        shutdown: CancellationToken,
+        health_tx: watch::Sender<Instant>,
+        initial_state: Option<bool>,
+    ) -> MotionSourceFuture {
+        Box::pin(Self::run(*self, tx, shutdown, health_tx, initial_state))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::SensorBackend;
+    use crate::sensor::MotionEvent;
+
+    /// A basic report captured from a module: moving and stationary target.
+    const CAPTURED_REPORT: [u8; 23] = [
+        0xF4, 0xF3, 0xF2, 0xF1, 0x0D, 0x00, 0x02, 0xAA, 0x03, 0x4E, 0x00, 0x3C, 0x64, 0x00, 0x52,
+        0x78, 0x00, 0x55, 0x00, 0xF8, 0xF7, 0xF6, 0xF5,
+    ];
+
+    fn report(state: u8) -> Vec<u8> {
+        let mut frame = CAPTURED_REPORT.to_vec();
+        frame[8] = state;
+        frame
+    }
+
+    async fn recv(rx: &mut mpsc::Receiver<SensorEvent>) -> SensorEvent {
+        tokio::time::timeout(Duration::from_secs(1), rx.recv())
+            .await
+            .expect("no event within 1s")
+            .expect("sensor channel closed")
+    }
+
+    fn ack(command: u16) -> Vec<u8> {
+        let mut body = (command | 0x0100).to_le_bytes().to_vec();
+        body.extend_from_slice(&[0x00, 0x00]);
+        let mut frame = COMMAND_HEADER.to_vec();
+        frame.extend_from_slice(&u16::try_from(body.len()).expect("short body").to_le_bytes());
+        frame.extend_from_slice(&body);
+        frame.extend_from_slice(&COMMAND_FOOTER);
+        frame
+    }
+
+    #[test]
+    fn captured_report_is_parsed() {
+        let mut parser = FrameParser::default();
+        parser.push(&CAPTURED_REPORT);
+
+        assert_eq!(
+            parser.next_frame(),
+            Some(Frame::Report(RadarReading {
+                moving: true,
+                stationary: true,
+                moving_distance_cm: 78,
+                moving_energy: 60,
+                static_distance_cm: 100,
+                static_energy: 82,
+                detection_distance_cm: 120,
+            }))
+        );
+        assert_eq!(parser.next_frame(), None);
+    }
+
+    #[test]
+    fn parser_resyncs_after_noise_and_split_reads() {
+        let mut parser = FrameParser::default();
+        let mut stream = vec![0x00, 0xF4, 0x12];
+        stream.extend(report(0x00));
+        stream.extend(ack(CMD_END_CONFIG));
+        stream.extend(report(0x02));
+
+        let (first, rest) = stream.split_at(10);
+        parser.push(first);
+        assert_eq!(parser.next_frame(), None);
+        parser.push(rest);
+
+        let mut frames = Vec::new();
+        while let Some(frame) = parser.next_frame() {
+            frames.push(frame);
+        }
+        assert_eq!(frames.len(), 3);
+        assert!(matches!(frames[0], Frame::Report(reading) if !reading.present()));
+        assert_eq!(
+            frames[1],
+            Frame::Ack {
+                command: CMD_END_CONFIG,
+                success: true
+            }
+        );
+        assert!(matches!(frames[2], Frame::Report(reading) if reading.stationary));
+    }
+
+    #[test]
+    fn gate_settings_become_commands() {
+        let config = RadarConfig {
+            max_moving_gate: Some(6),
+            max_static_gate: None,
+            moving_sensitivity: vec![50],
+            static_sensitivity: vec![0],
+        };
+        let commands = setup_commands(&config);
+
+        assert_eq!(commands.len(), 2);
+        assert_eq!(commands[0].0, CMD_MAX_GATES);
+        assert_eq!(
+            commands[0].1,
+            [0, 0, 6, 0, 0, 0, 1, 0, 8, 0, 0, 0, 2, 0, 5, 0, 0, 0]
+        );
+        assert_eq!(
+            command_frame(commands[1].0, &commands[1].1),
+            [
+                0xFD, 0xFC, 0xFB, 0xFA, 0x14, 0x00, 0x64, 0x00, 0, 0, 0, 0, 0, 0, 1, 0, 50, 0, 0,
+                0, 2, 0, 0, 0, 0, 0, 0x04, 0x03, 0x02, 0x01
+            ]
+        );
+        assert!(setup_commands(&RadarConfig::default()).is_empty());
+    }
+
+    /// Replay captured frames through a pseudo-terminal, configuring the
+    /// module first.
+    #[tokio::test]
+    async fn radar_on_a_pty_reports_presence_and_readings() {
+        let (mut module, port) = SerialStream::pair().expect("failed to open pty pair");
+        let config = SensorConfig {
+            backend: SensorBackend::Ld2410,
+            no_motion_delay_secs: 0,
+            radar: RadarConfig {
+                max_moving_gate: Some(6),
+                ..RadarConfig::default()
+            },
+            ..SensorConfig::default()
+        };
+        let sensor = Ld2410Sensor::with_port(&config, 0, TraceRecorder::default(), port);
+        let (tx, mut rx) = mpsc::channel(10);
+        let (health_tx, _health_rx) = watch::channel(Instant::now());
+        let shutdown = CancellationToken::new();
+        let task = tokio::spawn(sensor.run(tx, shutdown.clone(), health_tx, None));
+
+        // Acknowledge each configuration command as the module would
+        let mut buf = [0_u8; 64];
+        for command in [CMD_ENABLE_CONFIG, CMD_MAX_GATES, CMD_END_CONFIG] {
+            let n = module.read(&mut buf).await.expect("command read failed");
+            assert_eq!(&buf[6..8], command.to_le_bytes());
+            assert!(n > 0);
+            module.write_all(&ack(command)).await.expect("ack failed");
+        }
+
+        module
+            .write_all(&report(0x00))
+            .await
+            .expect("report failed");
+        assert_eq!(
+            recv(&mut rx).await,
+            SensorEvent::Ready {
+                sensor: 0,
+                motion_detected: false
+            }
+        );
+        assert!(matches!(
+            recv(&mut rx).await,
+            SensorEvent::Radar { reading, .. } if !reading.present()
+        ));
+
+        module
+            .write_all(&report(0x01))
+            .await
+            .expect("report failed");
+        assert_eq!(
+            recv(&mut rx).await,
+            SensorEvent::Motion {
+                sensor: 0,
+                event: MotionEvent::Detected
+            }
+        );
+
+        shutdown.cancel();
+        task.await.expect("radar task panicked");
+    }
+}
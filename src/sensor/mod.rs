@@ -1,13 +1,39 @@
-//! PIR sensor module using rppal GPIO.
+//! Motion sensors: PIRs on rppal or Linux character-device GPIO, and LD2410
+//! mmWave radars on a serial port.
 
-use rppal::gpio::{Gpio, InputPin, Level};
+mod cdev;
+mod debounce;
+mod fault;
+mod ld2410;
+#[cfg(test)]
+mod simulated;
+
+use rppal::gpio::{Gpio, InputPin, Level, Trigger};
+use std::future::{self, Future};
+use std::pin::Pin;
 use std::time::{Duration, Instant};
 use tokio::sync::{mpsc, watch};
 use tokio_util::sync::CancellationToken;
 use tracing::{debug, error, info, warn};
 
-use crate::config::SensorConfig;
+use crate::config::{Polarity, PullMode, SensorBackend, SensorConfig, SensorMode};
 use crate::error::SensorError;
+use crate::trace::{TraceEvent, TraceRecorder};
+
+use cdev::CdevInput;
+use debounce::GlitchFilter;
+use fault::FaultDetector;
+pub use fault::{FaultChange, SensorFault};
+use ld2410::Ld2410Sensor;
+pub use ld2410::RadarReading;
+#[cfg(test)]
+pub use simulated::SimulatedSource;
+
+/// Health tick interval while waiting for GPIO interrupts or warm-up.
+const EDGE_HEALTH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Kernel uptime, used to skip warm-up when the sensor has long been powered.
+const UPTIME_PATH: &str = "/proc/uptime";
 
 /// Motion events from the PIR sensor.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -18,63 +44,492 @@ pub enum MotionEvent {
     Cleared,
 }
 
+/// Events from a sensor task, tagged with the sensor's index into `Config::sensors()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SensorEvent {
+    /// The sensor's motion state changed
+    Motion { sensor: usize, event: MotionEvent },
+    /// The sensor finished warming up and reports its first real state
+    Ready {
+        sensor: usize,
+        motion_detected: bool,
+    },
+    /// A suspected hardware fault was detected or went away
+    Fault { sensor: usize, change: FaultChange },
+    /// New target readings from a radar
+    Radar {
+        sensor: usize,
+        reading: RadarReading,
+    },
+}
+
+/// Boxed future returned by [`MotionSource::run`].
+pub type MotionSourceFuture = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// A source of motion events, such as a GPIO PIR sensor.
+pub trait MotionSource: Send {
+    /// Initial motion state, or `None` while the source is still warming up.
+    fn initial_state(&self) -> Option<bool>;
+
+    /// Run until `shutdown` is cancelled, sending events to `tx` and a health
+    /// tick to `health_tx` at least every few seconds.
+    ///
+    /// Without an `initial_state` the source reports its first state as
+    /// [`SensorEvent::Ready`].
+    fn run(
+        self: Box<Self>,
+        tx: mpsc::Sender<SensorEvent>,
+        shutdown: CancellationToken,
+        health_tx: watch::Sender<Instant>,
+        initial_state: Option<bool>,
+    ) -> MotionSourceFuture;
+}
+
+/// Open the motion source for a sensor's configured backend.
+pub fn open(
+    config: &SensorConfig,
+    index: usize,
+    trace: TraceRecorder,
+) -> Result<Box<dyn MotionSource>, SensorError> {
+    match config.backend {
+        SensorBackend::Rppal | SensorBackend::Cdev => {
+            Ok(Box::new(PirSensor::new(config, index, trace)?))
+        }
+        SensorBackend::Ld2410 => Ok(Box::new(Ld2410Sensor::open(config, index, trace)?)),
+    }
+}
+
+/// Tracks motion state from raw GPIO levels and the no-motion delay.
+#[derive(Debug)]
+struct MotionTracker {
+    no_motion_delay: Duration,
+    last_state: bool,
+    motion_active: bool,
+    low_since: Option<Instant>,
+}
+
+impl MotionTracker {
+    const fn new(no_motion_delay: Duration, initial_state: bool) -> Self {
+        Self {
+            no_motion_delay,
+            last_state: initial_state,
+            motion_active: initial_state,
+            low_since: None,
+        }
+    }
+
+    /// Feed the current GPIO level, returning an event if the motion state changed.
+    fn update(&mut self, current_state: bool, now: Instant) -> Option<MotionEvent> {
+        let mut event = None;
+
+        if current_state {
+            if !self.last_state {
+                debug!("PIR sensor: rising edge detected");
+            }
+
+            if self.low_since.take().is_some() {
+                info!("No-motion timer cancelled");
+            }
+
+            if !self.motion_active {
+                self.motion_active = true;
+                info!("PIR motion detected");
+                event = Some(MotionEvent::Detected);
+            }
+        } else if self.motion_active {
+            if self.last_state && self.low_since.is_none() {
+                self.low_since = Some(now);
+                info!(
+                    no_motion_delay_secs = self.no_motion_delay.as_secs(),
+                    "No-motion timer started"
+                );
+            }
+
+            if let Some(started_at) = self.low_since {
+                let elapsed = now.duration_since(started_at);
+                if elapsed >= self.no_motion_delay {
+                    let elapsed_ms = u64::try_from(elapsed.as_millis()).unwrap_or(u64::MAX);
+                    info!(elapsed_ms, "No-motion timer fired");
+                    self.motion_active = false;
+                    self.low_since = None;
+                    event = Some(MotionEvent::Cleared);
+                }
+            }
+        }
+
+        self.last_state = current_state;
+        event
+    }
+
+    /// When the running no-motion timer fires, if one is running.
+    fn clear_deadline(&self) -> Option<Instant> {
+        self.low_since
+            .map(|started_at| started_at + self.no_motion_delay)
+    }
+}
+
+/// Glitch filter, fault detection and no-motion tracking for one sensor,
+/// fed raw levels.
+pub struct MotionLogic {
+    filter: GlitchFilter,
+    faults: FaultDetector,
+    tracker: MotionTracker,
+    ignore_stuck: bool,
+    fault_change: Option<FaultChange>,
+    raw: bool,
+}
+
+impl MotionLogic {
+    /// Create the logic for a sensor whose input is at `initial_state`.
+    pub fn new(config: &SensorConfig, initial_state: bool) -> Self {
+        Self {
+            filter: GlitchFilter::new(config, initial_state),
+            faults: FaultDetector::new(config),
+            tracker: MotionTracker::new(
+                Duration::from_secs(config.no_motion_delay_secs),
+                initial_state,
+            ),
+            ignore_stuck: config.ignore_stuck,
+            fault_change: None,
+            raw: initial_state,
+        }
+    }
+
+    /// Feed a raw level, returning an event if the motion state changed.
+    ///
+    /// A stuck input counts as no motion when `ignore_stuck` is set.
+    pub fn update(&mut self, raw: bool, now: Instant) -> Option<MotionEvent> {
+        self.raw = raw;
+        if let Some(change) = self.faults.update(raw, now) {
+            self.fault_change = Some(change);
+        }
+        let filtered = self.filter.sample(raw, now);
+        let active = filtered && !(self.ignore_stuck && self.faults.stuck());
+        self.tracker.update(active, now)
+    }
+
+    /// The fault state change from the last update, if any.
+    pub const fn take_fault_change(&mut self) -> Option<FaultChange> {
+        self.fault_change.take()
+    }
+
+    /// The last raw level fed in.
+    pub const fn raw(&self) -> bool {
+        self.raw
+    }
+
+    /// When the level must be sampled again even without an edge: the
+    /// no-motion timer, a pending pulse confirmation, or a fault threshold.
+    pub fn next_deadline(&self) -> Option<Instant> {
+        [
+            self.tracker.clear_deadline(),
+            self.filter.confirm_deadline(),
+            self.faults.next_deadline(),
+        ]
+        .into_iter()
+        .flatten()
+        .min()
+    }
+}
+
+/// The GPIO input behind a sensor.
+enum GpioInput {
+    /// Raspberry Pi GPIO via rppal; edges arrive from the interrupt thread
+    Rppal {
+        pin: InputPin,
+        active_level: Level,
+        edges: Option<mpsc::UnboundedReceiver<bool>>,
+    },
+    /// Linux GPIO character device
+    Cdev(CdevInput),
+}
+
+impl GpioInput {
+    fn open_rppal(config: &SensorConfig) -> Result<Self, SensorError> {
+        let gpio = Gpio::new()?;
+        let pin = gpio.get(config.gpio_pin).map_err(|e| {
+            error!(pin = config.gpio_pin, error = %e, "Failed to get GPIO pin");
+            SensorError::GpioInit(e)
+        })?;
+        let pin = match config.pull {
+            PullMode::Down => pin.into_input_pulldown(),
+            PullMode::Up => pin.into_input_pullup(),
+            PullMode::None => pin.into_input(),
+        };
+        let active_level = match config.polarity {
+            Polarity::ActiveHigh => Level::High,
+            Polarity::ActiveLow => Level::Low,
+        };
+
+        Ok(Self::Rppal {
+            pin,
+            active_level,
+            edges: None,
+        })
+    }
+
+    /// `true` when the input is at its active level.
+    fn read(&self) -> Result<bool, SensorError> {
+        match self {
+            Self::Rppal {
+                pin, active_level, ..
+            } => Ok(pin.read() == *active_level),
+            Self::Cdev(input) => input.read(),
+        }
+    }
+
+    /// Start delivering edges to [`Self::next_edge`].
+    fn enable_edges(&mut self) -> Result<(), SensorError> {
+        match self {
+            Self::Rppal {
+                pin,
+                active_level,
+                edges,
+            } => {
+                let (edge_tx, edge_rx) = mpsc::unbounded_channel();
+                let active_high = *active_level == Level::High;
+                pin.set_async_interrupt(Trigger::Both, None, move |event| {
+                    let rising = event.trigger == Trigger::RisingEdge;
+                    let _ = edge_tx.send(rising == active_high);
+                })?;
+                *edges = Some(edge_rx);
+                Ok(())
+            }
+            Self::Cdev(input) => input.enable_edges(),
+        }
+    }
+
+    /// Wait for the next edge, returning whether the input became active.
+    ///
+    /// Returns `None` once edge delivery has stopped.
+    async fn next_edge(&mut self) -> Option<bool> {
+        match self {
+            Self::Rppal { edges, .. } => match edges {
+                Some(edges) => edges.recv().await,
+                None => future::pending().await,
+            },
+            Self::Cdev(input) => match input.next_edge().await? {
+                Ok(active) => Some(active),
+                Err(e) => {
+                    error!(error = %e, "Failed to read GPIO line event");
+                    None
+                }
+            },
+        }
+    }
+}
+
 /// PIR motion sensor using GPIO.
 pub struct PirSensor {
-    pin: InputPin,
+    index: usize,
+    input: GpioInput,
     config: SensorConfig,
+    trace: TraceRecorder,
 }
 
 impl PirSensor {
-    /// Create a new PIR sensor on the specified GPIO pin.
-    pub fn new(config: &SensorConfig) -> Result<Self, SensorError> {
-        info!(pin = config.gpio_pin, "Initializing PIR sensor");
+    /// Create a new PIR sensor on the configured GPIO input.
+    ///
+    /// `index` identifies the sensor in the events it sends; raw level
+    /// changes are recorded to `trace`.
+    pub fn new(
+        config: &SensorConfig,
+        index: usize,
+        trace: TraceRecorder,
+    ) -> Result<Self, SensorError> {
+        info!(sensor = %config.name(), input = %config.input_id(), "Initializing PIR sensor");
 
-        let gpio = Gpio::new()?;
-        let pin = gpio
-            .get(config.gpio_pin)
-            .map_err(|e| {
-                error!(pin = config.gpio_pin, error = %e, "Failed to get GPIO pin");
-                SensorError::GpioInit(e)
-            })?
-            .into_input_pulldown();
+        let input = match config.backend {
+            SensorBackend::Rppal => GpioInput::open_rppal(config)?,
+            SensorBackend::Cdev => GpioInput::Cdev(CdevInput::open(config)?),
+            SensorBackend::Ld2410 => return Err(SensorError::NotGpio(config.input_id())),
+        };
 
-        info!(pin = config.gpio_pin, "PIR sensor initialized successfully");
+        info!(
+            sensor = %config.name(),
+            input = %config.input_id(),
+            backend = ?config.backend,
+            pull = ?config.pull,
+            polarity = ?config.polarity,
+            "PIR sensor initialized successfully"
+        );
 
         Ok(Self {
-            pin,
+            index,
+            input,
             config: config.clone(),
+            trace,
         })
     }
 
-    /// Read the current sensor state.
-    pub fn read(&self) -> bool {
-        self.pin.read() == Level::High
+    /// Read the current sensor state (`true` when the input is at its active level).
+    pub fn read(&self) -> Result<bool, SensorError> {
+        self.input.read()
+    }
+
+    /// Read the input inside a sensor loop, logging when reads start and
+    /// stop failing. `None` while failing: the caller skips the sample and
+    /// its health tick, so a dead input gets the service restarted instead
+    /// of reporting no motion forever.
+    fn sample(&self, failing: &mut bool) -> Option<bool> {
+        match self.read() {
+            Ok(active) => {
+                if std::mem::take(failing) {
+                    info!(input = %self.config.input_id(), "GPIO input readable again");
+                }
+                Some(active)
+            }
+            Err(e) => {
+                if !*failing {
+                    error!(
+                        input = %self.config.input_id(),
+                        error = %e,
+                        "Failed to read GPIO input; health ticks stop until it recovers"
+                    );
+                    *failing = true;
+                }
+                None
+            }
+        }
+    }
+
+    /// Warm-up time left since power-up.
+    ///
+    /// The sensor shares the Pi's power, so the kernel uptime stands in for
+    /// the time since the module was powered. Assumes a fresh boot if the
+    /// uptime cannot be read.
+    pub fn warmup_remaining(&self) -> Duration {
+        let warmup = Duration::from_secs(self.config.warmup_secs);
+        if warmup.is_zero() {
+            return warmup;
+        }
+
+        let uptime = std::fs::read_to_string(UPTIME_PATH)
+            .ok()
+            .and_then(|contents| parse_uptime(&contents));
+        uptime.map_or(warmup, |uptime| warmup.saturating_sub(uptime))
     }
 
-    /// Run the sensor polling loop, sending events to the provided channel.
+    /// Run the sensor loop, sending events to the provided channel.
     ///
-    /// This method runs indefinitely until cancelled.
+    /// Without an `initial_state` the sensor first waits out its warm-up and
+    /// reports the settled state as [`SensorEvent::Ready`]. Uses GPIO
+    /// interrupts in edge mode, falling back to polling if the interrupt
+    /// cannot be configured. This method runs indefinitely until cancelled.
     pub async fn run(
+        mut self,
+        tx: mpsc::Sender<SensorEvent>,
+        shutdown: CancellationToken,
+        health_tx: watch::Sender<Instant>,
+        initial_state: Option<bool>,
+    ) {
+        let initial_state = match initial_state {
+            Some(state) => state,
+            None => match self.warm_up(&tx, &shutdown, &health_tx).await {
+                Some(state) => state,
+                None => return,
+            },
+        };
+
+        let logic = MotionLogic::new(&self.config, initial_state);
+        self.trace.record(TraceEvent::Raw {
+            sensor: self.index,
+            active: initial_state,
+        });
+
+        if self.config.mode == SensorMode::Edge {
+            match self.input.enable_edges() {
+                Ok(()) => {
+                    self.run_edge(logic, tx, shutdown, health_tx).await;
+                    return;
+                }
+                Err(e) => {
+                    warn!(
+                        input = %self.config.input_id(),
+                        error = %e,
+                        "Failed to configure GPIO interrupt; falling back to polling"
+                    );
+                }
+            }
+        }
+
+        self.run_polling(logic, tx, shutdown, health_tx).await;
+    }
+
+    /// Ignore the input until warm-up ends, then report the settled state.
+    ///
+    /// Returns `None` if shut down or the receiver is gone.
+    async fn warm_up(
         &self,
-        tx: mpsc::Sender<MotionEvent>,
+        tx: &mpsc::Sender<SensorEvent>,
+        shutdown: &CancellationToken,
+        health_tx: &watch::Sender<Instant>,
+    ) -> Option<bool> {
+        let remaining = self.warmup_remaining();
+        let warmup_end = tokio::time::Instant::now() + remaining;
+        let mut health_tick = tokio::time::interval(EDGE_HEALTH_INTERVAL);
+        health_tick.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+        info!(
+            warmup_secs = self.config.warmup_secs,
+            remaining_ms = u64::try_from(remaining.as_millis()).unwrap_or(u64::MAX),
+            "PIR sensor warming up; ignoring input"
+        );
+
+        loop {
+            tokio::select! {
+                () = shutdown.cancelled() => {
+                    info!("PIR sensor shutting down");
+                    return None;
+                }
+                () = tokio::time::sleep_until(warmup_end) => break,
+                _ = health_tick.tick() => {
+                    if health_tx.send(Instant::now()).is_err() {
+                        debug!("PIR health receiver dropped");
+                    }
+                }
+            }
+        }
+
+        let motion_detected = self.read().unwrap_or_else(|e| {
+            error!(error = %e, "Failed to read GPIO input after warm-up");
+            false
+        });
+        info!(gpio_active = motion_detected, "PIR sensor warm-up complete");
+
+        let event = SensorEvent::Ready {
+            sensor: self.index,
+            motion_detected,
+        };
+        if let Err(e) = tx.send(event).await {
+            warn!(event = ?e.0, error = %e, "Failed to send sensor ready event");
+            return None;
+        }
+
+        Some(motion_detected)
+    }
+
+    /// Sample the GPIO level every `poll_interval_ms`.
+    async fn run_polling(
+        &self,
+        mut logic: MotionLogic,
+        tx: mpsc::Sender<SensorEvent>,
         shutdown: CancellationToken,
         health_tx: watch::Sender<Instant>,
-        initial_state: bool,
     ) {
         let poll_interval = Duration::from_millis(self.config.poll_interval_ms);
-        let no_motion_delay = Duration::from_secs(self.config.no_motion_delay_secs);
-
-        let mut last_state = initial_state;
-        let mut motion_active = initial_state;
-        let mut low_since: Option<Instant> = None;
 
         info!(
             poll_interval_ms = self.config.poll_interval_ms,
             no_motion_delay_secs = self.config.no_motion_delay_secs,
-            initial_gpio_active = initial_state,
+            min_pulse_ms = self.config.min_pulse_ms,
+            filter_window = self.config.filter_window,
+            filter_threshold = self.config.filter_threshold,
+            initial_gpio_active = logic.raw(),
             "Starting PIR sensor polling"
         );
 
+        let mut read_failed = false;
         loop {
             tokio::select! {
                 () = shutdown.cancelled() => {
@@ -83,87 +538,251 @@ impl PirSensor {
                 }
                 () = tokio::time::sleep(poll_interval) => {
                     let now = Instant::now();
-                    let current_state = self.read();
+                    let Some(raw) = self.sample(&mut read_failed) else {
+                        continue;
+                    };
                     if health_tx.send(now).is_err() {
                         debug!("PIR health receiver dropped");
                     }
 
-                    if current_state {
-                        if !last_state {
-                            debug!("PIR sensor: rising edge detected");
-                        }
-
-                        if low_since.take().is_some() {
-                            info!("No-motion timer cancelled");
-                        }
-
-                        if !motion_active {
-                            motion_active = true;
-                            info!("PIR motion detected");
-                            if !send_motion_event(&tx, MotionEvent::Detected).await {
-                                break;
-                            }
-                        }
-                    } else if motion_active {
-                        if last_state && low_since.is_none() {
-                            low_since = Some(now);
-                            info!(
-                                no_motion_delay_secs = self.config.no_motion_delay_secs,
-                                "No-motion timer started"
-                            );
-                        }
-
-                        if let Some(started_at) = low_since {
-                            let elapsed = now.duration_since(started_at);
-                            if elapsed >= no_motion_delay {
-                                let elapsed_ms = u64::try_from(elapsed.as_millis()).unwrap_or(u64::MAX);
-                                info!(elapsed_ms, "No-motion timer fired");
-                                motion_active = false;
-                                low_since = None;
-                                if !send_motion_event(&tx, MotionEvent::Cleared).await {
-                                    break;
-                                }
-                            }
-                        }
+                    if !self.process(&mut logic, raw, now, &tx).await {
+                        break;
                     }
+                }
+            }
+        }
+    }
+
+    /// Wait for GPIO edges, waking only for interrupts, the no-motion and
+    /// pulse confirmation timers, and periodic health ticks.
+    async fn run_edge(
+        &mut self,
+        mut logic: MotionLogic,
+        tx: mpsc::Sender<SensorEvent>,
+        shutdown: CancellationToken,
+        health_tx: watch::Sender<Instant>,
+    ) {
+        let mut health_tick = tokio::time::interval(EDGE_HEALTH_INTERVAL);
+        health_tick.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+        info!(
+            no_motion_delay_secs = self.config.no_motion_delay_secs,
+            min_pulse_ms = self.config.min_pulse_ms,
+            initial_gpio_active = logic.raw(),
+            "Starting PIR sensor edge detection"
+        );
+
+        let mut read_failed = false;
+        loop {
+            let deadline = logic.next_deadline();
 
-                    last_state = current_state;
+            let current_state = tokio::select! {
+                () = shutdown.cancelled() => {
+                    info!("PIR sensor shutting down");
+                    break;
+                }
+                edge = self.input.next_edge() => {
+                    let Some(active) = edge else {
+                        error!("GPIO interrupt handler stopped");
+                        break;
+                    };
+                    Some(active)
                 }
+                () = async {
+                    match deadline {
+                        Some(deadline) => tokio::time::sleep_until(deadline.into()).await,
+                        None => future::pending().await,
+                    }
+                } => self.sample(&mut read_failed),
+                _ = health_tick.tick() => {
+                    // Re-sync with the pin level in case an edge was missed
+                    let level = self.sample(&mut read_failed);
+                    if level.is_some() && health_tx.send(Instant::now()).is_err() {
+                        debug!("PIR health receiver dropped");
+                    }
+                    level
+                }
+            };
+            let Some(current_state) = current_state else {
+                continue;
+            };
+
+            if !self
+                .process(&mut logic, current_state, Instant::now(), &tx)
+                .await
+            {
+                break;
             }
         }
     }
+
+    /// Feed a raw level through the motion logic and send any resulting
+    /// events. Returns `false` once the receiver is gone.
+    async fn process(
+        &self,
+        logic: &mut MotionLogic,
+        raw: bool,
+        now: Instant,
+        tx: &mpsc::Sender<SensorEvent>,
+    ) -> bool {
+        process_level(self.index, &self.trace, logic, raw, now, tx).await
+    }
 }
 
-async fn send_motion_event(tx: &mpsc::Sender<MotionEvent>, event: MotionEvent) -> bool {
+/// Feed a raw level through a sensor's motion logic, recording level changes
+/// to `trace` and sending any resulting events. Returns `false` once the
+/// receiver is gone.
+async fn process_level(
+    sensor: usize,
+    trace: &TraceRecorder,
+    logic: &mut MotionLogic,
+    raw: bool,
+    now: Instant,
+    tx: &mpsc::Sender<SensorEvent>,
+) -> bool {
+    if raw != logic.raw() {
+        trace.record(TraceEvent::Raw {
+            sensor,
+            active: raw,
+        });
+    }
+
+    if let Some(event) = logic.update(raw, now) {
+        if !send_event(tx, SensorEvent::Motion { sensor, event }).await {
+            return false;
+        }
+    }
+
+    if let Some(change) = logic.take_fault_change() {
+        if !send_event(tx, SensorEvent::Fault { sensor, change }).await {
+            return false;
+        }
+    }
+
+    true
+}
+
+async fn send_event(tx: &mpsc::Sender<SensorEvent>, event: SensorEvent) -> bool {
     match tx.send(event).await {
         Ok(()) => true,
         Err(e) => {
-            warn!(event = ?e.0, error = %e, "Failed to send motion event");
+            warn!(event = ?e.0, error = %e, "Failed to send sensor event");
             false
         }
     }
 }
 
-/// Mock PIR sensor for testing without hardware.
-#[cfg(test)]
-pub struct MockPirSensor {
-    state: std::sync::atomic::AtomicBool,
+impl MotionSource for PirSensor {
+    fn initial_state(&self) -> Option<bool> {
+        if !self.warmup_remaining().is_zero() {
+            return None;
+        }
+        self.read()
+            .inspect_err(|e| warn!(error = %e, "Failed to read initial GPIO level"))
+            .ok()
+    }
+
+    fn run(
+        self: Box<Self>,
+        tx: mpsc::Sender<SensorEvent>,
+        shutdown: CancellationToken,
+        health_tx: watch::Sender<Instant>,
+        initial_state: Option<bool>,
+    ) -> MotionSourceFuture {
+        Box::pin(Self::run(*self, tx, shutdown, health_tx, initial_state))
+    }
+}
+
+/// Parse seconds since boot from the contents of `/proc/uptime`.
+fn parse_uptime(contents: &str) -> Option<Duration> {
+    let secs: f64 = contents.split_whitespace().next()?.parse().ok()?;
+    Duration::try_from_secs_f64(secs).ok()
 }
 
 #[cfg(test)]
-impl MockPirSensor {
-    pub fn new() -> Self {
-        Self {
-            state: std::sync::atomic::AtomicBool::new(false),
-        }
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_proc_uptime() {
+        assert_eq!(
+            parse_uptime("42.50 160.13\n"),
+            Some(Duration::from_millis(42_500))
+        );
+        assert_eq!(parse_uptime(""), None);
+        assert_eq!(parse_uptime("garbage"), None);
+    }
+
+    #[test]
+    fn rising_edge_reports_detected_once() {
+        let start = Instant::now();
+        let mut tracker = MotionTracker::new(Duration::from_secs(5), false);
+
+        assert_eq!(tracker.update(true, start), Some(MotionEvent::Detected));
+        assert_eq!(tracker.update(true, start + Duration::from_secs(1)), None);
+        assert!(tracker.clear_deadline().is_none());
+    }
+
+    #[test]
+    fn falling_edge_clears_after_no_motion_delay() {
+        let start = Instant::now();
+        let mut tracker = MotionTracker::new(Duration::from_secs(5), true);
+
+        assert_eq!(tracker.update(false, start), None);
+        assert_eq!(
+            tracker.clear_deadline(),
+            Some(start + Duration::from_secs(5))
+        );
+        assert_eq!(tracker.update(false, start + Duration::from_secs(4)), None);
+        assert_eq!(
+            tracker.update(false, start + Duration::from_secs(5)),
+            Some(MotionEvent::Cleared)
+        );
+        assert!(tracker.clear_deadline().is_none());
     }
 
-    pub fn set_motion(&self, detected: bool) {
-        self.state
-            .store(detected, std::sync::atomic::Ordering::SeqCst);
+    #[test]
+    fn ignored_stuck_sensor_clears_motion() {
+        let start = Instant::now();
+        let config = SensorConfig {
+            stuck_high_secs: 600,
+            ignore_stuck: true,
+            ..SensorConfig::default()
+        };
+        let mut logic = MotionLogic::new(&config, false);
+        let at = |secs| start + Duration::from_secs(secs);
+
+        assert_eq!(logic.update(true, at(0)), Some(MotionEvent::Detected));
+        assert_eq!(logic.next_deadline(), Some(at(600)));
+        assert_eq!(logic.update(true, at(600)), None);
+        assert_eq!(
+            logic.take_fault_change(),
+            Some(FaultChange::Raised(SensorFault::StuckHigh))
+        );
+        assert_eq!(logic.update(true, at(605)), Some(MotionEvent::Cleared));
+
+        // Once the input recovers, new motion is reported again
+        assert_eq!(logic.update(false, at(700)), None);
+        assert_eq!(
+            logic.take_fault_change(),
+            Some(FaultChange::Cleared(SensorFault::StuckHigh))
+        );
+        assert_eq!(logic.update(true, at(710)), Some(MotionEvent::Detected));
     }
 
-    pub fn read(&self) -> bool {
-        self.state.load(std::sync::atomic::Ordering::SeqCst)
+    #[test]
+    fn motion_during_delay_cancels_clear_timer() {
+        let start = Instant::now();
+        let mut tracker = MotionTracker::new(Duration::from_secs(5), true);
+
+        assert_eq!(tracker.update(false, start), None);
+        assert_eq!(tracker.update(true, start + Duration::from_secs(2)), None);
+        assert!(tracker.clear_deadline().is_none());
+        assert_eq!(tracker.update(false, start + Duration::from_secs(3)), None);
+        assert_eq!(tracker.update(false, start + Duration::from_secs(7)), None);
+        assert_eq!(
+            tracker.update(false, start + Duration::from_secs(8)),
+            Some(MotionEvent::Cleared)
+        );
     }
 }
@@ -0,0 +1,111 @@
+//! Simulated motion source for exercising the main loop without GPIO.
+
+use std::time::Instant;
+use tokio::sync::{mpsc, watch};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info};
+
+use super::{MotionEvent, MotionSource, MotionSourceFuture, SensorEvent};
+
+/// Input scripted through a [`SimulatedHandle`].
+#[derive(Debug)]
+enum SimulatedInput {
+    Motion(MotionEvent),
+    Ready(bool),
+    Tick(Instant),
+}
+
+/// A motion source driven by a [`SimulatedHandle`].
+///
+/// The source stops, closing its event channel, when the handle is dropped.
+pub struct SimulatedSource {
+    index: usize,
+    initial_state: Option<bool>,
+    inputs: mpsc::UnboundedReceiver<SimulatedInput>,
+}
+
+/// Drives a [`SimulatedSource`].
+#[derive(Debug, Clone)]
+pub struct SimulatedHandle {
+    inputs: mpsc::UnboundedSender<SimulatedInput>,
+}
+
+impl SimulatedSource {
+    /// Create a source reporting as sensor `index`, plus its handle.
+    pub fn new(index: usize, initial_state: Option<bool>) -> (Self, SimulatedHandle) {
+        let (inputs_tx, inputs) = mpsc::unbounded_channel();
+        (
+            Self {
+                index,
+                initial_state,
+                inputs,
+            },
+            SimulatedHandle { inputs: inputs_tx },
+        )
+    }
+}
+
+impl SimulatedHandle {
+    /// Report a motion event.
+    pub fn motion(&self, event: MotionEvent) {
+        let _ = self.inputs.send(SimulatedInput::Motion(event));
+    }
+
+    /// Finish warming up with the given state.
+    pub fn ready(&self, motion_detected: bool) {
+        let _ = self.inputs.send(SimulatedInput::Ready(motion_detected));
+    }
+
+    /// Send a health tick stamped `at`.
+    pub fn tick(&self, at: Instant) {
+        let _ = self.inputs.send(SimulatedInput::Tick(at));
+    }
+}
+
+impl MotionSource for SimulatedSource {
+    fn initial_state(&self) -> Option<bool> {
+        self.initial_state
+    }
+
+    fn run(
+        mut self: Box<Self>,
+        tx: mpsc::Sender<SensorEvent>,
+        shutdown: CancellationToken,
+        health_tx: watch::Sender<Instant>,
+        _initial_state: Option<bool>,
+    ) -> MotionSourceFuture {
+        Box::pin(async move {
+            loop {
+                let input = tokio::select! {
+                    () = shutdown.cancelled() => {
+                        info!("Simulated sensor shutting down");
+                        break;
+                    }
+                    input = self.inputs.recv() => input,
+                };
+
+                let event = match input {
+                    Some(SimulatedInput::Motion(event)) => SensorEvent::Motion {
+                        sensor: self.index,
+                        event,
+                    },
+                    Some(SimulatedInput::Ready(motion_detected)) => SensorEvent::Ready {
+                        sensor: self.index,
+                        motion_detected,
+                    },
+                    Some(SimulatedInput::Tick(at)) => {
+                        if health_tx.send(at).is_err() {
+                            debug!("Simulated sensor health receiver dropped");
+                        }
+                        continue;
+                    }
+                    None => break,
+                };
+
+                if tx.send(event).await.is_err() {
+                    break;
+                }
+            }
+        })
+    }
+}
@@ -0,0 +1,185 @@
+//! Rolling motion statistics for Home Assistant dashboards.
+//!
+//! Tracks when motion last started, how many motion events happened today,
+//! and how long motion was active over the last hour. The daily count and
+//! last motion time are saved to a small JSON state file so they survive
+//! restarts; the hourly activity starts over.
+
+use chrono::{DateTime, Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::io;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use crate::error::StatsError;
+
+/// Window for the recent activity duration.
+const ACTIVITY_WINDOW: Duration = Duration::from_hours(1);
+
+/// Counters saved across restarts.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+struct SavedStats {
+    /// Local date the daily count belongs to
+    day: Option<NaiveDate>,
+    motion_events_today: u64,
+    last_motion: Option<DateTime<Local>>,
+}
+
+/// Statistics as published to Home Assistant.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StatsSnapshot {
+    /// When motion last started
+    pub last_motion: Option<DateTime<Local>>,
+    /// Motion events since local midnight
+    pub motion_events_today: u64,
+    /// Minutes with motion in the last hour, to a tenth of a minute
+    pub active_minutes_last_hour: f64,
+}
+
+/// Rolling statistics for the combined motion state.
+#[derive(Debug, Default)]
+pub struct MotionStats {
+    saved: SavedStats,
+    active_since: Option<Instant>,
+    /// Finished motion periods that may overlap the activity window
+    periods: VecDeque<(Instant, Instant)>,
+}
+
+impl MotionStats {
+    /// Load saved counters, starting from zero if the file does not exist.
+    pub fn load(path: &Path) -> Result<Self, StatsError> {
+        let saved = match std::fs::read_to_string(path) {
+            Ok(contents) => serde_json::from_str(&contents)?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => SavedStats::default(),
+            Err(e) => return Err(e.into()),
+        };
+
+        Ok(Self {
+            saved,
+            ..Self::default()
+        })
+    }
+
+    /// Save the counters, replacing the file atomically.
+    pub fn save(&self, path: &Path) -> Result<(), StatsError> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, serde_json::to_vec(&self.saved)?)?;
+        std::fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    /// Record that motion started at wall time `wall`.
+    pub fn motion_started(&mut self, wall: DateTime<Local>, now: Instant) {
+        self.roll_over(wall.date_naive());
+        self.saved.motion_events_today = self.saved.motion_events_today.saturating_add(1);
+        self.saved.last_motion = Some(wall);
+        self.active_since.get_or_insert(now);
+    }
+
+    /// Record that motion stopped.
+    pub fn motion_stopped(&mut self, now: Instant) {
+        if let Some(started) = self.active_since.take() {
+            self.periods.push_back((started, now));
+        }
+    }
+
+    /// Current statistics, resetting the daily count after midnight.
+    pub fn snapshot(&mut self, wall: DateTime<Local>, now: Instant) -> StatsSnapshot {
+        self.roll_over(wall.date_naive());
+
+        let window_start = now.checked_sub(ACTIVITY_WINDOW);
+        while self
+            .periods
+            .front()
+            .is_some_and(|(_, ended)| window_start.is_some_and(|start| *ended <= start))
+        {
+            self.periods.pop_front();
+        }
+
+        let clipped = |started: Instant, ended: Instant| {
+            let started = window_start.map_or(started, |start| started.max(start));
+            ended.saturating_duration_since(started)
+        };
+        let active: Duration = self
+            .periods
+            .iter()
+            .map(|(started, ended)| clipped(*started, *ended))
+            .chain(self.active_since.map(|started| clipped(started, now)))
+            .sum();
+
+        StatsSnapshot {
+            last_motion: self.saved.last_motion,
+            motion_events_today: self.saved.motion_events_today,
+            active_minutes_last_hour: (active.as_secs_f64() / 6.0).round() / 10.0,
+        }
+    }
+
+    fn roll_over(&mut self, today: NaiveDate) {
+        if self.saved.day != Some(today) {
+            self.saved.day = Some(today);
+            self.saved.motion_events_today = 0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn wall(day: u32, hour: u32) -> DateTime<Local> {
+        Local
+            .with_ymd_and_hms(2026, 3, day, hour, 0, 0)
+            .single()
+            .expect("test time should be unambiguous")
+    }
+
+    #[test]
+    fn counts_events_and_recent_activity() {
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+        let mut stats = MotionStats::default();
+
+        stats.motion_started(wall(2, 9), at(0));
+        stats.motion_stopped(at(600));
+        stats.motion_started(wall(2, 10), at(3000));
+
+        let snapshot = stats.snapshot(wall(2, 10), at(3900));
+        assert_eq!(snapshot.motion_events_today, 2);
+        assert_eq!(snapshot.last_motion, Some(wall(2, 10)));
+        // 5 of the first 10 minutes are within the hour, plus 15 ongoing
+        assert!((snapshot.active_minutes_last_hour - 20.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn daily_count_resets_at_midnight() {
+        let now = Instant::now();
+        let mut stats = MotionStats::default();
+
+        stats.motion_started(wall(2, 23), now);
+        assert_eq!(stats.snapshot(wall(2, 23), now).motion_events_today, 1);
+        let snapshot = stats.snapshot(wall(3, 0), now);
+        assert_eq!(snapshot.motion_events_today, 0);
+        assert_eq!(snapshot.last_motion, Some(wall(2, 23)));
+    }
+
+    #[test]
+    fn counters_survive_restart() {
+        let dir = tempfile::tempdir().expect("temp dir should be created");
+        let path = dir.path().join("state/stats.json");
+
+        let mut stats = MotionStats::load(&path).expect("missing file should load");
+        stats.motion_started(wall(2, 9), Instant::now());
+        stats.save(&path).expect("stats should save");
+
+        let mut restored = MotionStats::load(&path).expect("saved file should load");
+        let snapshot = restored.snapshot(wall(2, 12), Instant::now());
+        assert_eq!(snapshot.motion_events_today, 1);
+        assert_eq!(snapshot.last_motion, Some(wall(2, 9)));
+        assert!(snapshot.active_minutes_last_hour.abs() < f64::EPSILON);
+    }
+}
@@ -105,11 +105,43 @@ fn calculate_sun_time(latitude: f64, longitude: f64, day_of_year: i64, is_sunris
     time_minutes / 60.0
 }
 
+/// Manual night mode override (set from Home Assistant).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NightModeOverride {
+    /// Follow the configured schedule
+    #[default]
+    Auto,
+    /// Night mode regardless of the schedule
+    On,
+    /// Day mode regardless of the schedule
+    Off,
+}
+
+impl NightModeOverride {
+    /// Every override, in the order Home Assistant lists them.
+    pub const ALL: [Self; 3] = [Self::Auto, Self::On, Self::Off];
+
+    /// Payload used on the MQTT state and command topics.
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Auto => "auto",
+            Self::On => "on",
+            Self::Off => "off",
+        }
+    }
+
+    /// Parse an MQTT payload.
+    pub fn parse(payload: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|mode| mode.as_str() == payload)
+    }
+}
+
 /// Night mode manager.
 pub struct NightModeManager {
     config: NightModeConfig,
     location: Option<LocationConfig>,
     cached_sun_times: Option<(chrono::NaiveDate, SunTimes)>,
+    override_mode: NightModeOverride,
 }
 
 impl NightModeManager {
@@ -119,11 +151,29 @@ impl NightModeManager {
             config: config.clone(),
             location: location.cloned(),
             cached_sun_times: None,
+            override_mode: NightModeOverride::Auto,
         }
     }
 
+    /// Get the manual override.
+    pub const fn override_mode(&self) -> NightModeOverride {
+        self.override_mode
+    }
+
+    /// Force night mode on or off, or follow the schedule again.
+    pub fn set_override(&mut self, mode: NightModeOverride) {
+        info!(mode = mode.as_str(), "Night mode override set");
+        self.override_mode = mode;
+    }
+
     /// Check if we're currently in night mode.
     pub fn is_night_mode(&mut self) -> bool {
+        match self.override_mode {
+            NightModeOverride::On => return true,
+            NightModeOverride::Off => return false,
+            NightModeOverride::Auto => {}
+        }
+
         if !self.config.enabled {
             return false;
         }
@@ -279,6 +329,25 @@ mod tests {
         assert!(!manager.is_night_from_hours(5)); // 5 AM (end)
     }
 
+    #[test]
+    fn test_override_replaces_schedule() {
+        let config = NightModeConfig {
+            enabled: false,
+            ..Default::default()
+        };
+
+        let mut manager = NightModeManager::new(&config, None);
+        assert!(!manager.is_night_mode());
+
+        manager.set_override(NightModeOverride::On);
+        assert!(manager.is_night_mode());
+
+        manager.set_override(NightModeOverride::Auto);
+        assert!(!manager.is_night_mode());
+        assert_eq!(NightModeOverride::parse("off"), Some(NightModeOverride::Off));
+        assert_eq!(NightModeOverride::parse("Night"), None);
+    }
+
     #[test]
     fn test_sun_calculation() {
         // Test for a known location (New York City area, longitude -74)
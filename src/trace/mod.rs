@@ -0,0 +1,256 @@
+//! Motion trace recording for replaying field reports.
+//!
+//! When `logging.trace_file` is set, raw sensor transitions, motion events,
+//! and screen/MQTT actions are appended to a JSONL file, one [`TraceRecord`]
+//! per line. `mrpir replay` feeds a trace back through the motion logic.
+
+pub mod replay;
+
+use chrono::{SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::sync::mpsc;
+use tracing::{info, warn};
+
+use crate::sensor::SensorFault;
+
+/// One line of a trace file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TraceRecord {
+    /// Milliseconds since recording started
+    pub t_ms: u64,
+    /// What happened
+    #[serde(flatten)]
+    pub event: TraceEvent,
+}
+
+/// Something worth recording.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TraceEvent {
+    /// Recording started
+    Start { wall_time: String, version: String },
+    /// Raw sensor level changed (before the glitch filter)
+    Raw { sensor: usize, active: bool },
+    /// One sensor's motion state changed
+    SensorMotion { sensor: usize, detected: bool },
+    /// The combined motion state handled by the app
+    Motion { detected: bool },
+    /// The occupancy state changed
+    Occupancy { occupied: bool },
+    /// Touchscreen or keyboard activity started or ended
+    Touch { active: bool },
+    /// A remote (MQTT) sensor's motion state changed
+    Remote { name: String, active: bool },
+    /// A sensor fault was raised or cleared
+    Fault {
+        sensor: usize,
+        fault: SensorFault,
+        active: bool,
+    },
+    /// A screen action was taken
+    Screen { action: ScreenAction },
+    /// A motion state was published to MQTT
+    Mqtt {
+        entity: String,
+        state: bool,
+        ok: bool,
+    },
+}
+
+/// Screen actions taken in response to motion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScreenAction {
+    /// Brightened on motion
+    Bright,
+    /// Dimmed after the motion timeout
+    Dim,
+    /// Turned off in night mode
+    Off,
+}
+
+impl std::fmt::Display for TraceEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let on_off = |state: bool| if state { "ON" } else { "OFF" };
+        match self {
+            Self::Start { wall_time, version } => {
+                write!(f, "recording started {wall_time} (mrpir {version})")
+            }
+            Self::Raw { sensor, active } => write!(f, "sensor {sensor} raw {}", on_off(*active)),
+            Self::SensorMotion { sensor, detected } => {
+                write!(f, "sensor {sensor} motion {}", on_off(*detected))
+            }
+            Self::Motion { detected } => write!(f, "motion {}", on_off(*detected)),
+            Self::Occupancy { occupied } => write!(f, "occupancy {}", on_off(*occupied)),
+            Self::Touch { active } => write!(f, "touch {}", on_off(*active)),
+            Self::Remote { name, active } => write!(f, "remote {name} {}", on_off(*active)),
+            Self::Fault {
+                sensor,
+                fault,
+                active,
+            } => write!(
+                f,
+                "sensor {sensor} fault {} {}",
+                fault.as_str(),
+                if *active { "raised" } else { "cleared" }
+            ),
+            Self::Screen { action } => write!(f, "screen {action:?}"),
+            Self::Mqtt { entity, state, ok } => {
+                write!(f, "mqtt {entity} {}", on_off(*state))?;
+                if !ok {
+                    f.write_str(" (failed)")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Sink {
+    tx: mpsc::UnboundedSender<TraceRecord>,
+    start: Instant,
+}
+
+/// Cheaply cloneable handle for recording trace events.
+///
+/// The default recorder is disabled and drops everything.
+#[derive(Debug, Clone, Default)]
+pub struct TraceRecorder {
+    sink: Option<Arc<Sink>>,
+}
+
+impl TraceRecorder {
+    /// Create a recorder that sends records to a channel, timed from `start`.
+    pub fn channel(start: Instant) -> (Self, mpsc::UnboundedReceiver<TraceRecord>) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        (
+            Self {
+                sink: Some(Arc::new(Sink { tx, start })),
+            },
+            rx,
+        )
+    }
+
+    /// Append records to a JSONL file from a background writer thread.
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let (recorder, mut rx) = Self::channel(Instant::now());
+
+        let writer_path = path.to_path_buf();
+        std::thread::Builder::new()
+            .name("mrpir-trace".to_string())
+            .spawn(move || {
+                let mut writer = BufWriter::new(file);
+                while let Some(record) = rx.blocking_recv() {
+                    let result = serde_json::to_writer(&mut writer, &record)
+                        .map_err(io::Error::from)
+                        .and_then(|()| writer.write_all(b"\n"))
+                        .and_then(|()| writer.flush());
+                    if let Err(e) = result {
+                        warn!(path = %writer_path.display(), error = %e, "Trace write failed; recording stopped");
+                        return;
+                    }
+                }
+            })?;
+
+        recorder.record(TraceEvent::Start {
+            wall_time: Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+        });
+        info!(path = %path.display(), "Recording motion trace");
+        Ok(recorder)
+    }
+
+    /// Whether records are being kept.
+    #[cfg(test)]
+    pub const fn is_enabled(&self) -> bool {
+        self.sink.is_some()
+    }
+
+    /// Record an event that happened now.
+    pub fn record(&self, event: TraceEvent) {
+        self.record_at(Instant::now(), event);
+    }
+
+    /// Record an event that happened at `at`.
+    pub fn record_at(&self, at: Instant, event: TraceEvent) {
+        let Some(sink) = &self.sink else {
+            return;
+        };
+
+        let elapsed = at.saturating_duration_since(sink.start);
+        let record = TraceRecord {
+            t_ms: u64::try_from(elapsed.as_millis()).unwrap_or(u64::MAX),
+            event,
+        };
+        // The writer only goes away after a write error, which it has logged
+        let _ = sink.tx.send(record);
+    }
+}
+
+/// Parse a JSONL trace, skipping blank lines.
+pub fn parse_trace(contents: &str) -> Result<Vec<TraceRecord>, serde_json::Error> {
+    contents
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(serde_json::from_str)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn records_round_trip_as_jsonl() {
+        let start = Instant::now();
+        let (recorder, mut rx) = TraceRecorder::channel(start);
+
+        recorder.record_at(
+            start + Duration::from_millis(1500),
+            TraceEvent::Raw {
+                sensor: 0,
+                active: true,
+            },
+        );
+        recorder.record_at(
+            start + Duration::from_secs(2),
+            TraceEvent::Screen {
+                action: ScreenAction::Bright,
+            },
+        );
+
+        let mut lines = String::new();
+        while let Ok(record) = rx.try_recv() {
+            lines.push_str(&serde_json::to_string(&record).expect("record should serialize"));
+            lines.push('\n');
+        }
+
+        assert!(lines.starts_with(r#"{"t_ms":1500,"kind":"raw","sensor":0,"active":true}"#));
+        let records = parse_trace(&lines).expect("trace should parse");
+        assert_eq!(records.len(), 2);
+        assert_eq!(
+            records[1],
+            TraceRecord {
+                t_ms: 2000,
+                event: TraceEvent::Screen {
+                    action: ScreenAction::Bright
+                },
+            }
+        );
+    }
+
+    #[test]
+    fn disabled_recorder_drops_events() {
+        let recorder = TraceRecorder::default();
+        assert!(!recorder.is_enabled());
+        recorder.record(TraceEvent::Motion { detected: true });
+    }
+}
@@ -0,0 +1,338 @@
+//! Replay a recorded trace through the motion logic under a virtual clock.
+//!
+//! Raw sensor transitions from the trace are fed through the glitch filter,
+//! the no-motion tracker and [`App`], with timers fired at their virtual
+//! deadlines instead of waiting for them. Screen control and MQTT are
+//! disconnected; their actions are collected as trace records instead.
+
+use anyhow::{anyhow, Context, Result};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use super::{parse_trace, ScreenAction, TraceEvent, TraceRecord, TraceRecorder};
+use crate::app::App;
+use crate::config::{Config, ScreenMethod};
+use crate::sensor::{MotionLogic, SensorEvent};
+
+const USAGE: &str = "usage: mrpir replay <trace.jsonl> [--no-motion-delay <secs>]";
+
+/// What to replay and which settings to try instead of the configured ones.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplayOptions {
+    pub trace_file: PathBuf,
+    pub no_motion_delay_secs: Option<u64>,
+}
+
+impl ReplayOptions {
+    /// Parse the arguments following `replay`.
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Result<Self> {
+        let mut trace_file = None;
+        let mut no_motion_delay_secs = None;
+
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let target = match arg.as_str() {
+                "--no-motion-delay" => &mut no_motion_delay_secs,
+                _ if arg.starts_with('-') || trace_file.is_some() => {
+                    return Err(anyhow!("unexpected argument {arg:?}\n{USAGE}"));
+                }
+                _ => {
+                    trace_file = Some(PathBuf::from(arg));
+                    continue;
+                }
+            };
+
+            let value = args
+                .next()
+                .ok_or_else(|| anyhow!("{arg} needs a value in seconds\n{USAGE}"))?;
+            *target = Some(
+                value
+                    .parse()
+                    .with_context(|| format!("invalid {arg} value {value:?}"))?,
+            );
+        }
+
+        Ok(Self {
+            trace_file: trace_file.ok_or_else(|| anyhow!("missing trace file\n{USAGE}"))?,
+            no_motion_delay_secs,
+        })
+    }
+
+    /// Apply the overrides and detach the config from real outputs.
+    pub fn apply(&self, mut config: Config) -> Config {
+        if let Some(delay) = self.no_motion_delay_secs {
+            for sensor in std::iter::once(&mut config.sensor).chain(&mut config.sensors) {
+                sensor.no_motion_delay_secs = delay;
+            }
+        }
+
+        config.mqtt.enabled = false;
+        config.logging.trace_file = None;
+        // Track screen actions without touching the display
+        config.screen.enabled = true;
+        config.screen.method = ScreenMethod::None;
+        config.screen.transition_time_secs = 0;
+        // Night mode follows the wall clock at replay time, not recording time
+        config.night_mode.enabled = false;
+        config
+    }
+}
+
+/// Replay the raw transitions in `records`, returning what the daemon would
+/// have recorded with `config`.
+pub async fn replay(config: &Config, records: &[TraceRecord]) -> Result<Vec<TraceRecord>> {
+    let start = Instant::now();
+    let (trace, mut replayed) = TraceRecorder::channel(start);
+    let mut app = App::new(config.clone());
+    app.set_trace(trace);
+    app.set_virtual_now(start);
+
+    let sensor_configs = config.sensors();
+    let mut sensors: Vec<MotionLogic> = sensor_configs
+        .iter()
+        .map(|sensor| MotionLogic::new(sensor, false))
+        .collect();
+    app.initialize_motion_state(&vec![Some(false); sensors.len()])
+        .await;
+
+    let mut raw: Vec<_> = records
+        .iter()
+        .filter_map(|record| match record.event {
+            TraceEvent::Raw { sensor, active } => Some((record.t_ms, sensor, active)),
+            _ => None,
+        })
+        .collect();
+    // Sensor threads record independently, so lines can be slightly out of order
+    raw.sort_by_key(|(t_ms, ..)| *t_ms);
+
+    for (t_ms, sensor, active) in raw {
+        let at = start + Duration::from_millis(t_ms);
+        fire_timers(&mut app, &mut sensors, Some(at)).await?;
+
+        let Some(logic) = sensors.get_mut(sensor) else {
+            return Err(anyhow!(
+                "trace has sensor {sensor} but only {} are configured",
+                sensor_configs.len()
+            ));
+        };
+        app.set_virtual_now(at);
+        feed(&mut app, logic, sensor, active, at).await?;
+    }
+    fire_timers(&mut app, &mut sensors, None).await?;
+
+    let mut records = Vec::new();
+    while let Ok(record) = replayed.try_recv() {
+        records.push(record);
+    }
+    Ok(records)
+}
+
+/// Fire sensor and app timers in deadline order, up to `until` if given.
+async fn fire_timers(
+    app: &mut App,
+    sensors: &mut [MotionLogic],
+    until: Option<Instant>,
+) -> Result<()> {
+    loop {
+        let sensor_deadline = sensors
+            .iter()
+            .enumerate()
+            .filter_map(|(index, logic)| logic.next_deadline().map(|at| (at, index)))
+            .min();
+        let app_deadline = app.next_deadline();
+
+        let next = match (sensor_deadline, app_deadline) {
+            (Some((sensor_at, _)), Some(app_at)) => sensor_at.min(app_at),
+            (Some((sensor_at, _)), None) => sensor_at,
+            (None, Some(app_at)) => app_at,
+            (None, None) => return Ok(()),
+        };
+        if until.is_some_and(|until| next > until) {
+            return Ok(());
+        }
+
+        app.set_virtual_now(next);
+        match sensor_deadline {
+            Some((at, sensor)) if at == next => {
+                let logic = &mut sensors[sensor];
+                feed(app, logic, sensor, logic.raw(), at).await?;
+            }
+            _ => app.handle_deadline().await,
+        }
+    }
+}
+
+/// Feed a raw level to one sensor's logic and pass its events to the app.
+async fn feed(
+    app: &mut App,
+    logic: &mut MotionLogic,
+    sensor: usize,
+    raw: bool,
+    at: Instant,
+) -> Result<()> {
+    if let Some(event) = logic.update(raw, at) {
+        app.handle_sensor_event(SensorEvent::Motion { sensor, event })
+            .await?;
+    }
+    if let Some(change) = logic.take_fault_change() {
+        app.handle_sensor_event(SensorEvent::Fault { sensor, change })
+            .await?;
+    }
+    Ok(())
+}
+
+/// Run `mrpir replay` and print the replayed timeline.
+pub async fn run_cli(args: impl IntoIterator<Item = String>) -> Result<()> {
+    let options = ReplayOptions::from_args(args)?;
+    let config = options.apply(Config::load().context("Failed to load configuration")?);
+
+    let contents = std::fs::read_to_string(&options.trace_file)
+        .with_context(|| format!("Failed to read {}", options.trace_file.display()))?;
+    let records = parse_trace(&contents)
+        .with_context(|| format!("Failed to parse {}", options.trace_file.display()))?;
+    let replayed = replay(&config, &records).await?;
+
+    let delays: Vec<String> = config
+        .sensors()
+        .iter()
+        .map(|sensor| sensor.no_motion_delay_secs.to_string())
+        .collect();
+    println!(
+        "Replaying {} with no_motion_delay_secs={}",
+        options.trace_file.display(),
+        delays.join(",")
+    );
+    for record in &replayed {
+        println!("{:>12}  {}", format_offset(record.t_ms), record.event);
+    }
+
+    let screen_actions = |records: &[TraceRecord], action: ScreenAction| {
+        records
+            .iter()
+            .filter(|record| record.event == TraceEvent::Screen { action })
+            .count()
+    };
+    println!();
+    println!("              recorded  replayed");
+    for (label, action) in [
+        ("screen bright", ScreenAction::Bright),
+        ("screen dim", ScreenAction::Dim),
+        ("screen off", ScreenAction::Off),
+    ] {
+        println!(
+            "{label:<14}{:>8}  {:>8}",
+            screen_actions(&records, action),
+            screen_actions(&replayed, action)
+        );
+    }
+
+    Ok(())
+}
+
+/// Format a trace offset as `+H:MM:SS.mmm`.
+fn format_offset(t_ms: u64) -> String {
+    let secs = t_ms / 1000;
+    format!(
+        "+{}:{:02}:{:02}.{:03}",
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60,
+        t_ms % 1000
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn raw(t_ms: u64, active: bool) -> TraceRecord {
+        TraceRecord {
+            t_ms,
+            event: TraceEvent::Raw { sensor: 0, active },
+        }
+    }
+
+    fn replay_config(no_motion_delay_secs: u64) -> Config {
+        ReplayOptions {
+            trace_file: PathBuf::from("trace.jsonl"),
+            no_motion_delay_secs: Some(no_motion_delay_secs),
+        }
+        .apply(Config::default())
+    }
+
+    fn screen_record(t_ms: u64, action: ScreenAction) -> TraceRecord {
+        TraceRecord {
+            t_ms,
+            event: TraceEvent::Screen { action },
+        }
+    }
+
+    fn screen(records: &[TraceRecord]) -> Vec<(u64, ScreenAction)> {
+        records
+            .iter()
+            .filter_map(|record| match record.event {
+                TraceEvent::Screen { action } => Some((record.t_ms, action)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Someone sits still for 40s between two bursts of motion, recorded
+    /// with a 5s no-motion delay.
+    fn desk_trace() -> Vec<TraceRecord> {
+        vec![
+            raw(0, false),
+            raw(1_000, true),
+            screen_record(1_000, ScreenAction::Bright),
+            raw(3_000, false),
+            screen_record(8_000, ScreenAction::Dim),
+            raw(43_000, true),
+            screen_record(43_000, ScreenAction::Bright),
+            raw(44_000, false),
+            screen_record(49_000, ScreenAction::Dim),
+        ]
+    }
+
+    #[tokio::test]
+    async fn replay_reproduces_the_recorded_screen_actions() {
+        let recorded = desk_trace();
+        let replayed = replay(&replay_config(5), &recorded)
+            .await
+            .expect("replay should succeed");
+
+        assert_eq!(screen(&replayed), screen(&recorded));
+    }
+
+    #[tokio::test]
+    async fn longer_no_motion_delay_keeps_screen_on() {
+        let replayed = replay(&replay_config(45), &desk_trace())
+            .await
+            .expect("replay should succeed");
+
+        // The second burst arrives before motion clears at 48s
+        assert_eq!(
+            screen(&replayed),
+            vec![(1_000, ScreenAction::Bright), (89_000, ScreenAction::Dim)]
+        );
+    }
+
+    #[test]
+    fn parses_replay_arguments() {
+        let args = ["trace.jsonl", "--no-motion-delay", "120"].map(String::from);
+        let options = ReplayOptions::from_args(args).expect("arguments should parse");
+        assert_eq!(options.trace_file, PathBuf::from("trace.jsonl"));
+        assert_eq!(options.no_motion_delay_secs, Some(120));
+
+        assert!(ReplayOptions::from_args(["--no-motion-delay".to_string()]).is_err());
+        assert!(ReplayOptions::from_args(
+            ["trace.jsonl", "--motion-timeout", "120"].map(String::from)
+        )
+        .is_err());
+        assert!(ReplayOptions::from_args(Vec::new()).is_err());
+    }
+
+    #[test]
+    fn formats_offsets() {
+        assert_eq!(format_offset(3_723_045), "+1:02:03.045");
+    }
+}